@@ -0,0 +1,33 @@
+//! Compare the cost of an exposure of a 32-byte key in `Shielded` memory and
+//! in a `ShieldedSmall`. Run with `cargo run --release --example small`.
+
+use std::time::{Duration, Instant};
+
+use shielded::{Shielded, ShieldedSmall};
+
+const ROUNDS: u32 = 10_000;
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        f();
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    let mut shielded = Shielded::new(vec![7; 32]);
+    let mut small = ShieldedSmall::new(&mut [7; 32]);
+    let mut small_prekey = Shielded::builder()
+        .prekey_len(1024)
+        .build(vec![7; 32])
+        .unwrap();
+
+    let shielded = time(|| assert_eq!(7, shielded.unshield().as_ref()[0]));
+    let prekey = time(|| assert_eq!(7, small_prekey.unshield().as_ref()[0]));
+    let small = time(|| assert_eq!(7, small.unshield()[0]));
+
+    println!("Shielded:                  {:>10?}", shielded);
+    println!("Shielded, 1 KiB prekey:    {:>10?}", prekey);
+    println!("ShieldedSmall:             {:>10?}", small);
+}
@@ -0,0 +1,52 @@
+/*
+ * C API of the shielded crate, built with the `ffi` feature. See the
+ * documentation of the `shielded::ffi` module.
+ */
+
+#ifndef SHIELDED_H
+#define SHIELDED_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SHIELDED_OK 0
+#define SHIELDED_ERR_TAMPER (-1)
+#define SHIELDED_ERR_RNG (-2)
+#define SHIELDED_ERR_CRYPTO (-3)
+#define SHIELDED_ERR_LOCK (-4)
+#define SHIELDED_ERR_BACKEND (-5)
+#define SHIELDED_ERR_ENCODING (-6)
+#define SHIELDED_ERR_EXPIRED (-7)
+#define SHIELDED_ERR_POLICY_VIOLATION (-8)
+#define SHIELDED_ERR_UNKNOWN_KEY (-9)
+#define SHIELDED_ERR_DENIED (-10)
+#define SHIELDED_ERR_DISCONNECTED (-11)
+#define SHIELDED_ERR_NULL (-12)
+#define SHIELDED_ERR_PANIC (-13)
+#define SHIELDED_ERR_UNPROTECTED (-14)
+#define SHIELDED_ERR_POISONED (-15)
+#define SHIELDED_ERR_UNSUPPORTED (-16)
+
+/* Opaque handle to shielded memory. */
+typedef struct ShieldedHandle shielded_t;
+
+typedef void (*shielded_expose_fn)(void *ctx, const uint8_t *data, size_t len);
+typedef void (*shielded_expose_mut_fn)(void *ctx, uint8_t *data, size_t len);
+
+int shielded_new(const uint8_t *data, size_t len, shielded_t **out);
+void shielded_free(shielded_t *handle);
+size_t shielded_len(const shielded_t *handle);
+int shielded_expose(shielded_t *handle, shielded_expose_fn f, void *ctx);
+int shielded_expose_mut(shielded_t *handle, shielded_expose_mut_fn f, void *ctx);
+int shielded_verify(shielded_t *handle);
+const char *shielded_strerror(int code);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SHIELDED_H */
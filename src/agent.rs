@@ -0,0 +1,265 @@
+//! An in-process agent owning shielded keys, in the style of ssh-agent.
+//!
+//! An [`Agent`](struct.Agent.html) holds named keys in
+//! [`Shielded`](../struct.Shielded.html) memory and answers
+//! [requests](enum.Request.html) to use them, so the rest of a service never
+//! sees a key. Each key can be limited to a number of uses, and requests can
+//! be confirmed by a callback before they are answered.
+//!
+//! The agent can be called directly with [`handle`](struct.Agent.html#method.handle),
+//! or run on a thread of its own with [`spawn`](struct.Agent.html#method.spawn)
+//! and called over a channel from any number of threads:
+//!
+//! ```
+//! use shielded::agent::{Agent, KeyConstraints, Request, Response};
+//! use shielded::Shielded;
+//!
+//! let mut agent = Agent::new();
+//! agent.add_key("mac", Shielded::new(b"key".to_vec()), KeyConstraints::new().max_uses(2));
+//! let handle = agent.spawn();
+//!
+//! let request = Request::HmacSha256 { key: "mac".into(), message: b"message".to_vec() };
+//! let mac = match handle.request(request).unwrap() {
+//!     Response::Mac(mac) => mac,
+//!     _ => unreachable!(),
+//! };
+//! assert_eq!(32, mac.len());
+//! ```
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::{ShieldError, Shielded};
+
+/// A request to an [`Agent`](struct.Agent.html) to use one of its keys.
+pub enum Request {
+    /// Sign `message` with Ed25519, using the 32-byte key as the seed.
+    /// Requires the `ed25519` feature.
+    #[cfg(feature = "ed25519")]
+    Sign {
+        /// Name of the key.
+        key: String,
+        /// The message to sign.
+        message: Vec<u8>,
+    },
+    /// Compute the HMAC-SHA-256 of `message`.
+    HmacSha256 {
+        /// Name of the key.
+        key: String,
+        /// The message to authenticate.
+        message: Vec<u8>,
+    },
+    /// Decrypt a key wrapped with
+    /// [`Shielded::wrap`](../struct.Shielded.html#method.wrap) under the key,
+    /// right into new shielded memory.
+    Decrypt {
+        /// Name of the key.
+        key: String,
+        /// The wrapped key.
+        ciphertext: Vec<u8>,
+    },
+}
+
+impl Request {
+    /// Name of the key the request uses.
+    pub fn key(&self) -> &str {
+        match self {
+            #[cfg(feature = "ed25519")]
+            Request::Sign { key, .. } => key,
+            Request::HmacSha256 { key, .. } => key,
+            Request::Decrypt { key, .. } => key,
+        }
+    }
+}
+
+/// The answer of an [`Agent`](struct.Agent.html) to a
+/// [`Request`](enum.Request.html).
+pub enum Response {
+    /// The signature of a [`Request::Sign`](enum.Request.html#variant.Sign).
+    #[cfg(feature = "ed25519")]
+    Signature([u8; 64]),
+    /// The MAC of a
+    /// [`Request::HmacSha256`](enum.Request.html#variant.HmacSha256).
+    Mac([u8; 32]),
+    /// The plaintext of a
+    /// [`Request::Decrypt`](enum.Request.html#variant.Decrypt).
+    Plaintext(Box<Shielded>),
+}
+
+/// Limits on the use of a key held by an [`Agent`](struct.Agent.html).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeyConstraints {
+    max_uses: Option<u64>,
+    confirm: bool,
+}
+
+impl KeyConstraints {
+    /// No limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove the key after it has been used `max_uses` times. Requests
+    /// after that fail with
+    /// [`ShieldError::UnknownKey`](../enum.ShieldError.html#variant.UnknownKey).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_uses` is zero.
+    pub fn max_uses(mut self, max_uses: u64) -> Self {
+        assert!(max_uses > 0, "max_uses must be positive");
+        self.max_uses = Some(max_uses);
+        self
+    }
+
+    /// Ask the [confirmation callback](struct.Agent.html#method.confirm_with)
+    /// before every use of the key, like `ssh-add -c`.
+    pub fn confirm(mut self, confirm: bool) -> Self {
+        self.confirm = confirm;
+        self
+    }
+}
+
+struct AgentKey {
+    shielded: Shielded,
+    constraints: KeyConstraints,
+    uses: u64,
+}
+
+type Confirm = Box<dyn FnMut(&Request) -> bool + Send>;
+
+/// Owns a set of named keys in shielded memory and answers
+/// [requests](enum.Request.html) to use them.
+#[derive(Default)]
+pub struct Agent {
+    keys: BTreeMap<String, AgentKey>,
+    confirm: Option<Confirm>,
+}
+
+impl Agent {
+    /// Create an agent without keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `shielded` as the key called `name`, limited by `constraints`.
+    /// A previous key of that name is removed and wiped.
+    pub fn add_key(&mut self, name: &str, shielded: Shielded, constraints: KeyConstraints) {
+        let key = AgentKey {
+            shielded,
+            constraints,
+            uses: 0,
+        };
+        let _ = self.keys.insert(String::from(name), key);
+    }
+
+    /// Remove the key called `name`, wiping its memory. Returns `true` if
+    /// there was such a key.
+    pub fn remove_key(&mut self, name: &str) -> bool {
+        self.keys.remove(name).is_some()
+    }
+
+    /// The names of the keys, in order.
+    pub fn key_names(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(String::as_str)
+    }
+
+    /// How many times the key called `name` has been used, or `None` if there
+    /// is no such key.
+    pub fn uses(&self, name: &str) -> Option<u64> {
+        self.keys.get(name).map(|key| key.uses)
+    }
+
+    /// Set the callback confirming requests for keys added with
+    /// [`KeyConstraints::confirm`](struct.KeyConstraints.html#method.confirm),
+    /// e.g. to prompt an operator. The request is answered only if it returns
+    /// `true`. Without a callback such requests are denied.
+    pub fn confirm_with<F>(&mut self, confirm: F)
+    where
+        F: FnMut(&Request) -> bool + Send + 'static,
+    {
+        self.confirm = Some(Box::new(confirm));
+    }
+
+    /// Answer `request`.
+    ///
+    /// Returns [`ShieldError::UnknownKey`](../enum.ShieldError.html#variant.UnknownKey)
+    /// if there is no such key, [`ShieldError::Denied`](../enum.ShieldError.html#variant.Denied)
+    /// if the request wasn't confirmed, and the error of the key if it can't
+    /// be used.
+    pub fn handle(&mut self, request: Request) -> Result<Response, ShieldError> {
+        let key = self
+            .keys
+            .get_mut(request.key())
+            .ok_or(ShieldError::UnknownKey)?;
+        if key.constraints.confirm && !self.confirm.as_mut().is_some_and(|f| f(&request)) {
+            return Err(ShieldError::Denied);
+        }
+
+        key.uses += 1;
+        let exhausted = key.constraints.max_uses.is_some_and(|max| key.uses >= max);
+        let response = answer(&mut key.shielded, &request);
+        if exhausted {
+            let _ = self.keys.remove(request.key());
+        }
+        response
+    }
+
+    /// Run the agent on a new thread, answering the requests sent with the
+    /// returned handle. The thread stops and the keys are wiped once every
+    /// clone of the handle has been dropped.
+    pub fn spawn(mut self) -> AgentHandle {
+        let (sender, receiver): (_, Receiver<Message>) = mpsc::channel();
+        let _ = thread::spawn(move || {
+            for (request, reply) in receiver {
+                let _ = reply.send(self.handle(request));
+            }
+        });
+        AgentHandle { sender }
+    }
+}
+
+fn answer(shielded: &mut Shielded, request: &Request) -> Result<Response, ShieldError> {
+    match request {
+        #[cfg(feature = "ed25519")]
+        Request::Sign { message, .. } => {
+            use ed25519_dalek::Signer;
+
+            shielded
+                .try_expose(|seed| {
+                    crate::signing::signing_key(seed).map(|key| key.sign(message).to_bytes())
+                })?
+                .map(Response::Signature)
+        }
+        Request::HmacSha256 { message, .. } => shielded.try_hmac_sha256(message).map(Response::Mac),
+        Request::Decrypt { ciphertext, .. } => shielded
+            .unwrap_into_shielded(ciphertext)
+            .map(|plaintext| Response::Plaintext(Box::new(plaintext))),
+    }
+}
+
+type Message = (Request, Sender<Result<Response, ShieldError>>);
+
+/// Sends requests to an [`Agent`](struct.Agent.html) running on its own
+/// thread. Cloned handles talk to the same agent.
+#[derive(Clone)]
+pub struct AgentHandle {
+    sender: Sender<Message>,
+}
+
+impl AgentHandle {
+    /// Send `request` to the agent and wait for the answer, see
+    /// [`Agent::handle`](struct.Agent.html#method.handle).
+    ///
+    /// Returns [`ShieldError::Disconnected`](../enum.ShieldError.html#variant.Disconnected)
+    /// if the agent has stopped, e.g. because the confirmation callback
+    /// panicked.
+    pub fn request(&self, request: Request) -> Result<Response, ShieldError> {
+        let (reply, answer) = mpsc::channel();
+        self.sender
+            .send((request, reply))
+            .map_err(|_| ShieldError::Disconnected)?;
+        answer.recv().map_err(|_| ShieldError::Disconnected)?
+    }
+}
@@ -0,0 +1,435 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::Deref;
+
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::crypto::{Crypto, CryptoBackend, KEY_LEN, MAX_NONCE_LEN};
+use crate::entropy::EntropySource;
+use crate::mem::{BufOptions, SecretBuf};
+use crate::protection::{ProtectionRequirements, ProtectionStatus};
+use crate::{fill_random, Cipher, LockMode, ShieldError, ShieldedBuilder};
+
+/// Info string of the key of every sealing of an arena entry.
+const ARENA_KEY_INFO: &[u8] = b"shielded 1 arena entry key";
+
+/// Number of entries allocated at once.
+const SLOTS_PER_CHUNK: usize = 64;
+
+/// A handle to an entry of a [`ShieldedArena`](struct.ShieldedArena.html),
+/// returned by [`insert`](struct.ShieldedArena.html#method.insert).
+///
+/// Handles of removed entries are never valid again, even once their slot
+/// is reused. A slot is reused at most `u32::MAX` times and then retired, so
+/// its generation never wraps around to that of an old handle. A handle only
+/// refers to entries of the arena which returned it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ArenaHandle {
+    index: u32,
+    generation: u32,
+}
+
+impl ArenaHandle {
+    // The handle as the associated data of the entry, so entries can't be
+    // swapped between slots or outlive their handle.
+    fn aad(self) -> [u8; 8] {
+        let mut aad = [0; 8];
+        aad[..4].copy_from_slice(&self.index.to_be_bytes());
+        aad[4..].copy_from_slice(&self.generation.to_be_bytes());
+        aad
+    }
+}
+
+// The bookkeeping of a slot for one entry.
+struct Slot {
+    generation: u32,
+    // Length of the entry, `None` if the slot is free.
+    len: Option<usize>,
+    nonce: [u8; MAX_NONCE_LEN],
+}
+
+/// Many small secrets of at most [`MAX_LEN`](#associatedconstant.MAX_LEN)
+/// bytes, e.g. the tokens of thousands of tenants, kept encrypted under a
+/// single prekey.
+///
+/// Every [`Shielded`](struct.Shielded.html) or
+/// [`ShieldedSmall`](struct.ShieldedSmall.html) costs a prekey and
+/// allocations of its own. An arena instead keeps its entries in fixed-size
+/// slots of a few shared buffers, which are locked and excluded from core
+/// dumps like shielded memory, and grow by 64 slots at a time. Every entry is
+/// encrypted under a key derived from the shared prekey and a nonce of its
+/// own, and is bound to its handle. It is encrypted under a new nonce after
+/// every exposure, and wiped when removed.
+///
+/// Only some settings of a [`ShieldedBuilder`](struct.ShieldedBuilder.html)
+/// apply, see [`build_arena`](struct.ShieldedBuilder.html#method.build_arena).
+///
+/// ```
+/// use shielded::ShieldedArena;
+///
+/// let mut arena = ShieldedArena::new();
+/// let alice = arena.insert(&mut b"alice's token".to_owned()).unwrap();
+/// let bob = arena.insert(&mut b"bob's token".to_owned()).unwrap();
+///
+/// let len = arena.expose(alice, |token| token.len());
+/// assert_eq!(Some(13), len);
+///
+/// assert!(arena.remove(bob));
+/// assert_eq!(None, arena.expose(bob, |token| token.len()));
+/// ```
+pub struct ShieldedArena {
+    prekey: SecretBuf,
+    // The slots of the entries, `SLOTS_PER_CHUNK` to a buffer, each with room
+    // for the longest entry and its tag.
+    chunks: Vec<SecretBuf>,
+    slots: Vec<Slot>,
+    // Indexes of the free slots, the lowest last.
+    free: Vec<u32>,
+    len: usize,
+    cipher: Cipher,
+    context: Vec<u8>,
+    entropy: Option<Arc<dyn EntropySource>>,
+    lock: LockMode,
+    protection: ProtectionRequirements,
+    options: BufOptions,
+}
+
+impl ShieldedArena {
+    /// The longest secret an entry can hold.
+    pub const MAX_LEN: usize = 64;
+
+    /// Create an empty arena with the default settings. See
+    /// [`ShieldedBuilder::build_arena`](struct.ShieldedBuilder.html#method.build_arena)
+    /// for other settings.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prekey can't be generated. See
+    /// [`try_new`](#method.try_new) for a fallible version.
+    pub fn new() -> Self {
+        Self::try_new().expect("shield new memory")
+    }
+
+    /// Create an empty arena like [`new`](#method.new), returning an error
+    /// if the prekey can't be generated.
+    pub fn try_new() -> Result<Self, ShieldError> {
+        ShieldedBuilder::new().build_arena()
+    }
+
+    pub(crate) fn with_builder(builder: &ShieldedBuilder) -> Result<Self, ShieldError> {
+        let prekey_len = builder.prekey_len * builder.cipher.keys();
+        let mut arena = Self {
+            prekey: SecretBuf::new(prekey_len, builder.prekey_options()),
+            chunks: Vec::new(),
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            cipher: builder.cipher,
+            context: builder.context.clone(),
+            entropy: builder.entropy.clone(),
+            lock: builder.lock,
+            protection: builder.protection,
+            options: builder.memory_options(),
+        };
+        arena.check(&arena.prekey)?;
+        fill_random(arena.entropy.as_deref(), &mut arena.prekey)?;
+        arena.prekey.include_in_wipe();
+        Ok(arena)
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the arena has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if `handle` refers to an entry.
+    pub fn contains(&self, handle: ArenaHandle) -> bool {
+        self.entry_len(handle).is_some()
+    }
+
+    /// Shield `secret` as a new entry, wiping `secret`, and return its
+    /// handle.
+    ///
+    /// Returns an error if the slots can't be grown or the entry can't be
+    /// shielded. No entry is added then.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `secret` is longer than
+    /// [`MAX_LEN`](#associatedconstant.MAX_LEN).
+    pub fn insert(&mut self, secret: &mut [u8]) -> Result<ArenaHandle, ShieldError> {
+        assert!(
+            secret.len() <= Self::MAX_LEN,
+            "an arena entry holds at most {} bytes",
+            Self::MAX_LEN
+        );
+        if self.free.is_empty() {
+            self.grow()?;
+        }
+        let index = self.free.pop().expect("free slot");
+        let handle = ArenaHandle {
+            index,
+            generation: self.slots[index as usize].generation,
+        };
+
+        self.chunk_mut(index).exclude_from_wipe();
+        self.slot_mut(index)[..secret.len()].copy_from_slice(secret);
+        secret.zeroize();
+        if let Err(e) = self.seal(handle, secret.len()) {
+            self.slot_mut(index).zeroize();
+            self.chunk_mut(index).include_in_wipe();
+            self.free.push(index);
+            return Err(e);
+        }
+        self.chunk_mut(index).include_in_wipe();
+        self.slots[index as usize].len = Some(secret.len());
+        self.len += 1;
+        Ok(handle)
+    }
+
+    /// Remove the entry of `handle`, wiping it. Returns `true` if there was
+    /// such an entry.
+    pub fn remove(&mut self, handle: ArenaHandle) -> bool {
+        if !self.contains(handle) {
+            return false;
+        }
+        self.chunk_mut(handle.index).exclude_from_wipe();
+        self.slot_mut(handle.index).zeroize();
+        self.chunk_mut(handle.index).include_in_wipe();
+        let slot = &mut self.slots[handle.index as usize];
+        slot.len = None;
+        slot.nonce.zeroize();
+        // Retire the slot rather than wrap its generation around to that of
+        // an old handle.
+        if let Some(generation) = slot.generation.checked_add(1) {
+            slot.generation = generation;
+            self.free.push(handle.index);
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Call `f` with the decrypted entry of `handle` and shield it again
+    /// under a new nonce once `f` returns or panics. Returns `None` if there
+    /// is no such entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the entry fails authentication. See
+    /// [`try_expose`](#method.try_expose) for a fallible version.
+    pub fn expose<R, F>(&mut self, handle: ArenaHandle, f: F) -> Option<R>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        self.try_expose(handle, f).expect("unshield memory")
+    }
+
+    /// Call `f` with the decrypted entry of `handle` like
+    /// [`expose`](#method.expose), returning an error if the entry fails
+    /// authentication.
+    pub fn try_expose<R, F>(&mut self, handle: ArenaHandle, f: F) -> Result<Option<R>, ShieldError>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        let len = match self.entry_len(handle) {
+            Some(len) => len,
+            None => return Ok(None),
+        };
+        self.chunk_mut(handle.index).exclude_from_wipe();
+        if let Err(e) = self.open(handle, len) {
+            self.chunk_mut(handle.index).include_in_wipe();
+            return Err(e);
+        }
+        let exposed = Exposed {
+            arena: self,
+            handle,
+            len,
+        };
+        Ok(Some(f(&exposed)))
+    }
+
+    fn entry_len(&self, handle: ArenaHandle) -> Option<usize> {
+        self.slots
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+            .and_then(|slot| slot.len)
+    }
+
+    // Allocate another chunk of free slots.
+    fn grow(&mut self) -> Result<(), ShieldError> {
+        let mut chunk = SecretBuf::new(SLOTS_PER_CHUNK * self.slot_len(), self.options);
+        self.check(&chunk)?;
+        chunk.include_in_wipe();
+        self.chunks.push(chunk);
+        let start = self.slots.len();
+        self.slots.extend((0..SLOTS_PER_CHUNK).map(|_| Slot {
+            generation: 0,
+            len: None,
+            nonce: [0; MAX_NONCE_LEN],
+        }));
+        let end = u32::try_from(self.slots.len()).expect("arena slots overflow");
+        self.free.extend((start as u32..end).rev());
+        Ok(())
+    }
+
+    fn check(&self, buf: &SecretBuf) -> Result<(), ShieldError> {
+        if self.lock == LockMode::Required && !buf.is_locked() {
+            return Err(ShieldError::Lock);
+        }
+        if !ProtectionStatus::of(buf).satisfies(&self.protection) {
+            return Err(ShieldError::Unprotected);
+        }
+        Ok(())
+    }
+
+    // Room for the longest entry and its tag.
+    fn slot_len(&self) -> usize {
+        Self::MAX_LEN + self.cipher.tag_len()
+    }
+
+    // The chunk of the slot `index`, to keep `wipe_all` away from it while the
+    // slot is worked on.
+    fn chunk_mut(&mut self, index: u32) -> &mut SecretBuf {
+        &mut self.chunks[index as usize / SLOTS_PER_CHUNK]
+    }
+
+    fn slot(&self, index: u32) -> &[u8] {
+        let index = index as usize;
+        let start = index % SLOTS_PER_CHUNK * self.slot_len();
+        &self.chunks[index / SLOTS_PER_CHUNK][start..start + self.slot_len()]
+    }
+
+    fn slot_mut(&mut self, index: u32) -> &mut [u8] {
+        let index = index as usize;
+        let slot_len = self.slot_len();
+        let start = index % SLOTS_PER_CHUNK * slot_len;
+        &mut self.chunks[index / SLOTS_PER_CHUNK][start..start + slot_len]
+    }
+
+    // Encrypt the `len` bytes of plaintext of the entry under a new nonce.
+    fn seal(&mut self, handle: ArenaHandle, len: usize) -> Result<(), ShieldError> {
+        let nonce_len = self.cipher.nonce_len();
+        let mut nonce = [0; MAX_NONCE_LEN];
+        fill_random(self.entropy.as_deref(), &mut nonce[..nonce_len])?;
+        let key = self.key(&nonce[..nonce_len])?;
+
+        let cipher = self.cipher;
+        let tag_len = cipher.tag_len();
+        let (payload, tag) = self.slot_mut(handle.index)[..len + tag_len].split_at_mut(len);
+        Crypto::seal(
+            cipher,
+            &key[..cipher.key_len()],
+            &nonce[..nonce_len],
+            &handle.aad(),
+            payload,
+            tag,
+        )?;
+        self.slots[handle.index as usize].nonce = nonce;
+        Ok(())
+    }
+
+    // Decrypt the entry in-place.
+    fn open(&mut self, handle: ArenaHandle, len: usize) -> Result<(), ShieldError> {
+        let nonce = self.slots[handle.index as usize].nonce;
+        let nonce = &nonce[..self.cipher.nonce_len()];
+        let key = self.key(nonce)?;
+
+        let cipher = self.cipher;
+        let tag_len = cipher.tag_len();
+        let sealed = &mut self.slot_mut(handle.index)[..len + tag_len];
+        let _ = Crypto::open(
+            cipher,
+            &key[..cipher.key_len()],
+            nonce,
+            &handle.aad(),
+            sealed,
+        )?;
+        Ok(())
+    }
+
+    // The key of one sealing, derived from the prekey and its nonce into an
+    // array which is wiped when dropped.
+    fn key(&self, nonce: &[u8]) -> Result<Zeroizing<[u8; 2 * KEY_LEN]>, ShieldError> {
+        let mut key = Zeroizing::new([0u8; 2 * KEY_LEN]);
+        let parts = self
+            .prekey
+            .chunks_exact(self.prekey.len() / self.cipher.keys());
+        for (part, key) in parts.zip(key[..self.cipher.key_len()].chunks_exact_mut(KEY_LEN)) {
+            Crypto::hkdf_sha512(part, &[ARENA_KEY_INFO, &self.context, nonce], key)?;
+        }
+        Ok(key)
+    }
+}
+
+impl Default for ShieldedArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ShieldedArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShieldedArena")
+            .field("len", &self.len)
+            .field("cipher", &self.cipher)
+            .finish_non_exhaustive()
+    }
+}
+
+// A decrypted entry, shielded again when dropped.
+struct Exposed<'a> {
+    arena: &'a mut ShieldedArena,
+    handle: ArenaHandle,
+    len: usize,
+}
+
+impl<'a> Deref for Exposed<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.arena.slot(self.handle.index)[..self.len]
+    }
+}
+
+impl<'a> Drop for Exposed<'a> {
+    fn drop(&mut self) {
+        if self.arena.seal(self.handle, self.len).is_err() {
+            // Nothing decrypts any more, and nothing is left in the clear.
+            self.arena.slot_mut(self.handle.index).zeroize();
+        }
+        self.arena.chunk_mut(self.handle.index).include_in_wipe();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arena_generation_limit() {
+        let mut arena = ShieldedArena::new();
+        let old = arena.insert(&mut b"old".to_owned()).unwrap();
+        assert!(arena.remove(old));
+
+        // The slot of `old` after it was reused all but once more.
+        arena.slots[old.index as usize].generation = u32::MAX;
+        let last = arena.insert(&mut b"last".to_owned()).unwrap();
+        assert_eq!(old.index, last.index);
+        assert!(arena.remove(last));
+
+        // The slot is retired instead of wrapping around to `old`.
+        let new = arena.insert(&mut b"new".to_owned()).unwrap();
+        assert_ne!(old, new);
+        assert!(!arena.contains(old));
+        assert!(!arena.contains(last));
+        assert_eq!(None, arena.expose(old, |secret| secret.len()));
+        assert_eq!(Some(b"new".to_vec()), arena.expose(new, <[u8]>::to_vec));
+    }
+}
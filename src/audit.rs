@@ -0,0 +1,68 @@
+//! Hooks reporting when shielded memory is shielded, unshielded and wiped.
+//!
+//! An [`Audit`](trait.Audit.html) set with
+//! [`ShieldedBuilder::audit`](../struct.ShieldedBuilder.html#method.audit) is
+//! called on every such event with the
+//! [label](../struct.ShieldedBuilder.html#method.label) of the memory, e.g. to
+//! log or count how often and when secrets are decrypted in a running
+//! service. The hooks never see the content.
+
+use core::fmt;
+
+/// Receives the events of shielded memory. Every method does nothing by
+/// default.
+///
+/// The hooks are called while the memory is being shielded or unshielded and
+/// should return quickly.
+pub trait Audit: fmt::Debug + Send + Sync {
+    /// The memory has been encrypted, on construction or after an exposure.
+    fn on_shield(&self, _event: &AuditEvent<'_>) {}
+
+    /// The content is about to be exposed by an unshield operation.
+    fn on_unshield(&self, _event: &AuditEvent<'_>) {}
+
+    /// The memory has been wiped, because it was dropped, expired or couldn't
+    /// be shielded again.
+    fn on_wipe(&self, _event: &AuditEvent<'_>) {}
+
+    /// An exposure outlived the duration it was allowed, and the memory has
+    /// been poisoned. See
+    /// [`Shielded::unshield_for`](../struct.Shielded.html#method.unshield_for).
+    fn on_violation(&self, _event: &AuditEvent<'_>) {}
+
+    /// The memory failed authentication, e.g. because it was corrupted or
+    /// tampered with, and has been poisoned: it is wiped and every later
+    /// operation fails with
+    /// [`ShieldError::Poisoned`](../enum.ShieldError.html#variant.Poisoned).
+    fn on_poison(&self, _event: &AuditEvent<'_>) {}
+}
+
+/// An event of shielded memory, passed to [`Audit`](trait.Audit.html).
+#[derive(Clone, Copy, Debug)]
+pub struct AuditEvent<'a> {
+    label: &'a str,
+    #[cfg(feature = "std")]
+    time: std::time::SystemTime,
+}
+
+impl<'a> AuditEvent<'a> {
+    pub(crate) fn new(label: &'a str) -> Self {
+        Self {
+            label,
+            #[cfg(feature = "std")]
+            time: std::time::SystemTime::now(),
+        }
+    }
+
+    /// The label of the memory, empty unless set with
+    /// [`ShieldedBuilder::label`](../struct.ShieldedBuilder.html#method.label).
+    pub fn label(&self) -> &'a str {
+        self.label
+    }
+
+    /// When the event happened.
+    #[cfg(feature = "std")]
+    pub fn time(&self) -> std::time::SystemTime {
+        self.time
+    }
+}
@@ -0,0 +1,159 @@
+//! Backends protecting the prekey while the memory is shielded.
+//!
+//! By default the prekey is kept in plain userspace memory, and the protection
+//! of the shielded memory comes solely from the size of the prekey. A
+//! [`Backend`](trait.Backend.html) adds an OS or hardware assisted layer on top
+//! of that by transforming the prekey at rest, after the memory has been
+//! shielded, and restoring it only for the duration of an unshield.
+//!
+//! A backend can also take over the encryption of the memory, so the prekey
+//! is never restored in the process, e.g. to keep it in an enclave. See
+//! [`Backend::encrypts`](trait.Backend.html#method.encrypts).
+
+use core::fmt;
+
+use crate::crypto::{Crypto, CryptoBackend};
+use crate::{new_key, with_authenticated, Cipher, ShieldError};
+
+#[cfg(all(windows, feature = "crypt-protect-memory"))]
+mod crypt_protect_memory;
+#[cfg(all(target_os = "macos", feature = "keychain"))]
+mod keychain;
+#[cfg(all(target_os = "linux", feature = "linux-keyring"))]
+mod linux_keyring;
+mod software_enclave;
+mod wrapped;
+
+#[cfg(all(windows, feature = "crypt-protect-memory"))]
+pub use crypt_protect_memory::CryptProtectMemory;
+#[cfg(all(target_os = "macos", feature = "keychain"))]
+pub use keychain::{Keychain, SecureEnclave};
+#[cfg(all(target_os = "linux", feature = "linux-keyring"))]
+pub use linux_keyring::LinuxKeyring;
+pub use software_enclave::SoftwareEnclave;
+pub use wrapped::{WrappedPrekey, WrappingKey, WRAPPING_KEY_LEN};
+
+/// Protection of the prekey at rest.
+///
+/// Both methods operate on the prekey in-place. `unprotect` must restore the
+/// exact bytes given to `protect`, otherwise unshielding fails with
+/// [`ShieldError::Tamper`](../enum.ShieldError.html#variant.Tamper).
+pub trait Backend: fmt::Debug + Send + Sync {
+    /// Protect a freshly generated prekey after the memory has been shielded
+    /// with it.
+    fn protect(&self, prekey: &mut [u8]) -> Result<(), ShieldError>;
+
+    /// Restore a prekey previously protected with `protect`.
+    fn unprotect(&self, prekey: &mut [u8]) -> Result<(), ShieldError>;
+
+    /// Release anything held for a prekey protected with `protect`, which is
+    /// about to be wiped without being restored, e.g. because the memory is
+    /// dropped. Does nothing by default.
+    fn discard(&self, _prekey: &mut [u8]) {}
+
+    /// Number of bytes the backend keeps next to every protected prekey, e.g.
+    /// a salt drawn for every `protect`. `protect`, `unprotect`, `discard`,
+    /// `seal` and `open` are then called with the prekey followed by that
+    /// many bytes, which are kept between the calls. Only the prekey has to
+    /// be restored. 0 by default.
+    fn extra_len(&self) -> usize {
+        0
+    }
+
+    /// Whether the backend encrypts and decrypts the memory itself with
+    /// [`seal`](#method.seal) and [`open`](#method.open), e.g. inside an
+    /// SGX enclave or SEV-SNP protected memory. The prekey is then protected
+    /// right after it is generated and never restored in the shielded memory,
+    /// so the encryption key only ever exists in the backend. `false` by
+    /// default.
+    fn encrypts(&self) -> bool {
+        false
+    }
+
+    /// Encrypt `payload` in-place and write its tag to `tag`, with the prekey
+    /// protected as `prekey`, see [`Aead::seal`](struct.Aead.html#method.seal).
+    /// Only called if [`encrypts`](#method.encrypts) returns `true`. Fails by
+    /// default.
+    fn seal(
+        &self,
+        _prekey: &[u8],
+        _aead: &Aead<'_>,
+        _payload: &mut [u8],
+        _tag: &mut [u8],
+    ) -> Result<(), ShieldError> {
+        Err(ShieldError::Backend)
+    }
+
+    /// Decrypt `in_out`, the ciphertext followed by its tag, in-place with the
+    /// prekey protected as `prekey`, returning the length of the plaintext,
+    /// see [`Aead::open`](struct.Aead.html#method.open). Only called if
+    /// [`encrypts`](#method.encrypts) returns `true`. Fails by default.
+    fn open(
+        &self,
+        _prekey: &[u8],
+        _aead: &Aead<'_>,
+        _in_out: &mut [u8],
+    ) -> Result<usize, ShieldError> {
+        Err(ShieldError::Backend)
+    }
+}
+
+/// The encryption of one chunk of shielded memory, handed to a
+/// [`Backend`](trait.Backend.html) which encrypts itself.
+///
+/// The backend restores the prekey on its side and calls
+/// [`seal`](#method.seal) or [`open`](#method.open) with it, which derive the
+/// key and encrypt exactly like the memory does without such a backend.
+#[derive(Clone, Copy, Debug)]
+pub struct Aead<'a> {
+    cipher: Cipher,
+    nonce: &'a [u8],
+    context: &'a [u8],
+    aad: &'a [u8],
+}
+
+impl<'a> Aead<'a> {
+    pub(crate) fn new(cipher: Cipher, nonce: &'a [u8], context: &'a [u8], aad: &'a [u8]) -> Self {
+        Self {
+            cipher,
+            nonce,
+            context,
+            aad,
+        }
+    }
+
+    /// The cipher of the memory.
+    pub fn cipher(&self) -> Cipher {
+        self.cipher
+    }
+
+    /// The nonce of the chunk.
+    pub fn nonce(&self) -> &'a [u8] {
+        self.nonce
+    }
+
+    /// Encrypt `payload` in-place and write its tag to `tag`, under the key
+    /// derived from the restored `prekey`, which is also authenticated along
+    /// with the caller's
+    /// [associated data](../struct.ShieldedBuilder.html#method.associated_data).
+    pub fn seal(
+        &self,
+        prekey: &[u8],
+        payload: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), ShieldError> {
+        let key = new_key(self.cipher, prekey, self.context)?;
+        with_authenticated(prekey, self.aad, |authenticated| {
+            Crypto::seal(self.cipher, &key.0, self.nonce, authenticated, payload, tag)
+        })
+    }
+
+    /// Decrypt `in_out` in-place under the key derived from the restored
+    /// `prekey`, returning the length of the plaintext.
+    pub fn open(&self, prekey: &[u8], in_out: &mut [u8]) -> Result<usize, ShieldError> {
+        let key = new_key(self.cipher, prekey, self.context)?;
+        with_authenticated(prekey, self.aad, |authenticated| {
+            Crypto::open(self.cipher, &key.0, self.nonce, authenticated, in_out)
+        })
+    }
+}
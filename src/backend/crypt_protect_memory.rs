@@ -0,0 +1,65 @@
+use core::convert::TryFrom;
+
+use windows_sys::Win32::Security::Cryptography::{
+    CryptProtectMemory as crypt_protect_memory, CryptUnprotectMemory as crypt_unprotect_memory,
+    CRYPTPROTECTMEMORY_BLOCK_SIZE, CRYPTPROTECTMEMORY_SAME_PROCESS,
+};
+
+use super::Backend;
+use crate::ShieldError;
+
+/// A [`Backend`](trait.Backend.html) encrypting the prekey at rest with
+/// Windows' `CryptProtectMemory`.
+///
+/// The prekey can only be restored by the same process. Its length must be a
+/// multiple of 16 bytes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CryptProtectMemory;
+
+impl CryptProtectMemory {
+    /// Create a new `CryptProtectMemory` backend.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Backend for CryptProtectMemory {
+    fn protect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        let len = block_len(prekey)?;
+        let ok = unsafe {
+            crypt_protect_memory(
+                prekey.as_mut_ptr().cast(),
+                len,
+                CRYPTPROTECTMEMORY_SAME_PROCESS,
+            )
+        };
+        if ok != 0 {
+            Ok(())
+        } else {
+            Err(ShieldError::Backend)
+        }
+    }
+
+    fn unprotect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        let len = block_len(prekey)?;
+        let ok = unsafe {
+            crypt_unprotect_memory(
+                prekey.as_mut_ptr().cast(),
+                len,
+                CRYPTPROTECTMEMORY_SAME_PROCESS,
+            )
+        };
+        if ok != 0 {
+            Ok(())
+        } else {
+            Err(ShieldError::Backend)
+        }
+    }
+}
+
+fn block_len(prekey: &[u8]) -> Result<u32, ShieldError> {
+    match u32::try_from(prekey.len()) {
+        Ok(len) if len % CRYPTPROTECTMEMORY_BLOCK_SIZE == 0 => Ok(len),
+        _ => Err(ShieldError::Backend),
+    }
+}
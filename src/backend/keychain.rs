@@ -0,0 +1,181 @@
+use std::ptr;
+
+use core_foundation::base::TCFType;
+use core_foundation::data::{CFData, CFDataRef};
+use core_foundation::error::{CFError, CFErrorRef};
+use security_framework::key::{Algorithm, GenerateKeyOptions, KeyType, SecKey, Token};
+use security_framework::passwords::{get_generic_password, set_generic_password};
+use security_framework_sys::base::{errSecItemNotFound, SecKeyRef};
+use security_framework_sys::key::{
+    SecKeyAlgorithm, SecKeyCreateDecryptedData, SecKeyCreateEncryptedData,
+};
+use zeroize::Zeroize;
+
+use super::{WrappingKey, WRAPPING_KEY_LEN};
+use crate::{fill_random, Key, ShieldError};
+
+const ALGORITHM: Algorithm = Algorithm::ECIESEncryptionCofactorVariableIVX963SHA256AESGCM;
+
+/// Keeps the wrapping key of a [`WrappedPrekey`](struct.WrappedPrekey.html)
+/// backend as a generic password in the macOS Keychain.
+///
+/// The wrapping key is read from the Keychain for every shield and unshield,
+/// so the prekey in memory is always encrypted in between. The Keychain may
+/// ask the user to allow the access, depending on the access control of the
+/// item.
+///
+/// ```no_run
+/// use shielded::backend::{Keychain, WrappedPrekey};
+/// use shielded::Shielded;
+///
+/// let keychain = Keychain::new("com.example.app", "prekey wrapping key").unwrap();
+/// let mut shielded = Shielded::builder()
+///     .backend(WrappedPrekey::new(keychain))
+///     .build(b"secret".to_vec())
+///     .unwrap();
+/// assert_eq!(b"secret", shielded.unshield().as_ref());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keychain {
+    service: String,
+    account: String,
+}
+
+impl Keychain {
+    /// Use the generic password of `service` and `account` as the wrapping
+    /// key, adding a random one to the default keychain if there is none.
+    ///
+    /// Returns [`ShieldError::Backend`](../enum.ShieldError.html#variant.Backend)
+    /// if the Keychain can't be accessed or the password isn't a wrapping key.
+    pub fn new(service: &str, account: &str) -> Result<Self, ShieldError> {
+        let keychain = Self {
+            service: String::from(service),
+            account: String::from(account),
+        };
+        match get_generic_password(service, account) {
+            Ok(mut password) => {
+                let len = password.len();
+                password.zeroize();
+                if len != WRAPPING_KEY_LEN {
+                    return Err(ShieldError::Backend);
+                }
+            }
+            Err(e) if e.code() == errSecItemNotFound => {
+                let mut key = Key::new(WRAPPING_KEY_LEN);
+                fill_random(None, &mut key.0)?;
+                set_generic_password(service, account, &key.0).map_err(|_| ShieldError::Backend)?;
+            }
+            Err(_) => return Err(ShieldError::Backend),
+        }
+        Ok(keychain)
+    }
+}
+
+impl WrappingKey for Keychain {
+    fn fetch(&self, key: &mut [u8; WRAPPING_KEY_LEN]) -> Result<(), ShieldError> {
+        let mut password =
+            get_generic_password(&self.service, &self.account).map_err(|_| ShieldError::Backend)?;
+        let result = if password.len() == WRAPPING_KEY_LEN {
+            key.copy_from_slice(&password);
+            Ok(())
+        } else {
+            Err(ShieldError::Backend)
+        };
+        password.zeroize();
+        result
+    }
+}
+
+/// Keeps the wrapping key of a [`WrappedPrekey`](struct.WrappedPrekey.html)
+/// backend encrypted under a key in the Secure Enclave of the Mac.
+///
+/// A P-256 key is generated in the Secure Enclave (`kSecAttrTokenIDSecureEnclave`)
+/// and a random wrapping key is encrypted to it with ECIES. The private key
+/// never leaves the Secure Enclave, which decrypts the wrapping key for every
+/// shield and unshield. The key isn't stored in the Keychain, so it is lost,
+/// and with it the shielded memory, when the `SecureEnclave` is dropped.
+///
+/// ```no_run
+/// use shielded::backend::{WrappedPrekey, SecureEnclave};
+/// use shielded::Shielded;
+///
+/// let mut shielded = Shielded::builder()
+///     .backend(WrappedPrekey::new(SecureEnclave::new().unwrap()))
+///     .build(b"secret".to_vec())
+///     .unwrap();
+/// assert_eq!(b"secret", shielded.unshield().as_ref());
+/// ```
+#[derive(Debug)]
+pub struct SecureEnclave {
+    key: SecKey,
+    wrapped: Vec<u8>,
+}
+
+impl SecureEnclave {
+    /// Generate a key in the Secure Enclave and a wrapping key encrypted to
+    /// it.
+    ///
+    /// Returns [`ShieldError::Backend`](../enum.ShieldError.html#variant.Backend)
+    /// if the Mac has no Secure Enclave or the key can't be generated.
+    pub fn new() -> Result<Self, ShieldError> {
+        let mut options = GenerateKeyOptions::default();
+        let _ = options
+            .set_key_type(KeyType::ec())
+            .set_size_in_bits(256)
+            .set_token(Token::SecureEnclave);
+        let key = SecKey::generate(options.to_dictionary()).map_err(|_| ShieldError::Backend)?;
+        let public_key = key.public_key().ok_or(ShieldError::Backend)?;
+
+        let mut wrapping_key = Key::new(WRAPPING_KEY_LEN);
+        fill_random(None, &mut wrapping_key.0)?;
+        let wrapped = crypt(&public_key, &wrapping_key.0, SecKeyCreateEncryptedData)?;
+        Ok(Self {
+            key,
+            wrapped: wrapped.to_vec(),
+        })
+    }
+}
+
+impl WrappingKey for SecureEnclave {
+    fn fetch(&self, key: &mut [u8; WRAPPING_KEY_LEN]) -> Result<(), ShieldError> {
+        let plaintext = crypt(&self.key, &self.wrapped, SecKeyCreateDecryptedData)?;
+        let result = if plaintext.len() == WRAPPING_KEY_LEN as isize {
+            key.copy_from_slice(plaintext.bytes());
+            Ok(())
+        } else {
+            Err(ShieldError::Backend)
+        };
+        // The decrypted data is owned solely by us, wipe it before it is
+        // released.
+        unsafe {
+            let bytes = plaintext.bytes();
+            ptr::write_bytes(bytes.as_ptr() as *mut u8, 0, bytes.len());
+        }
+        result
+    }
+}
+
+type Crypt =
+    unsafe extern "C" fn(SecKeyRef, SecKeyAlgorithm, CFDataRef, *mut CFErrorRef) -> CFDataRef;
+
+// Encrypt or decrypt `input` with `key`.
+fn crypt(key: &SecKey, input: &[u8], crypt: Crypt) -> Result<CFData, ShieldError> {
+    let input = CFData::from_buffer(input);
+    let mut error: CFErrorRef = ptr::null_mut();
+    let output = unsafe {
+        crypt(
+            key.as_concrete_TypeRef(),
+            ALGORITHM.into(),
+            input.as_concrete_TypeRef(),
+            &mut error,
+        )
+    };
+    if !error.is_null() {
+        let _ = unsafe { CFError::wrap_under_create_rule(error) };
+        return Err(ShieldError::Backend);
+    }
+    if output.is_null() {
+        return Err(ShieldError::Backend);
+    }
+    Ok(unsafe { CFData::wrap_under_create_rule(output) })
+}
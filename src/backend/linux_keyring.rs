@@ -0,0 +1,128 @@
+use core::convert::{TryFrom, TryInto};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use alloc::format;
+use zeroize::Zeroize;
+
+use super::Backend;
+use crate::ShieldError;
+
+// The serial number of the key replaces the prekey in memory.
+const SERIAL_LEN: usize = 4;
+
+// Tells the keys of concurrently shielded memories apart, as adding a key
+// with the description of an existing one would replace it.
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A [`Backend`](trait.Backend.html) moving the prekey at rest out of the
+/// address space into a Linux kernel keyring.
+///
+/// The prekey is added as a `user` key and only its serial number is kept in
+/// memory. It is read back and the key invalidated for each unshield.
+///
+/// The kernel limits the keys of a user: by default to 20000 bytes for users
+/// other than root, see `/proc/sys/kernel/keys/maxbytes`. Use a shorter
+/// [prekey](../struct.ShieldedBuilder.html#method.prekey_len) or raise the
+/// limit to keep more than one memory shielded at a time. Prekeys longer than
+/// 32767 bytes are refused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinuxKeyring {
+    keyring: i32,
+}
+
+impl LinuxKeyring {
+    /// Keep the prekeys in the process keyring, shared by all threads of the
+    /// process.
+    pub fn new() -> Self {
+        Self {
+            keyring: libc::KEY_SPEC_PROCESS_KEYRING,
+        }
+    }
+
+    /// Keep the prekeys in the session keyring, shared with other processes
+    /// of the session.
+    pub fn session() -> Self {
+        Self {
+            keyring: libc::KEY_SPEC_SESSION_KEYRING,
+        }
+    }
+
+    /// Keep the prekeys in the thread keyring. The memory can then only be
+    /// unshielded on the thread which shielded it.
+    pub fn thread() -> Self {
+        Self {
+            keyring: libc::KEY_SPEC_THREAD_KEYRING,
+        }
+    }
+}
+
+impl Default for LinuxKeyring {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for LinuxKeyring {
+    fn protect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        if prekey.len() < SERIAL_LEN {
+            return Err(ShieldError::Backend);
+        }
+        let description = format!(
+            "shielded prekey {} {}\0",
+            unsafe { libc::getpid() },
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let serial = unsafe {
+            libc::syscall(
+                libc::SYS_add_key,
+                b"user\0".as_ptr(),
+                description.as_ptr(),
+                prekey.as_ptr(),
+                prekey.len(),
+                self.keyring,
+            )
+        };
+        let serial = i32::try_from(serial).map_err(|_| ShieldError::Backend)?;
+        if serial < 0 {
+            return Err(ShieldError::Backend);
+        }
+
+        prekey.zeroize();
+        prekey[..SERIAL_LEN].copy_from_slice(&serial.to_ne_bytes());
+        Ok(())
+    }
+
+    fn unprotect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        let serial = serial(prekey)?;
+        let len = unsafe {
+            libc::syscall(
+                libc::SYS_keyctl,
+                libc::KEYCTL_READ,
+                serial,
+                prekey.as_mut_ptr(),
+                prekey.len(),
+            )
+        };
+        invalidate(serial);
+        if usize::try_from(len) != Ok(prekey.len()) {
+            return Err(ShieldError::Backend);
+        }
+        Ok(())
+    }
+
+    fn discard(&self, prekey: &mut [u8]) {
+        if let Ok(serial) = serial(prekey) {
+            invalidate(serial);
+        }
+    }
+}
+
+fn serial(prekey: &[u8]) -> Result<i32, ShieldError> {
+    let serial = prekey.get(..SERIAL_LEN).ok_or(ShieldError::Backend)?;
+    Ok(i32::from_ne_bytes(serial.try_into().expect("4 bytes")))
+}
+
+fn invalidate(serial: i32) {
+    let _ = unsafe { libc::syscall(libc::SYS_keyctl, libc::KEYCTL_INVALIDATE, serial) };
+}
@@ -0,0 +1,98 @@
+use super::{Aead, Backend};
+use crate::mem::{BufOptions, SecretBuf};
+use crate::ShieldError;
+
+/// A reference [`Backend`](trait.Backend.html) encrypting the memory itself,
+/// the way an enclave backend would, but in the process: the prekey
+/// protected by `B` is only restored into scratch memory of its own for each
+/// chunk, and wiped right after.
+///
+/// It adds no isolation beyond what `B` provides, but keeps the prekey and
+/// key out of the shielded memory at all times. An SGX or SEV-SNP backend
+/// implements the same methods with the scratch memory inside the enclave.
+///
+/// ```
+/// use shielded::backend::{WrappedPrekey, SoftwareEnclave, WrappingKey, WRAPPING_KEY_LEN};
+/// use shielded::{Shielded, ShieldError};
+///
+/// #[derive(Debug)]
+/// struct FixedKey;
+///
+/// impl WrappingKey for FixedKey {
+///     fn fetch(&self, key: &mut [u8; WRAPPING_KEY_LEN]) -> Result<(), ShieldError> {
+///         key.copy_from_slice(&[7; WRAPPING_KEY_LEN]);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut shielded = Shielded::builder()
+///     .backend(SoftwareEnclave::new(WrappedPrekey::new(FixedKey)))
+///     .build(b"secret".to_vec())
+///     .unwrap();
+/// assert_eq!(b"secret", shielded.unshield().as_ref());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SoftwareEnclave<B> {
+    inner: B,
+}
+
+impl<B: Backend> SoftwareEnclave<B> {
+    /// Create a new `SoftwareEnclave` keeping the prekey protected with
+    /// `inner`. `inner` must be able to restore the same prekey any number of
+    /// times.
+    pub fn new(inner: B) -> Self {
+        Self { inner }
+    }
+
+    // Restore the prekey protected as `prekey` into scratch memory, which
+    // also holds the bytes `inner` keeps next to it.
+    fn restore(&self, prekey: &[u8]) -> Result<SecretBuf, ShieldError> {
+        let mut scratch = SecretBuf::new(prekey.len(), BufOptions::new(true));
+        scratch.copy_from_slice(prekey);
+        self.inner.unprotect(&mut scratch)?;
+        let len = prekey.len() - self.inner.extra_len();
+        scratch.set_len(len);
+        Ok(scratch)
+    }
+}
+
+impl<B: Backend> Backend for SoftwareEnclave<B> {
+    fn protect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        self.inner.protect(prekey)
+    }
+
+    fn unprotect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        self.inner.unprotect(prekey)
+    }
+
+    fn discard(&self, prekey: &mut [u8]) {
+        self.inner.discard(prekey)
+    }
+
+    fn extra_len(&self) -> usize {
+        self.inner.extra_len()
+    }
+
+    fn encrypts(&self) -> bool {
+        true
+    }
+
+    fn seal(
+        &self,
+        prekey: &[u8],
+        aead: &Aead<'_>,
+        payload: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), ShieldError> {
+        aead.seal(&self.restore(prekey)?, payload, tag)
+    }
+
+    fn open(
+        &self,
+        prekey: &[u8],
+        aead: &Aead<'_>,
+        in_out: &mut [u8],
+    ) -> Result<usize, ShieldError> {
+        aead.open(&self.restore(prekey)?, in_out)
+    }
+}
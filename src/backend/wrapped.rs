@@ -0,0 +1,137 @@
+use core::convert::TryInto;
+use core::fmt;
+
+use alloc::sync::Arc;
+
+use super::Backend;
+use crate::crypto::{Crypto, CryptoBackend};
+use crate::entropy::EntropySource;
+use crate::{fill_random, Key, ShieldError};
+
+/// Length of the key fetched by [`WrappingKey`](trait.WrappingKey.html).
+pub const WRAPPING_KEY_LEN: usize = 32;
+
+// HKDF-SHA512 output is limited to 255 blocks of 64 bytes, so the keystream
+// is expanded in pieces with a counter after the info string.
+const KEYSTREAM_INFO: &[u8] = b"shielded 1 wrapped prekey";
+const KEYSTREAM_PIECE_LEN: usize = 255 * 64;
+
+// The salt drawn for every protect, kept after the prekey, so no keystream
+// is ever used twice.
+const SALT_LEN: usize = 16;
+
+/// Fetches the wrapping key of a [`WrappedPrekey`](struct.WrappedPrekey.html)
+/// backend from wherever it is kept outside of the process memory.
+///
+/// This is the hook to plug an external key store into; the crate only ships
+/// implementations for the macOS Keychain and Secure Enclave, behind the
+/// `keychain` feature. The key must be the same on every call.
+pub trait WrappingKey: fmt::Debug + Send + Sync {
+    /// Write the wrapping key to `key`.
+    fn fetch(&self, key: &mut [u8; WRAPPING_KEY_LEN]) -> Result<(), ShieldError>;
+}
+
+/// A [`Backend`](trait.Backend.html) encrypting the prekey at rest under a
+/// wrapping key kept outside of the process, fetched with a
+/// [`WrappingKey`](trait.WrappingKey.html).
+///
+/// The wrapping key is fetched for every shield and unshield, used to
+/// XOR the prekey with a keystream derived from it with HKDF-SHA512, and
+/// wiped right after. A dump of the process memory in between holds neither
+/// the prekey nor the wrapping key.
+///
+/// Every protect draws a new random salt into the keystream, kept next to
+/// the prekey, so no keystream is ever used twice. A prekey recovered while
+/// its memory is exposed reveals nothing about the other prekeys protected
+/// with the same wrapping key.
+///
+/// ```
+/// use shielded::backend::{WrappedPrekey, WrappingKey, WRAPPING_KEY_LEN};
+/// use shielded::{Shielded, ShieldError};
+///
+/// #[derive(Debug)]
+/// struct FixedKey;
+///
+/// impl WrappingKey for FixedKey {
+///     fn fetch(&self, key: &mut [u8; WRAPPING_KEY_LEN]) -> Result<(), ShieldError> {
+///         // Read the key from an external key store instead.
+///         key.copy_from_slice(&[7; WRAPPING_KEY_LEN]);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut shielded = Shielded::builder()
+///     .backend(WrappedPrekey::new(FixedKey))
+///     .build(b"secret".to_vec())
+///     .unwrap();
+/// assert_eq!(b"secret", shielded.unshield().as_ref());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct WrappedPrekey<K> {
+    wrapping_key: K,
+    entropy: Option<Arc<dyn EntropySource>>,
+}
+
+impl<K: WrappingKey> WrappedPrekey<K> {
+    /// Create a new `WrappedPrekey` backend fetching the wrapping key with
+    /// `wrapping_key`.
+    pub fn new(wrapping_key: K) -> Self {
+        Self {
+            wrapping_key,
+            entropy: None,
+        }
+    }
+
+    /// Draw the salts from `entropy` instead of the operating system's
+    /// random number generator. Required without the `std` feature.
+    pub fn entropy<E: EntropySource + 'static>(mut self, entropy: E) -> Self {
+        self.entropy = Some(Arc::new(entropy));
+        self
+    }
+
+    // XOR `prekey` with the keystream of `salt`. Its own inverse.
+    fn apply(&self, prekey: &mut [u8], salt: &[u8]) -> Result<(), ShieldError> {
+        let mut wrapping_key = Key::new(WRAPPING_KEY_LEN);
+        let key = (&mut wrapping_key.0[..]).try_into();
+        self.wrapping_key.fetch(key.expect("wrapping key length"))?;
+
+        let mut keystream = Key::new(KEYSTREAM_PIECE_LEN);
+        for (i, piece) in prekey.chunks_mut(KEYSTREAM_PIECE_LEN).enumerate() {
+            let counter = (i as u64).to_be_bytes();
+            let keystream = &mut keystream.0[..piece.len()];
+            let info = [KEYSTREAM_INFO, salt, &counter];
+            Crypto::hkdf_sha512(&wrapping_key.0, &info, keystream)?;
+            piece
+                .iter_mut()
+                .zip(keystream.iter())
+                .for_each(|(byte, key)| *byte ^= key);
+        }
+        Ok(())
+    }
+}
+
+impl<K: WrappingKey> Backend for WrappedPrekey<K> {
+    fn protect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        let (prekey, salt) = split_salt(prekey)?;
+        fill_random(self.entropy.as_deref(), salt)?;
+        self.apply(prekey, salt)
+    }
+
+    fn unprotect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        let (prekey, salt) = split_salt(prekey)?;
+        self.apply(prekey, salt)
+    }
+
+    fn extra_len(&self) -> usize {
+        SALT_LEN
+    }
+}
+
+// Split the prekey from the salt after it.
+fn split_salt(prekey: &mut [u8]) -> Result<(&mut [u8], &mut [u8]), ShieldError> {
+    let len = prekey
+        .len()
+        .checked_sub(SALT_LEN)
+        .ok_or(ShieldError::Backend)?;
+    Ok(prekey.split_at_mut(len))
+}
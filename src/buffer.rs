@@ -0,0 +1,156 @@
+use zeroize::Zeroize;
+
+use crate::layout::Layout;
+use crate::mem::SecretBuf;
+use crate::{LockMode, ShieldError, Shielded, ShieldedBuilder};
+
+/// A staging area for constructing [`Shielded`](struct.Shielded.html) memory
+/// from many small pieces, encrypting it only once.
+///
+/// The pieces are collected in memory which is locked according to the
+/// builder, excluded from core dumps where the platform supports it and wiped
+/// when no longer needed. Call [`finish`](#method.finish) to shield it.
+///
+/// ```
+/// use shielded::ShieldedBuffer;
+///
+/// let mut buffer = ShieldedBuffer::new();
+/// for word in &["correct", " horse", " battery", " staple"] {
+///     buffer.extend_from_slice(word.as_bytes()).unwrap();
+/// }
+/// let mut shielded = buffer.finish().unwrap();
+/// assert_eq!(b"correct horse battery staple", shielded.unshield().as_ref());
+/// ```
+pub struct ShieldedBuffer {
+    builder: ShieldedBuilder,
+    // The collected plaintext, `memory.len()` bytes long.
+    memory: SecretBuf,
+}
+
+impl ShieldedBuffer {
+    /// Create an empty buffer which is shielded with the default settings.
+    pub fn new() -> Self {
+        Self::with_builder(ShieldedBuilder::new())
+    }
+
+    pub(crate) fn with_builder(builder: ShieldedBuilder) -> Self {
+        let memory = SecretBuf::new(0, builder.memory_options());
+        Self { builder, memory }
+    }
+
+    /// Number of bytes collected so far.
+    pub fn len(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Returns `true` if nothing has been collected yet.
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty()
+    }
+
+    /// Append `data`.
+    ///
+    /// Returns [`ShieldError::Lock`](enum.ShieldError.html#variant.Lock) if
+    /// the buffer needs to grow, locking is required and the new memory can't
+    /// be locked. The buffer is left unchanged then.
+    pub fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), ShieldError> {
+        let old_len = self.memory.len();
+        let new_len = old_len.checked_add(data.len()).expect("capacity overflow");
+        self.reserve(new_len)?;
+        self.memory.set_len(new_len);
+        self.memory[old_len..].copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Read `reader` to its end, right into the buffer, returning the number
+    /// of bytes read. The data read before an error is kept.
+    ///
+    /// Errors of the buffer are returned as
+    /// [`io::ErrorKind::Other`](https://doc.rust-lang.org/std/io/enum.ErrorKind.html)
+    /// wrapping a [`ShieldError`](enum.ShieldError.html).
+    #[cfg(feature = "std")]
+    pub fn read_from<R: std::io::Read>(&mut self, mut reader: R) -> std::io::Result<usize> {
+        let start = self.memory.len();
+        loop {
+            let len = self.memory.len();
+            if len == self.memory.capacity() {
+                self.reserve(len + 1).map_err(std::io::Error::other)?;
+            }
+            // Read into the spare capacity, never through another buffer.
+            self.memory.set_len(self.memory.capacity());
+            let result = reader.read(&mut self.memory[len..]);
+            self.memory.set_len(len);
+            match result {
+                Ok(0) => return Ok(len - start),
+                Ok(n) => self.memory.set_len(len + n),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Shorten the buffer to `len` bytes, wiping the rest. Has no effect if
+    /// `len` is greater than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.memory.len() {
+            self.memory[len..].zeroize();
+            self.memory.set_len(len);
+        }
+    }
+
+    /// Encrypt the collected bytes, returning the `Shielded` memory holding
+    /// them.
+    pub fn finish(mut self) -> Result<Shielded, ShieldError> {
+        let len = self.memory.len();
+        let padded_len = self.builder.padding.padded_len(len);
+        let memory_len = Layout::new(
+            padded_len,
+            self.builder.chunk_size,
+            self.builder.cipher.tag_len(),
+        )
+        .memory_len();
+        self.reserve(memory_len)?;
+        self.memory.set_len(memory_len);
+        self.builder
+            .padding
+            .pad(&mut self.memory[..padded_len], len);
+
+        let mut shielded = Shielded::with_memory(self.memory, &self.builder)?;
+        shielded.shield()?;
+        Ok(shielded)
+    }
+
+    // Make room for `len` bytes, growing geometrically so collecting many
+    // small pieces doesn't copy the buffer over and over.
+    fn reserve(&mut self, len: usize) -> Result<(), ShieldError> {
+        if len > self.memory.capacity() {
+            let mut memory = self
+                .memory
+                .resized(len.max(self.memory.capacity().saturating_mul(2)));
+            if self.builder.lock == LockMode::Required && !memory.is_locked() {
+                return Err(ShieldError::Lock);
+            }
+            memory.set_len(self.memory.len());
+            self.memory = memory;
+        }
+        Ok(())
+    }
+}
+
+impl Default for ShieldedBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for ShieldedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.extend_from_slice(buf).map_err(std::io::Error::other)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,658 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::ffi::OsStr;
+#[cfg(feature = "std")]
+use std::io::{self, Read};
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::thread::{self, ThreadId};
+
+use crate::audit::Audit;
+use crate::backend::Backend;
+use crate::entropy::EntropySource;
+use crate::mem::BufOptions;
+use crate::padding::Padding;
+use crate::protection::ProtectionRequirements;
+#[cfg(feature = "passphrase")]
+use crate::KdfParams;
+#[cfg(feature = "totp")]
+use crate::ShieldedTotp;
+#[cfg(feature = "std")]
+use crate::ShieldedWriter;
+use crate::{
+    Cipher, ShieldError, Shielded, ShieldedArena, ShieldedBuffer, ShieldedSmall, ShieldedStore,
+    SHIELD_PREKEY_LEN, SHIELD_PREKEY_MIN_LEN,
+};
+
+/// Whether the memory of a [`Shielded`](struct.Shielded.html) is locked into
+/// RAM, preventing it from being swapped to disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LockMode {
+    /// Don't lock the memory. This is the default.
+    #[default]
+    Off,
+    /// Try to lock the memory, but carry on unlocked if the platform refuses,
+    /// e.g. because `RLIMIT_MEMLOCK` has been exhausted.
+    BestEffort,
+    /// Lock the memory or fail with
+    /// [`ShieldError::Lock`](enum.ShieldError.html#variant.Lock).
+    Required,
+}
+
+/// When the prekey of a [`Shielded`](struct.Shielded.html) is replaced.
+///
+/// Generating a new prekey and deriving the encryption key from it is the
+/// most expensive part of shielding the memory again after every exposure.
+/// Keeping the prekey for several exposures trades a longer key lifetime for
+/// throughput in hot paths. The memory is always encrypted again under a new
+/// nonce, and the encryption key is only kept while the memory is unshielded.
+///
+/// Nonces are random, so with ChaCha20-Poly1305 and AES-256-GCM a prekey
+/// should not be kept for more than a few billion exposures.
+/// XChaCha20-Poly1305 has no such limit. With a
+/// [nonce sequence](struct.ShieldedBuilder.html#method.nonce_sequence) the
+/// crate guarantees unique nonces instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReshieldPolicy {
+    /// Generate a new prekey on every shielding. This is the default.
+    #[default]
+    Always,
+    /// Keep the prekey for this many exposures.
+    Every(u64),
+    /// Keep the prekey for exposures within this duration of its generation.
+    #[cfg(feature = "std")]
+    After(Duration),
+}
+
+/// Restrictions on how the content of a [`Shielded`](struct.Shielded.html)
+/// may be exposed. Nothing is restricted by default.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use shielded::{ExposurePolicy, Shielded};
+///
+/// let policy = ExposurePolicy::new()
+///     .max_duration(Duration::from_millis(100))
+///     .current_thread();
+/// let mut shielded = Shielded::builder()
+///     .exposure_policy(policy)
+///     .build(b"secret".to_vec())
+///     .unwrap();
+/// assert_eq!(6, shielded.expose(|secret| secret.len()));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExposurePolicy {
+    pub(crate) max_duration: Option<Duration>,
+    pub(crate) thread: Option<ThreadId>,
+    pub(crate) exclusive: bool,
+}
+
+#[cfg(feature = "std")]
+impl ExposurePolicy {
+    /// Create a policy restricting nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cap how long the guard returned by
+    /// [`unshield`](struct.Shielded.html#method.unshield) or
+    /// [`unshield_mut`](struct.Shielded.html#method.unshield_mut) may live.
+    /// A guard living longer still shields the memory again when dropped,
+    /// but poisons it: every later exposure fails with
+    /// [`ShieldError::PolicyViolation`](enum.ShieldError.html#variant.PolicyViolation).
+    pub fn max_duration(mut self, duration: Duration) -> Self {
+        self.max_duration = Some(duration);
+        self
+    }
+
+    /// Only allow exposing the content on the thread `thread`. Exposures on
+    /// other threads fail with
+    /// [`ShieldError::PolicyViolation`](enum.ShieldError.html#variant.PolicyViolation).
+    pub fn thread(mut self, thread: ThreadId) -> Self {
+        self.thread = Some(thread);
+        self
+    }
+
+    /// Only allow exposing the content on the current thread.
+    pub fn current_thread(self) -> Self {
+        self.thread(thread::current().id())
+    }
+
+    /// Fail exposures of a [`ShieldedCell`](struct.ShieldedCell.html) with
+    /// [`ShieldError::PolicyViolation`](enum.ShieldError.html#variant.PolicyViolation)
+    /// while another thread is exposing the content, instead of waiting for
+    /// it. A `Shielded` itself can't be exposed concurrently, as exposing it
+    /// takes `&mut self`.
+    pub fn exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+}
+
+/// A builder for constructing [`Shielded`](struct.Shielded.html) memory with
+/// non-default settings.
+///
+/// ```
+/// use shielded::{LockMode, Shielded};
+///
+/// let shielded = Shielded::builder()
+///     .lock(LockMode::BestEffort)
+///     .build(b"secret".to_vec())
+///     .unwrap();
+/// # drop(shielded);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ShieldedBuilder {
+    pub(crate) lock: LockMode,
+    pub(crate) prekey_len: usize,
+    pub(crate) prekey_fragments: usize,
+    pub(crate) chunk_size: Option<usize>,
+    pub(crate) padding: Padding,
+    pub(crate) backend: Option<Arc<dyn Backend>>,
+    pub(crate) cipher: Cipher,
+    pub(crate) policy: ReshieldPolicy,
+    pub(crate) nonce_sequence: bool,
+    pub(crate) key_commitment: bool,
+    pub(crate) context: Vec<u8>,
+    pub(crate) aad: Vec<u8>,
+    pub(crate) entropy: Option<Arc<dyn EntropySource>>,
+    pub(crate) max_uses: Option<u64>,
+    pub(crate) audit: Option<Arc<dyn Audit>>,
+    pub(crate) label: String,
+    #[cfg(feature = "std")]
+    pub(crate) ttl: Option<Duration>,
+    #[cfg(feature = "std")]
+    pub(crate) exposure: ExposurePolicy,
+    pub(crate) protection: ProtectionRequirements,
+    #[cfg(feature = "memfd-secret")]
+    memfd_secret_memory: bool,
+}
+
+impl Default for ShieldedBuilder {
+    fn default() -> Self {
+        Self {
+            lock: LockMode::default(),
+            prekey_len: SHIELD_PREKEY_LEN,
+            prekey_fragments: 1,
+            chunk_size: None,
+            padding: Padding::default(),
+            backend: None,
+            cipher: Cipher::default(),
+            policy: ReshieldPolicy::default(),
+            nonce_sequence: false,
+            key_commitment: false,
+            context: Vec::new(),
+            aad: Vec::new(),
+            entropy: None,
+            max_uses: None,
+            audit: None,
+            label: String::new(),
+            #[cfg(feature = "std")]
+            ttl: None,
+            #[cfg(feature = "std")]
+            exposure: ExposurePolicy::default(),
+            protection: ProtectionRequirements::default(),
+            #[cfg(feature = "memfd-secret")]
+            memfd_secret_memory: false,
+        }
+    }
+}
+
+impl ShieldedBuilder {
+    /// Create a builder with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether the prekey, nonce and shielded memory are locked into RAM.
+    pub fn lock(mut self, lock: LockMode) -> Self {
+        self.lock = lock;
+        self
+    }
+
+    /// Set the length of the random prekey the encryption key is derived
+    /// from. Defaults to 16kB. A longer prekey makes recovering it through a
+    /// memory sidechannel harder, a shorter one saves memory and the cost of
+    /// generating it on every shielding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is less than 1kB.
+    pub fn prekey_len(mut self, len: usize) -> Self {
+        assert!(
+            len >= SHIELD_PREKEY_MIN_LEN,
+            "prekey must be at least {} bytes",
+            SHIELD_PREKEY_MIN_LEN
+        );
+        self.prekey_len = len;
+        self
+    }
+
+    /// Scatter the prekey over `fragments` allocations of equal length
+    /// instead of keeping it in one piece. A contiguous prekey is a
+    /// convenient target for attacks reading physical memory like Rambleed.
+    /// Scattered, the fragments sit in pages of their own, allocated in
+    /// random order, and have to be found and put in order before the prekey
+    /// can be used. They are gathered into scratch memory only while the
+    /// prekey is used, to derive the encryption key or by the
+    /// [backend](#method.backend). Defaults to 1, a contiguous prekey.
+    ///
+    /// Every fragment takes at least a page of memory, and there is at most
+    /// one fragment per byte of the prekey.
+    /// With [`Cipher::Cascade`](enum.Cipher.html#variant.Cascade), both halves
+    /// of the prekey are split into `fragments` fragments of their own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fragments` is zero.
+    pub fn scatter_prekey(mut self, fragments: usize) -> Self {
+        assert!(fragments > 0, "prekey must have at least one fragment");
+        self.prekey_fragments = fragments;
+        self
+    }
+
+    /// Encrypt the memory in separate chunks of `size` bytes, so that
+    /// [`Shielded::unshield_range`](struct.Shielded.html#method.unshield_range)
+    /// only has to decrypt the chunks covering the range. By default the
+    /// memory is encrypted as a whole.
+    ///
+    /// Every chunk costs an extra encryption tag of 16 bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero.
+    pub fn chunk_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "chunk size must not be zero");
+        self.chunk_size = Some(size);
+        self
+    }
+
+    /// Pad the content before it is encrypted, so the length of the shielded
+    /// memory doesn't reveal the exact length of the content. Defaults to
+    /// [`Padding::Off`](enum.Padding.html#variant.Off).
+    ///
+    /// With padding, [`Shielded::len`](struct.Shielded.html#method.len) is
+    /// the padded length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the block size of
+    /// [`Padding::Block`](enum.Padding.html#variant.Block) is zero.
+    pub fn padding(mut self, padding: Padding) -> Self {
+        assert!(
+            padding != Padding::Block(0),
+            "padding block size must not be zero"
+        );
+        self.padding = padding;
+        self
+    }
+
+    /// Set the cipher used to encrypt the memory. Defaults to
+    /// [`Cipher::ChaCha20Poly1305`](enum.Cipher.html#variant.ChaCha20Poly1305).
+    pub fn cipher(mut self, cipher: Cipher) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// Set when the prekey is replaced. Defaults to
+    /// [`ReshieldPolicy::Always`](enum.ReshieldPolicy.html#variant.Always).
+    pub fn reshield_policy(mut self, policy: ReshieldPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Count the nonce up from the random one of the prekey when the memory
+    /// is shielded again under a kept prekey, instead of generating a random
+    /// nonce. Shielding a hot secret again then costs no entropy at all, and
+    /// the nonces are unique by construction rather than with high
+    /// probability.
+    ///
+    /// Has no effect with
+    /// [`ReshieldPolicy::Always`](enum.ReshieldPolicy.html#variant.Always).
+    /// Otherwise the prekey is still replaced as the
+    /// [`ReshieldPolicy`](enum.ReshieldPolicy.html) says, and in any case
+    /// after 2³² − 1 exposures, before the sequence could repeat.
+    ///
+    /// ```
+    /// use shielded::{ReshieldPolicy, Shielded};
+    ///
+    /// let mut shielded = Shielded::builder()
+    ///     .reshield_policy(ReshieldPolicy::Every(1000))
+    ///     .nonce_sequence(true)
+    ///     .build(b"secret".to_vec())
+    ///     .unwrap();
+    /// assert_eq!(6, shielded.expose(|secret| secret.len()));
+    /// ```
+    pub fn nonce_sequence(mut self, enable: bool) -> Self {
+        self.nonce_sequence = enable;
+        self
+    }
+
+    /// Commit to the encryption key with an HMAC-SHA-256 of it, checked
+    /// before every decryption. Neither ChaCha20-Poly1305 nor AES-256-GCM
+    /// commit to their key: a ciphertext can be crafted to authenticate
+    /// under several keys, e.g. by an attacker able to modify the memory and
+    /// to influence the prekey. With the commitment, unshielding only
+    /// succeeds under the exact prekey the memory was shielded with, and
+    /// fails with [`ShieldError::Tamper`](enum.ShieldError.html#variant.Tamper)
+    /// otherwise. Costs one HMAC per shielding and unshielding.
+    pub fn key_commitment(mut self, enable: bool) -> Self {
+        self.key_commitment = enable;
+        self
+    }
+
+    /// Wipe the memory after it has been unshielded `uses` times. Every
+    /// [`unshield`](struct.Shielded.html#method.unshield),
+    /// [`unshield_mut`](struct.Shielded.html#method.unshield_mut),
+    /// [`unshield_range`](struct.Shielded.html#method.unshield_range),
+    /// [`ct_eq`](struct.Shielded.html#method.ct_eq) and reader counts, as
+    /// does everything built on them like
+    /// [`expose`](struct.Shielded.html#method.expose). Every poll of
+    /// [`expose_async`](struct.Shielded.html#method.expose_async) counts
+    /// separately, a reader counts once. Afterwards these fail
+    /// with [`ShieldError::Expired`](enum.ShieldError.html#variant.Expired).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `uses` is zero.
+    pub fn max_uses(mut self, uses: u64) -> Self {
+        assert!(uses > 0, "max uses must not be zero");
+        self.max_uses = Some(uses);
+        self
+    }
+
+    /// Wipe the memory once `ttl` has passed since it was constructed. The
+    /// time is checked whenever the memory is unshielded or shielded again,
+    /// so memory which is never accessed again is wiped only when dropped.
+    /// Afterwards unshielding fails with
+    /// [`ShieldError::Expired`](enum.ShieldError.html#variant.Expired).
+    #[cfg(feature = "std")]
+    pub fn time_to_live(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Restrict how the content may be exposed. See
+    /// [`ExposurePolicy`](struct.ExposurePolicy.html).
+    #[cfg(feature = "std")]
+    pub fn exposure_policy(mut self, policy: ExposurePolicy) -> Self {
+        self.exposure = policy;
+        self
+    }
+
+    /// Set an application context label mixed into the derivation of the
+    /// encryption key, separating keys of different applications or purposes
+    /// sharing the crate. Empty by default.
+    pub fn context(mut self, context: &[u8]) -> Self {
+        self.context = context.to_vec();
+        self
+    }
+
+    /// Set associated data, e.g. the name, owner or epoch of the secret,
+    /// which is authenticated along with the prekey whenever the memory is
+    /// encrypted or decrypted. Memory corruption moving the ciphertext and
+    /// prekey of one `Shielded` into another with different associated data
+    /// is detected then, and unshielding fails with
+    /// [`ShieldError::Tamper`](enum.ShieldError.html#variant.Tamper). Empty
+    /// by default.
+    ///
+    /// Unlike the [`context`](#method.context), which separates the keys of
+    /// different applications, the associated data goes into the AEAD
+    /// itself and is copied into scratch memory next to the prekey
+    /// for every operation.
+    pub fn associated_data(mut self, aad: &[u8]) -> Self {
+        self.aad = aad.to_vec();
+        self
+    }
+
+    /// Generate prekeys and nonces with `entropy` instead of the operating
+    /// system's random number generator. Required without the `std` feature,
+    /// where building fails with
+    /// [`ShieldError::Rng`](enum.ShieldError.html#variant.Rng) otherwise.
+    pub fn entropy<E: EntropySource + 'static>(mut self, entropy: E) -> Self {
+        self.entropy = Some(Arc::new(entropy));
+        self
+    }
+
+    /// Report when the memory is shielded, unshielded and wiped to `audit`.
+    /// See the [`audit`](audit/index.html) module.
+    pub fn audit<A: Audit + 'static>(mut self, audit: A) -> Self {
+        self.audit = Some(Arc::new(audit));
+        self
+    }
+
+    /// Set a label identifying the memory in
+    /// [audit events](audit/struct.AuditEvent.html) and, with the `metrics`
+    /// feature, in [metrics](metrics/index.html). Empty by default.
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = String::from(label);
+        self
+    }
+
+    /// Protect the prekey at rest with `backend`. By default the prekey is
+    /// kept in plain userspace memory.
+    pub fn backend<B: Backend + 'static>(mut self, backend: B) -> Self {
+        self.backend = Some(Arc::new(backend));
+        self
+    }
+
+    /// Fail construction with
+    /// [`ShieldError::Unprotected`](enum.ShieldError.html#variant.Unprotected)
+    /// unless the platform provides the protections `requirements` ask for.
+    /// Memory allocated later, e.g. when the content grows, must have them
+    /// too, except for secret memory which only the prekey needs.
+    pub fn require_protection(mut self, requirements: ProtectionRequirements) -> Self {
+        self.protection = requirements;
+        self
+    }
+
+    /// Also place the shielded memory, not only the prekey, in a
+    /// `memfd_secret(2)` mapping. Falls back to ordinary memory on platforms
+    /// and kernels without secret memory support.
+    #[cfg(feature = "memfd-secret")]
+    pub fn memfd_secret_memory(mut self, enable: bool) -> Self {
+        self.memfd_secret_memory = enable;
+        self
+    }
+
+    /// Construct the `Shielded` memory holding `buf`.
+    pub fn build(self, buf: Vec<u8>) -> Result<Shielded, ShieldError> {
+        Shielded::with_builder(buf, &self)
+    }
+
+    /// Construct a [`ShieldedSmall`](struct.ShieldedSmall.html) holding
+    /// `secret`, wiping `secret`. Only the cipher, prekey length, context,
+    /// entropy source and lock mode apply; set a
+    /// [`prekey_len`](#method.prekey_len) smaller than the default 16 KiB to
+    /// make shielding cheaper still.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `secret` is longer than
+    /// [`ShieldedSmall::MAX_LEN`](struct.ShieldedSmall.html#associatedconstant.MAX_LEN).
+    pub fn build_small(self, secret: &mut [u8]) -> Result<ShieldedSmall, ShieldError> {
+        ShieldedSmall::with_builder(secret, &self)
+    }
+
+    /// Create an empty [`ShieldedArena`](struct.ShieldedArena.html) whose
+    /// entries are shielded with these settings. Only the cipher, prekey
+    /// length, context, entropy source, lock mode and protection requirements
+    /// apply.
+    pub fn build_arena(self) -> Result<ShieldedArena, ShieldError> {
+        ShieldedArena::with_builder(&self)
+    }
+
+    /// Construct a [`ShieldedTotp`](struct.ShieldedTotp.html) using the
+    /// base32 encoded seed `encoded`, wiping `encoded`. See
+    /// [`ShieldedTotp::from_base32`](struct.ShieldedTotp.html#method.from_base32).
+    #[cfg(feature = "totp")]
+    pub fn build_totp(self, encoded: String) -> Result<ShieldedTotp, ShieldError> {
+        crate::totp::from_base32(encoded, &self)
+    }
+
+    /// Construct `Shielded` memory holding `len` random bytes, e.g. a new
+    /// key. The bytes are generated right into the memory and never exist
+    /// unencrypted anywhere else.
+    pub fn random(self, len: usize) -> Result<Shielded, ShieldError> {
+        Shielded::with_content(len, &self, |memory| {
+            crate::fill_random(self.entropy.as_deref(), &mut memory[..len])
+        })
+    }
+
+    /// Construct `Shielded` memory holding a subkey of `len` bytes derived
+    /// from the content of `master`. See
+    /// [`Shielded::derive_subkey`](struct.Shielded.html#method.derive_subkey).
+    pub fn derive_subkey(
+        self,
+        master: &mut Shielded,
+        info: &[u8],
+        len: usize,
+    ) -> Result<Shielded, ShieldError> {
+        crate::derive::derive_subkey(master, info, len, &self)
+    }
+
+    /// Split the content of `shielded` into `n` shares, any `k` of which
+    /// combine back to the content, each in `Shielded` memory with these
+    /// settings. See [`Shielded::split`](struct.Shielded.html#method.split).
+    pub fn split(
+        self,
+        shielded: &mut Shielded,
+        n: u8,
+        k: u8,
+    ) -> Result<Vec<Shielded>, ShieldError> {
+        crate::shamir::split(shielded, n, k, &self)
+    }
+
+    /// Construct the `Shielded` memory holding the content combined from
+    /// `shares`. See
+    /// [`Shielded::combine`](struct.Shielded.html#method.combine).
+    pub fn combine(self, shares: &mut [Shielded]) -> Result<Shielded, ShieldError> {
+        crate::shamir::combine(shares, &self)
+    }
+
+    /// Construct the `Shielded` memory holding the content exported with
+    /// [`Shielded::export`](struct.Shielded.html#method.export) under `kek`.
+    /// See [`Shielded::import`](struct.Shielded.html#method.import).
+    pub fn import(self, blob: &[u8], kek: &[u8]) -> Result<Shielded, ShieldError> {
+        crate::export::import(blob, kek, &self)
+    }
+
+    /// Construct the `Shielded` memory holding the key wrapped with
+    /// [`Shielded::wrap`](struct.Shielded.html#method.wrap) under the content
+    /// of `kek`. See
+    /// [`Shielded::unwrap_into_shielded`](struct.Shielded.html#method.unwrap_into_shielded).
+    pub fn unwrap_into_shielded(
+        self,
+        kek: &mut Shielded,
+        wrapped: &[u8],
+    ) -> Result<Shielded, ShieldError> {
+        crate::export::unwrap(kek, wrapped, &self)
+    }
+
+    /// Construct the `Shielded` memory holding the content of the file at
+    /// `path` written by
+    /// [`Shielded::seal_to_file`](struct.Shielded.html#method.seal_to_file)
+    /// with `passphrase`. See
+    /// [`Shielded::open_from_file`](struct.Shielded.html#method.open_from_file).
+    #[cfg(feature = "passphrase")]
+    pub fn open_from_file<P: AsRef<Path>>(
+        self,
+        path: P,
+        passphrase: &[u8],
+    ) -> io::Result<Shielded> {
+        crate::file::open_from_file(path.as_ref(), passphrase, &self)
+    }
+
+    /// Construct the `Shielded` memory holding the key derived from
+    /// `passphrase` with Argon2id and `params`, wiping `passphrase`. See
+    /// [`Shielded::from_passphrase`](struct.Shielded.html#method.from_passphrase).
+    #[cfg(feature = "passphrase")]
+    pub fn build_from_passphrase(
+        self,
+        passphrase: &mut [u8],
+        params: &KdfParams,
+    ) -> Result<Shielded, ShieldError> {
+        crate::kdf::derive(passphrase, params, &self)
+    }
+
+    /// Construct the `Shielded` memory holding everything read from
+    /// `reader`. See
+    /// [`Shielded::from_reader`](struct.Shielded.html#method.from_reader).
+    #[cfg(feature = "std")]
+    pub fn build_from_reader<R: Read>(self, reader: R) -> io::Result<Shielded> {
+        crate::io::read_to_shielded(reader, self)
+    }
+
+    /// Construct the `Shielded` memory holding the content of the file at
+    /// `path`. See
+    /// [`Shielded::from_file`](struct.Shielded.html#method.from_file).
+    #[cfg(feature = "std")]
+    pub fn build_from_file<P: AsRef<Path>>(self, path: P) -> io::Result<Shielded> {
+        crate::io::read_to_shielded(std::fs::File::open(path)?, self)
+    }
+
+    /// Construct the `Shielded` memory holding the value of the environment
+    /// variable `name`, and scrub the variable from the environment. See
+    /// [`Shielded::from_env`](struct.Shielded.html#method.from_env).
+    ///
+    /// # Safety
+    ///
+    /// The same as for
+    /// [`Shielded::from_env`](struct.Shielded.html#method.from_env).
+    #[cfg(feature = "std")]
+    pub unsafe fn build_from_env<K: AsRef<OsStr>>(self, name: K) -> io::Result<Shielded> {
+        unsafe { crate::env::take_env(name.as_ref(), self) }
+    }
+
+    /// Create an empty [`ShieldedStore`](struct.ShieldedStore.html) whose
+    /// entries are shielded with these settings.
+    pub fn store(self) -> ShieldedStore {
+        ShieldedStore::with_builder(self)
+    }
+
+    /// Create a [`ShieldedBuffer`](struct.ShieldedBuffer.html) collecting the
+    /// content of the `Shielded` memory piece by piece before encrypting it
+    /// once.
+    pub fn buffer(self) -> ShieldedBuffer {
+        ShieldedBuffer::with_builder(self)
+    }
+
+    /// Create a [`ShieldedWriter`](struct.ShieldedWriter.html) constructing
+    /// the `Shielded` memory from streamed data, encrypted in chunks as it is
+    /// written. Chunks are 64kB unless a [chunk size](#method.chunk_size) is
+    /// set.
+    #[cfg(feature = "std")]
+    pub fn writer(self) -> Result<ShieldedWriter, ShieldError> {
+        ShieldedWriter::new(&self)
+    }
+
+    fn buf_options(&self) -> BufOptions {
+        BufOptions::new(self.lock != LockMode::Off || self.protection.locked)
+    }
+
+    pub(crate) fn prekey_options(&self) -> BufOptions {
+        let options = self.buf_options().random_placement(true);
+        #[cfg(feature = "memfd-secret")]
+        return options.memfd_secret(true);
+        #[cfg(not(feature = "memfd-secret"))]
+        return options;
+    }
+
+    pub(crate) fn nonce_options(&self) -> BufOptions {
+        self.buf_options()
+    }
+
+    pub(crate) fn memory_options(&self) -> BufOptions {
+        #[cfg(feature = "memfd-secret")]
+        return self.buf_options().memfd_secret(self.memfd_secret_memory);
+        #[cfg(not(feature = "memfd-secret"))]
+        return self.buf_options();
+    }
+}
@@ -0,0 +1,129 @@
+use std::sync::{Mutex, MutexGuard, TryLockError};
+
+use crate::{ShieldError, Shielded};
+
+/// [`Shielded`](struct.Shielded.html) memory which can be shared between
+/// threads.
+///
+/// Every access locks an internal mutex for the whole unshield / shield cycle,
+/// so concurrent callers are serialized and the memory is never unshielded
+/// twice at the same time. With an
+/// [exclusive](struct.ExposurePolicy.html#method.exclusive) exposure policy
+/// they fail instead of waiting.
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// use shielded::{Shielded, ShieldedCell};
+///
+/// let cell = Arc::new(ShieldedCell::new(Shielded::new(b"secret".to_vec())));
+/// let handles: Vec<_> = (0..4)
+///     .map(|_| {
+///         let cell = cell.clone();
+///         thread::spawn(move || cell.expose(|secret| secret.len()))
+///     })
+///     .collect();
+/// for handle in handles {
+///     assert_eq!(6, handle.join().unwrap());
+/// }
+/// ```
+pub struct ShieldedCell {
+    shielded: Mutex<Shielded>,
+    exclusive: bool,
+}
+
+impl ShieldedCell {
+    /// Wrap `shielded` for sharing between threads.
+    pub fn new(shielded: Shielded) -> Self {
+        ShieldedCell {
+            exclusive: shielded.exposure.exclusive,
+            shielded: Mutex::new(shielded),
+        }
+    }
+
+    /// Call `f` with the decrypted content and encrypt it again once `f`
+    /// returns or panics. Blocks while another thread accesses the content.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_expose`](#method.try_expose) for a fallible version.
+    pub fn expose<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        self.try_expose(f).expect("unshield memory")
+    }
+
+    /// Call `f` with the decrypted content like [`expose`](#method.expose),
+    /// returning an error if the shielded memory fails authentication.
+    pub fn try_expose<R, F>(&self, f: F) -> Result<R, ShieldError>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        self.lock()?.try_expose(f)
+    }
+
+    /// Call `f` with the decrypted content for modification and encrypt the
+    /// possibly modified content again once `f` returns or panics. Blocks
+    /// while another thread accesses the content.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_expose_mut`](#method.try_expose_mut) for a fallible version.
+    pub fn expose_mut<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        self.try_expose_mut(f).expect("unshield memory")
+    }
+
+    /// Call `f` with the decrypted content for modification like
+    /// [`expose_mut`](#method.expose_mut), returning an error if the shielded
+    /// memory fails authentication.
+    pub fn try_expose_mut<R, F>(&self, f: F) -> Result<R, ShieldError>
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        self.lock()?.try_expose_mut(f)
+    }
+
+    /// Unwrap the `Shielded` memory.
+    pub fn into_inner(self) -> Shielded {
+        self.shielded
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Shield the memory again under a new prekey and nonce, unless another
+    // thread is using it.
+    #[cfg(all(feature = "auto-rotate", not(target_family = "wasm")))]
+    pub(crate) fn rotate_if_idle(&self) -> Result<(), ShieldError> {
+        match self.shielded.try_lock() {
+            Ok(mut shielded) => shielded.rotate(),
+            Err(TryLockError::Poisoned(e)) => e.into_inner().rotate(),
+            Err(TryLockError::WouldBlock) => Ok(()),
+        }
+    }
+
+    // A panic in a closure poisons the mutex, but the guards have shielded the
+    // memory again by then, so it is safe to carry on.
+    fn lock(&self) -> Result<MutexGuard<'_, Shielded>, ShieldError> {
+        if !self.exclusive {
+            return Ok(self.shielded.lock().unwrap_or_else(|e| e.into_inner()));
+        }
+        match self.shielded.try_lock() {
+            Ok(guard) => Ok(guard),
+            Err(TryLockError::Poisoned(e)) => Ok(e.into_inner()),
+            Err(TryLockError::WouldBlock) => Err(ShieldError::PolicyViolation),
+        }
+    }
+}
+
+impl From<Shielded> for ShieldedCell {
+    fn from(shielded: Shielded) -> Self {
+        ShieldedCell::new(shielded)
+    }
+}
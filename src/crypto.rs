@@ -0,0 +1,161 @@
+//! Cryptographic primitives used for shielding, behind a common interface so
+//! the implementation can be picked at build time.
+//!
+//! `ring` is used by default. The `rustcrypto` feature provides a pure Rust
+//! alternative for targets where `ring` doesn't build. If both are enabled,
+//! `ring` is used, except for XChaCha20-Poly1305 which `ring` doesn't
+//! implement.
+//!
+//! Random numbers come from the operating system through `getrandom` with
+//! either, or from `crypto.getRandomValues` in the browser with the `js`
+//! feature.
+
+use crate::ShieldError;
+
+mod cascade;
+#[cfg(feature = "ring")]
+mod ring_crypto;
+#[cfg(feature = "rustcrypto")]
+mod rust_aead;
+#[cfg(all(feature = "rustcrypto", not(feature = "ring")))]
+mod rust_crypto;
+
+#[cfg(feature = "ring")]
+pub(crate) use ring_crypto::Ring as Crypto;
+#[cfg(all(feature = "rustcrypto", not(feature = "ring")))]
+pub(crate) use rust_crypto::RustCrypto as Crypto;
+
+#[cfg(not(any(feature = "ring", feature = "rustcrypto")))]
+compile_error!("either the `ring` or the `rustcrypto` feature must be enabled");
+
+/// Fill `buf` with secure random bytes from the operating system.
+#[cfg(feature = "std")]
+pub(crate) fn os_random(buf: &mut [u8]) -> Result<(), ShieldError> {
+    getrandom::getrandom(buf).map_err(|_| ShieldError::Rng)
+}
+
+/// Length of the key of every single cipher.
+pub(crate) const KEY_LEN: usize = 32;
+/// Length of the longest nonce of the supported ciphers.
+pub(crate) const MAX_NONCE_LEN: usize = 24;
+/// Length of the authentication tag of every single cipher.
+pub(crate) const TAG_LEN: usize = 16;
+
+/// The AEAD cipher used to encrypt [`Shielded`](struct.Shielded.html) memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Cipher {
+    /// ChaCha20-Poly1305. This is the default.
+    #[default]
+    ChaCha20Poly1305,
+    /// AES-256-GCM. Substantially faster than ChaCha20-Poly1305 for large
+    /// memory on CPUs with AES instructions.
+    Aes256Gcm,
+    /// XChaCha20-Poly1305, with a 192-bit nonce that is safe to pick at
+    /// random for every shielding without any risk of reuse. Requires the
+    /// `rustcrypto` feature, shielding fails with
+    /// [`ShieldError::Unsupported`](enum.ShieldError.html#variant.Unsupported)
+    /// without it.
+    XChaCha20Poly1305,
+    /// ChaCha20-Poly1305 under one key, then AES-256-GCM under a second,
+    /// independent key, for when recovering one key through a side channel
+    /// must not be enough to decrypt.
+    ///
+    /// The prekey is twice the
+    /// [configured length](struct.ShieldedBuilder.html#method.prekey_len),
+    /// in two halves kept in separate allocations, and each key is derived
+    /// from a half of its own. Every chunk carries both tags, 32 bytes.
+    Cascade,
+}
+
+impl Cipher {
+    /// Length of the nonce.
+    pub(crate) fn nonce_len(self) -> usize {
+        match self {
+            Cipher::ChaCha20Poly1305 | Cipher::Aes256Gcm | Cipher::Cascade => 12,
+            Cipher::XChaCha20Poly1305 => 24,
+        }
+    }
+
+    /// Number of independent keys, each with a tag of its own.
+    pub(crate) fn keys(self) -> usize {
+        match self {
+            Cipher::Cascade => 2,
+            _ => 1,
+        }
+    }
+
+    /// Length of the key, all keys together.
+    pub(crate) fn key_len(self) -> usize {
+        self.keys() * KEY_LEN
+    }
+
+    /// Length of the authentication tag, all tags together.
+    pub(crate) fn tag_len(self) -> usize {
+        self.keys() * TAG_LEN
+    }
+
+    /// Identifier of the cipher in exported memory.
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            Cipher::ChaCha20Poly1305 => 1,
+            Cipher::Aes256Gcm => 2,
+            Cipher::XChaCha20Poly1305 => 3,
+            Cipher::Cascade => 4,
+        }
+    }
+
+    /// The cipher identified by `id` in exported memory, if it is known.
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Cipher::ChaCha20Poly1305),
+            2 => Some(Cipher::Aes256Gcm),
+            3 => Some(Cipher::XChaCha20Poly1305),
+            4 => Some(Cipher::Cascade),
+            _ => None,
+        }
+    }
+}
+
+/// The random number generator, hash and AEAD ciphers needed for shielding.
+pub(crate) trait CryptoBackend {
+    /// Derive `out` from the input keying material `ikm` with HKDF-SHA-512,
+    /// using an empty salt and the concatenation of `info` as the info string.
+    fn hkdf_sha512(ikm: &[u8], info: &[&[u8]], out: &mut [u8]) -> Result<(), ShieldError>;
+
+    /// Compute the SHA-256 hash of `data`.
+    fn sha256(data: &[u8]) -> [u8; 32];
+
+    /// Compute the HMAC-SHA-1 of `message` under `key`, for one-time
+    /// passwords only.
+    #[cfg(feature = "totp")]
+    fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20];
+
+    /// Compute the HMAC-SHA-256 of `message` under `key`.
+    fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32];
+
+    /// Compute the HMAC-SHA-512 of `message` under `key`.
+    fn hmac_sha512(key: &[u8], message: &[u8]) -> [u8; 64];
+
+    /// Encrypt `in_out` in-place with `cipher` and write the authentication
+    /// tag into `tag`.
+    fn seal(
+        cipher: Cipher,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), ShieldError>;
+
+    /// Authenticate and decrypt the ciphertext in `in_out` in-place with
+    /// `cipher`. The ciphertext is followed by the authentication tag, and the
+    /// length of the plaintext is returned. Fails with `ShieldError::Tamper` if
+    /// authentication fails.
+    fn open(
+        cipher: Cipher,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut [u8],
+    ) -> Result<usize, ShieldError>;
+}
@@ -0,0 +1,53 @@
+//! Two layers of encryption under independent keys, on top of any backend.
+//!
+//! The inner layer encrypts with ChaCha20-Poly1305 under the first half of
+//! the key, authenticating the caller's associated data. The outer layer
+//! encrypts the result again with AES-256-GCM under the second half,
+//! authenticating the inner tag. Sealed, the ciphertext is followed by the
+//! outer tag and then the inner tag.
+
+use super::{Cipher, CryptoBackend, KEY_LEN, TAG_LEN};
+use crate::ShieldError;
+
+const INNER: Cipher = Cipher::ChaCha20Poly1305;
+const OUTER: Cipher = Cipher::Aes256Gcm;
+
+pub(super) fn seal<C: CryptoBackend>(
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    in_out: &mut [u8],
+    tag: &mut [u8],
+) -> Result<(), ShieldError> {
+    if key.len() != 2 * KEY_LEN || tag.len() != 2 * TAG_LEN {
+        return Err(ShieldError::Crypto);
+    }
+    let (inner_key, outer_key) = key.split_at(KEY_LEN);
+    let (outer_tag, inner_tag) = tag.split_at_mut(TAG_LEN);
+
+    C::seal(INNER, inner_key, nonce, aad, in_out, inner_tag)?;
+    C::seal(OUTER, outer_key, nonce, inner_tag, in_out, outer_tag)
+}
+
+pub(super) fn open<C: CryptoBackend>(
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    in_out: &mut [u8],
+) -> Result<usize, ShieldError> {
+    if key.len() != 2 * KEY_LEN {
+        return Err(ShieldError::Crypto);
+    }
+    let len = in_out
+        .len()
+        .checked_sub(2 * TAG_LEN)
+        .ok_or(ShieldError::Tamper)?;
+    let (inner_key, outer_key) = key.split_at(KEY_LEN);
+
+    let (sealed, inner_tag) = in_out.split_at_mut(len + TAG_LEN);
+    let _ = C::open(OUTER, outer_key, nonce, inner_tag, sealed)?;
+
+    // Move the inner tag right after the ciphertext, where the outer one was.
+    sealed[len..].copy_from_slice(inner_tag);
+    C::open(INNER, inner_key, nonce, aad, sealed)
+}
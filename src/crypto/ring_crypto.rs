@@ -0,0 +1,148 @@
+use ring::aead::{self, BoundKey, OpeningKey, SealingKey, UnboundKey};
+use ring::{digest, hkdf, hmac};
+
+#[cfg(feature = "rustcrypto")]
+use chacha20poly1305::XChaCha20Poly1305;
+
+#[cfg(feature = "rustcrypto")]
+use super::rust_aead;
+use super::{cascade, Cipher, CryptoBackend};
+use crate::ShieldError;
+
+/// Cryptography provided by `ring`.
+pub(crate) struct Ring;
+
+impl CryptoBackend for Ring {
+    // The intermediate `Prk` is owned by ring and can't be wiped from here.
+    fn hkdf_sha512(ikm: &[u8], info: &[&[u8]], out: &mut [u8]) -> Result<(), ShieldError> {
+        let prk = hkdf::Salt::new(hkdf::HKDF_SHA512, &[]).extract(ikm);
+        prk.expand(info, OkmLen(out.len()))
+            .and_then(|okm| okm.fill(out))
+            .map_err(|_| ShieldError::Crypto)
+    }
+
+    fn sha256(data: &[u8]) -> [u8; 32] {
+        let mut hash = [0; 32];
+        hash.copy_from_slice(digest::digest(&digest::SHA256, data).as_ref());
+        hash
+    }
+
+    #[cfg(feature = "totp")]
+    fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+        let mut mac = [0; 20];
+        mac.copy_from_slice(hmac_sign(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key, message).as_ref());
+        mac
+    }
+
+    fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+        let mut mac = [0; 32];
+        mac.copy_from_slice(hmac_sign(hmac::HMAC_SHA256, key, message).as_ref());
+        mac
+    }
+
+    fn hmac_sha512(key: &[u8], message: &[u8]) -> [u8; 64] {
+        let mut mac = [0; 64];
+        mac.copy_from_slice(hmac_sign(hmac::HMAC_SHA512, key, message).as_ref());
+        mac
+    }
+
+    fn seal(
+        cipher: Cipher,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), ShieldError> {
+        if cipher == Cipher::XChaCha20Poly1305 {
+            #[cfg(feature = "rustcrypto")]
+            return rust_aead::seal::<XChaCha20Poly1305>(key, nonce, aad, in_out, tag);
+            #[cfg(not(feature = "rustcrypto"))]
+            return Err(ShieldError::Unsupported);
+        }
+        if cipher == Cipher::Cascade {
+            return cascade::seal::<Self>(key, nonce, aad, in_out, tag);
+        }
+
+        let unbound_key =
+            UnboundKey::new(algorithm(cipher), key).map_err(|_| ShieldError::Crypto)?;
+        let nonce =
+            aead::Nonce::try_assume_unique_for_key(nonce).map_err(|_| ShieldError::Crypto)?;
+        let nonce_sequence = OneNonceSequence::new(nonce);
+        let mut sealing_key = SealingKey::new(unbound_key, nonce_sequence);
+
+        let t = sealing_key
+            .seal_in_place_separate_tag(aead::Aad::from(aad), in_out)
+            .map_err(|_| ShieldError::Crypto)?;
+        tag.copy_from_slice(t.as_ref());
+        Ok(())
+    }
+
+    fn open(
+        cipher: Cipher,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut [u8],
+    ) -> Result<usize, ShieldError> {
+        if cipher == Cipher::XChaCha20Poly1305 {
+            #[cfg(feature = "rustcrypto")]
+            return rust_aead::open::<XChaCha20Poly1305>(key, nonce, aad, in_out);
+            #[cfg(not(feature = "rustcrypto"))]
+            return Err(ShieldError::Unsupported);
+        }
+        if cipher == Cipher::Cascade {
+            return cascade::open::<Self>(key, nonce, aad, in_out);
+        }
+
+        let unbound_key =
+            UnboundKey::new(algorithm(cipher), key).map_err(|_| ShieldError::Crypto)?;
+        let nonce =
+            aead::Nonce::try_assume_unique_for_key(nonce).map_err(|_| ShieldError::Crypto)?;
+        let nonce_sequence = OneNonceSequence::new(nonce);
+        let mut opening_key = OpeningKey::new(unbound_key, nonce_sequence);
+
+        let plaintext = opening_key
+            .open_in_place(aead::Aad::from(aad), in_out)
+            .map_err(|_| ShieldError::Tamper)?;
+        Ok(plaintext.len())
+    }
+}
+
+fn algorithm(cipher: Cipher) -> &'static aead::Algorithm {
+    match cipher {
+        Cipher::ChaCha20Poly1305 => &aead::CHACHA20_POLY1305,
+        Cipher::Aes256Gcm => &aead::AES_256_GCM,
+        Cipher::XChaCha20Poly1305 => unreachable!("handled by RustCrypto"),
+        Cipher::Cascade => unreachable!("handled by cascade"),
+    }
+}
+
+// Output length for HKDF expansion of an arbitrary number of bytes.
+struct OkmLen(usize);
+
+impl hkdf::KeyType for OkmLen {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+// This struct and following impls' are borrowed from Ring's tests.
+struct OneNonceSequence(Option<aead::Nonce>);
+
+impl OneNonceSequence {
+    fn new(nonce: aead::Nonce) -> Self {
+        Self(Some(nonce))
+    }
+}
+
+impl aead::NonceSequence for OneNonceSequence {
+    fn advance(&mut self) -> Result<aead::Nonce, ring::error::Unspecified> {
+        self.0.take().ok_or(ring::error::Unspecified)
+    }
+}
+
+// The key schedule is owned by ring and can't be wiped from here.
+fn hmac_sign(algorithm: hmac::Algorithm, key: &[u8], message: &[u8]) -> hmac::Tag {
+    hmac::sign(&hmac::Key::new(algorithm, key), message)
+}
@@ -0,0 +1,54 @@
+//! AEAD on top of the RustCrypto traits, shared by the RustCrypto backend and
+//! by the ciphers `ring` doesn't implement.
+
+use chacha20poly1305::aead::generic_array::typenum::Unsigned;
+use chacha20poly1305::aead::generic_array::GenericArray;
+use chacha20poly1305::aead::{AeadCore, AeadInPlace, KeyInit};
+
+use super::{KEY_LEN, TAG_LEN};
+use crate::ShieldError;
+
+pub(super) fn seal<C: KeyInit + AeadInPlace>(
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    in_out: &mut [u8],
+    tag: &mut [u8],
+) -> Result<(), ShieldError> {
+    let cipher = new_cipher::<C>(key, nonce)?;
+    let t = cipher
+        .encrypt_in_place_detached(GenericArray::from_slice(nonce), aad, in_out)
+        .map_err(|_| ShieldError::Crypto)?;
+    tag.copy_from_slice(&t);
+    Ok(())
+}
+
+pub(super) fn open<C: KeyInit + AeadInPlace>(
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    in_out: &mut [u8],
+) -> Result<usize, ShieldError> {
+    let cipher = new_cipher::<C>(key, nonce)?;
+    let len = in_out
+        .len()
+        .checked_sub(TAG_LEN)
+        .ok_or(ShieldError::Tamper)?;
+    let (ciphertext, tag) = in_out.split_at_mut(len);
+    cipher
+        .decrypt_in_place_detached(
+            GenericArray::from_slice(nonce),
+            aad,
+            ciphertext,
+            GenericArray::from_slice(tag),
+        )
+        .map_err(|_| ShieldError::Tamper)?;
+    Ok(len)
+}
+
+fn new_cipher<C: KeyInit + AeadCore>(key: &[u8], nonce: &[u8]) -> Result<C, ShieldError> {
+    if key.len() != KEY_LEN || nonce.len() != C::NonceSize::USIZE {
+        return Err(ShieldError::Crypto);
+    }
+    C::new_from_slice(key).map_err(|_| ShieldError::Crypto)
+}
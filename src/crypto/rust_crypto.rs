@@ -0,0 +1,76 @@
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+#[cfg(feature = "totp")]
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+use super::rust_aead::{open, seal};
+use super::{cascade, Cipher, CryptoBackend};
+use crate::ShieldError;
+
+/// Cryptography provided by the pure Rust RustCrypto crates.
+pub(crate) struct RustCrypto;
+
+impl CryptoBackend for RustCrypto {
+    fn hkdf_sha512(ikm: &[u8], info: &[&[u8]], out: &mut [u8]) -> Result<(), ShieldError> {
+        Hkdf::<Sha512>::new(None, ikm)
+            .expand_multi_info(info, out)
+            .map_err(|_| ShieldError::Crypto)
+    }
+
+    fn sha256(data: &[u8]) -> [u8; 32] {
+        Sha256::digest(data).into()
+    }
+
+    #[cfg(feature = "totp")]
+    fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+        let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC takes any key length");
+        mac.update(message);
+        mac.finalize().into_bytes().into()
+    }
+
+    fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes any key length");
+        mac.update(message);
+        mac.finalize().into_bytes().into()
+    }
+
+    fn hmac_sha512(key: &[u8], message: &[u8]) -> [u8; 64] {
+        let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC takes any key length");
+        mac.update(message);
+        mac.finalize().into_bytes().into()
+    }
+
+    fn seal(
+        cipher: Cipher,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), ShieldError> {
+        match cipher {
+            Cipher::ChaCha20Poly1305 => seal::<ChaCha20Poly1305>(key, nonce, aad, in_out, tag),
+            Cipher::Aes256Gcm => seal::<Aes256Gcm>(key, nonce, aad, in_out, tag),
+            Cipher::XChaCha20Poly1305 => seal::<XChaCha20Poly1305>(key, nonce, aad, in_out, tag),
+            Cipher::Cascade => cascade::seal::<Self>(key, nonce, aad, in_out, tag),
+        }
+    }
+
+    fn open(
+        cipher: Cipher,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut [u8],
+    ) -> Result<usize, ShieldError> {
+        match cipher {
+            Cipher::ChaCha20Poly1305 => open::<ChaCha20Poly1305>(key, nonce, aad, in_out),
+            Cipher::Aes256Gcm => open::<Aes256Gcm>(key, nonce, aad, in_out),
+            Cipher::XChaCha20Poly1305 => open::<XChaCha20Poly1305>(key, nonce, aad, in_out),
+            Cipher::Cascade => cascade::open::<Self>(key, nonce, aad, in_out),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::crypto::{Crypto, CryptoBackend};
+use crate::{ShieldError, Shielded, ShieldedBuilder};
+
+impl Shielded {
+    /// Derive a subkey of `len` bytes from the content with HKDF-SHA512,
+    /// using an empty salt and `info` to tell subkeys apart. The subkey is
+    /// derived right into new `Shielded` memory with the default settings, so
+    /// neither the content nor the subkey is ever handed out. See
+    /// [`ShieldedBuilder::derive_subkey`](struct.ShieldedBuilder.html#method.derive_subkey)
+    /// for other settings.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut master = Shielded::builder().random(32).unwrap();
+    /// let mut db_key = master.derive_subkey(b"database", 32);
+    /// let mut api_key = master.derive_subkey(b"api", 32);
+    /// assert_ne!(db_key.unshield().as_ref(), api_key.unshield().as_ref());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication, the subkey can't
+    /// be shielded or `len` is longer than HKDF-SHA512 allows, 16320 bytes.
+    /// See [`try_derive_subkey`](#method.try_derive_subkey) for a fallible
+    /// version.
+    pub fn derive_subkey(&mut self, info: &[u8], len: usize) -> Shielded {
+        self.try_derive_subkey(info, len).expect("derive subkey")
+    }
+
+    /// Derive a subkey like [`derive_subkey`](#method.derive_subkey),
+    /// returning an error if the shielded memory fails authentication, the
+    /// subkey can't be shielded or `len` is too long.
+    pub fn try_derive_subkey(&mut self, info: &[u8], len: usize) -> Result<Shielded, ShieldError> {
+        derive_subkey(self, info, len, &ShieldedBuilder::new())
+    }
+}
+
+pub(crate) fn derive_subkey(
+    master: &mut Shielded,
+    info: &[u8],
+    len: usize,
+    builder: &ShieldedBuilder,
+) -> Result<Shielded, ShieldError> {
+    master.try_expose(|master| {
+        Shielded::with_content(len, builder, |memory| {
+            Crypto::hkdf_sha512(master, &[info], &mut memory[..len])
+        })
+    })?
+}
@@ -0,0 +1,21 @@
+//! Sources of the randomness prekeys and nonces are generated from.
+//!
+//! With the `std` feature the operating system's random number generator is
+//! used by default. An [`EntropySource`](trait.EntropySource.html) replaces it,
+//! e.g. with an HSM-backed generator, a hardware TRNG on embedded targets
+//! without an operating system, or a deterministic generator for reproducible
+//! tests.
+
+use core::fmt;
+
+use crate::ShieldError;
+
+/// A source of secure random bytes.
+///
+/// The security of the shielded memory rests on the prekeys and nonces being
+/// unpredictable. A deterministic source must never be used outside of tests.
+pub trait EntropySource: fmt::Debug + Send + Sync {
+    /// Fill `buf` entirely with random bytes, or fail with
+    /// [`ShieldError::Rng`](../enum.ShieldError.html#variant.Rng).
+    fn fill(&self, buf: &mut [u8]) -> Result<(), ShieldError>;
+}
@@ -0,0 +1,124 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::io;
+
+use crate::{Shielded, ShieldedBuilder};
+
+impl Shielded {
+    /// Construct a new `Shielded` memory holding the value of the environment
+    /// variable `name`, and scrub the variable from the environment of the
+    /// process. See
+    /// [`ShieldedBuilder::build_from_env`](struct.ShieldedBuilder.html#method.build_from_env)
+    /// for other settings.
+    ///
+    /// Secrets are often handed to a service in its environment, where they
+    /// otherwise stay in plaintext for the life of the process, e.g. in
+    /// `/proc/<pid>/environ` on Linux. On Unix the value is overwritten with
+    /// zeros where the environment keeps it, which is what `/proc` shows, and
+    /// the variable is then removed. Elsewhere it is only removed.
+    ///
+    /// Returns an [`io::ErrorKind::NotFound`](https://doc.rust-lang.org/std/io/enum.ErrorKind.html)
+    /// error if the variable isn't set, and
+    /// [`io::ErrorKind::InvalidData`](https://doc.rust-lang.org/std/io/enum.ErrorKind.html)
+    /// if its value isn't Unicode outside of Unix. Errors of the shielded
+    /// memory are returned as `io::ErrorKind::Other` wrapping a
+    /// [`ShieldError`](enum.ShieldError.html), and leave the variable set.
+    ///
+    /// ```
+    /// use std::env;
+    ///
+    /// use shielded::Shielded;
+    ///
+    /// env::set_var("API_TOKEN", "hunter2");
+    /// let mut token = unsafe { Shielded::from_env("API_TOKEN") }.unwrap();
+    /// assert_eq!(b"hunter2", token.unshield().as_ref());
+    /// assert!(env::var_os("API_TOKEN").is_none());
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// On Unix the value is overwritten where the environment keeps it, so it
+    /// must be writable: part of the environment the process was started
+    /// with, or set with `setenv` or
+    /// [`env::set_var`](https://doc.rust-lang.org/std/env/fn.set_var.html).
+    /// A value placed by C code with `putenv` in read-only memory, e.g. a
+    /// string literal, crashes the process.
+    ///
+    /// Like [`env::remove_var`](https://doc.rust-lang.org/std/env/fn.remove_var.html),
+    /// this must not race with other threads reading or writing the
+    /// environment, so call it early, e.g. at the start of `main`.
+    pub unsafe fn from_env<K: AsRef<OsStr>>(name: K) -> io::Result<Self> {
+        unsafe { Self::builder().build_from_env(name) }
+    }
+}
+
+// Safety: see `Shielded::from_env`.
+pub(crate) unsafe fn take_env(name: &OsStr, builder: ShieldedBuilder) -> io::Result<Shielded> {
+    let value = env::var_os(name)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "environment variable not set"))?;
+    let shielded = builder
+        .build(into_bytes(value)?)
+        .map_err(io::Error::other)?;
+    unsafe { scrub(name) };
+    env::remove_var(name);
+    Ok(shielded)
+}
+
+#[cfg(unix)]
+fn into_bytes(value: OsString) -> io::Result<Vec<u8>> {
+    use std::os::unix::ffi::OsStringExt;
+
+    Ok(value.into_vec())
+}
+
+#[cfg(not(unix))]
+fn into_bytes(value: OsString) -> io::Result<Vec<u8>> {
+    value.into_string().map(String::into_bytes).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "environment variable not Unicode",
+        )
+    })
+}
+
+// Overwrite the value of every `name=value` entry in the environment with
+// zeros in-place. Every such entry must be writable.
+#[cfg(unix)]
+unsafe fn scrub(name: &OsStr) {
+    use core::ptr;
+    use std::os::unix::ffi::OsStrExt;
+
+    #[cfg(not(target_vendor = "apple"))]
+    extern "C" {
+        static mut environ: *mut *mut libc::c_char;
+    }
+
+    let name = name.as_bytes();
+    // The environment is a null-terminated array of pointers to
+    // NUL-terminated strings, which nothing changes concurrently.
+    unsafe {
+        #[cfg(target_vendor = "apple")]
+        let mut entry = *libc::_NSGetEnviron();
+        #[cfg(not(target_vendor = "apple"))]
+        let mut entry = ptr::addr_of!(environ).read();
+        if entry.is_null() {
+            return;
+        }
+        while !(*entry).is_null() {
+            let var = (*entry).cast::<u8>();
+            let len = libc::strlen(*entry);
+            let matches = len > name.len()
+                && (0..name.len()).all(|i| *var.add(i) == name[i])
+                && *var.add(name.len()) == b'=';
+            if matches {
+                for i in name.len() + 1..len {
+                    ptr::write_volatile(var.add(i), 0);
+                }
+            }
+            entry = entry.add(1);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+unsafe fn scrub(_name: &OsStr) {}
@@ -0,0 +1,259 @@
+//! Export of the content of shielded memory, encrypted under a key-encryption
+//! key (KEK) of the caller, and import of it. Keys wrapped with shielded
+//! memory as the KEK use the same format.
+//!
+//! The exported format, version 1, is:
+//!
+//! ```text
+//! magic "shld" | version 1 | cipher | salt | nonce | ciphertext | tag
+//! ```
+//!
+//! The ciphertext is the content encrypted under a key derived from the KEK
+//! and the random 32-byte salt with HKDF-SHA512, with everything before it as
+//! associated data. The tag of a cascade is both of its tags.
+//!
+//! The version tells later formats apart, see [`Format`](../enum.Format.html).
+
+use alloc::vec::Vec;
+
+use zeroize::Zeroize;
+
+use crate::crypto::{Crypto, CryptoBackend};
+use crate::entropy::EntropySource;
+use crate::mem::SecretBuf;
+use crate::{fill_random, Cipher, Key, ShieldError, Shielded, ShieldedBuilder};
+
+const MAGIC: &[u8] = b"shld";
+const SALT_LEN: usize = 32;
+const EXPORT_KEY_INFO: &[u8] = b"shielded 1 export key";
+
+/// Version of the format of exported memory and wrapped keys.
+///
+/// [`Shielded::export`](struct.Shielded.html#method.export) always writes
+/// [`Format::LATEST`](#associatedconstant.LATEST), and every version listed
+/// here keeps being imported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Format {
+    /// The first format. The key is derived from the KEK and a random salt of
+    /// every blob.
+    V1,
+}
+
+impl Format {
+    /// The format written by this version of the crate.
+    pub const LATEST: Format = Format::V1;
+
+    /// The format of `blob`, without decrypting it.
+    ///
+    /// Returns [`ShieldError::Encoding`](enum.ShieldError.html#variant.Encoding)
+    /// if `blob` isn't exported memory or of a format this version of the
+    /// crate doesn't know.
+    pub fn of(blob: &[u8]) -> Result<Format, ShieldError> {
+        if blob.len() < MAGIC.len() + 2 || &blob[..MAGIC.len()] != MAGIC {
+            return Err(ShieldError::Encoding);
+        }
+        match blob[MAGIC.len()] {
+            1 => Ok(Format::V1),
+            _ => Err(ShieldError::Encoding),
+        }
+    }
+
+    fn version(self) -> u8 {
+        match self {
+            Format::V1 => 1,
+        }
+    }
+}
+
+impl Shielded {
+    /// Export the content encrypted under the key-encryption key `kek`, e.g.
+    /// to persist it or hand it to another process. The prekey, nonce and
+    /// settings of this `Shielded` are not part of the export. Import it with
+    /// [`import`](#method.import).
+    ///
+    /// `kek` should be a uniformly random key of at least 32 bytes.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let kek = [7u8; 32];
+    /// let blob = Shielded::new(b"secret".to_vec()).export(&kek);
+    /// let mut imported = Shielded::import(&blob, &kek).unwrap();
+    /// assert_eq!(b"secret", imported.unshield().as_ref());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication or the content
+    /// can't be encrypted. See [`try_export`](#method.try_export) for a
+    /// fallible version.
+    pub fn export(&mut self, kek: &[u8]) -> Vec<u8> {
+        self.try_export(kek).expect("export memory")
+    }
+
+    /// Export the content encrypted under `kek` like
+    /// [`export`](#method.export), returning an error if the shielded memory
+    /// fails authentication or the content can't be encrypted.
+    pub fn try_export(&mut self, kek: &[u8]) -> Result<Vec<u8>, ShieldError> {
+        let (cipher, entropy) = (self.cipher, self.entropy.clone());
+        self.try_expose(|content| seal_blob(cipher, kek, entropy.as_deref(), content))?
+    }
+
+    /// Construct `Shielded` memory with the default settings holding the
+    /// content exported with [`export`](#method.export) under `kek`. See
+    /// [`ShieldedBuilder::import`](struct.ShieldedBuilder.html#method.import)
+    /// for other settings.
+    ///
+    /// Returns [`ShieldError::Encoding`](enum.ShieldError.html#variant.Encoding)
+    /// if `blob` isn't exported memory and
+    /// [`ShieldError::Tamper`](enum.ShieldError.html#variant.Tamper) if it was
+    /// exported under another KEK or has been modified.
+    pub fn import(blob: &[u8], kek: &[u8]) -> Result<Self, ShieldError> {
+        Self::builder().import(blob, kek)
+    }
+
+    /// Wrap `key` with the content as the key-encryption key, e.g. to keep
+    /// many data keys under one shielded root key. The content is only
+    /// decrypted for the wrapping and never handed out. Unwrap it with
+    /// [`unwrap_into_shielded`](#method.unwrap_into_shielded).
+    ///
+    /// The wrapped key has the format of [`export`](#method.export).
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut root = Shielded::builder().random(32).unwrap();
+    /// let wrapped = root.wrap(b"data key");
+    /// let mut data_key = root.unwrap_into_shielded(&wrapped).unwrap();
+    /// assert_eq!(b"data key", data_key.unshield().as_ref());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication or the key can't be
+    /// encrypted. See [`try_wrap`](#method.try_wrap) for a fallible version.
+    pub fn wrap(&mut self, key: &[u8]) -> Vec<u8> {
+        self.try_wrap(key).expect("wrap key")
+    }
+
+    /// Wrap `key` like [`wrap`](#method.wrap), returning an error if the
+    /// shielded memory fails authentication or the key can't be encrypted.
+    pub fn try_wrap(&mut self, key: &[u8]) -> Result<Vec<u8>, ShieldError> {
+        let (cipher, entropy) = (self.cipher, self.entropy.clone());
+        self.try_expose(|kek| seal_blob(cipher, kek, entropy.as_deref(), key))?
+    }
+
+    /// Unwrap a key wrapped with [`wrap`](#method.wrap) right into new
+    /// `Shielded` memory with the default settings. See
+    /// [`ShieldedBuilder::unwrap_into_shielded`](struct.ShieldedBuilder.html#method.unwrap_into_shielded)
+    /// for other settings.
+    ///
+    /// Fails like [`import`](#method.import), or if the shielded memory fails
+    /// authentication.
+    pub fn unwrap_into_shielded(&mut self, wrapped: &[u8]) -> Result<Shielded, ShieldError> {
+        ShieldedBuilder::new().unwrap_into_shielded(self, wrapped)
+    }
+}
+
+pub(crate) fn import(
+    blob: &[u8],
+    kek: &[u8],
+    builder: &ShieldedBuilder,
+) -> Result<Shielded, ShieldError> {
+    open_blob(blob, kek, builder)
+}
+
+pub(crate) fn unwrap(
+    kek: &mut Shielded,
+    wrapped: &[u8],
+    builder: &ShieldedBuilder,
+) -> Result<Shielded, ShieldError> {
+    kek.try_expose(|kek| open_blob(wrapped, kek, builder))?
+}
+
+// Encrypt `content` under `kek` in the latest exported format.
+fn seal_blob(
+    cipher: Cipher,
+    kek: &[u8],
+    entropy: Option<&dyn EntropySource>,
+    content: &[u8],
+) -> Result<Vec<u8>, ShieldError> {
+    let header_len = header_len(cipher);
+
+    let tag_len = cipher.tag_len();
+    let mut blob = Vec::with_capacity(header_len + content.len() + tag_len);
+    blob.extend_from_slice(MAGIC);
+    blob.push(Format::LATEST.version());
+    blob.push(cipher.id());
+    blob.resize(header_len, 0);
+    // The salt and the nonce.
+    fill_random(entropy, &mut blob[MAGIC.len() + 2..])?;
+
+    let key = export_key(cipher, kek, &blob[..header_len])?;
+    blob.extend_from_slice(content);
+    blob.resize(blob.len() + tag_len, 0);
+
+    let (header, rest) = blob.split_at_mut(header_len);
+    let (in_out, tag) = rest.split_at_mut(rest.len() - tag_len);
+    let nonce = &header[header_len - cipher.nonce_len()..];
+    if let Err(e) = Crypto::seal(cipher, &key.0, nonce, header, in_out, tag) {
+        // Don't leave the content behind unencrypted.
+        blob.zeroize();
+        return Err(e);
+    }
+    Ok(blob)
+}
+
+// Decrypt `blob` in the exported format under `kek` right into new shielded
+// memory.
+fn open_blob(blob: &[u8], kek: &[u8], builder: &ShieldedBuilder) -> Result<Shielded, ShieldError> {
+    let (cipher, header_len) = parse_header(blob)?;
+    let (header, sealed) = blob.split_at(header_len);
+    let nonce = &header[header_len - cipher.nonce_len()..];
+    let key = export_key(cipher, kek, header)?;
+
+    // Decrypt right into the shielded memory, so the content never exists
+    // unencrypted anywhere else. The memory has room for the tags of the
+    // cipher of the builder, so a blob with longer tags is decrypted in
+    // scratch memory first, which is wiped when dropped.
+    let len = sealed.len() - cipher.tag_len();
+    Shielded::with_content(len, builder, |memory| {
+        if memory.len() >= sealed.len() {
+            let in_out = &mut memory[..sealed.len()];
+            in_out.copy_from_slice(sealed);
+            let _ = Crypto::open(cipher, &key.0, nonce, header, in_out)?;
+        } else {
+            let mut scratch = SecretBuf::new(sealed.len(), builder.memory_options());
+            scratch.copy_from_slice(sealed);
+            let _ = Crypto::open(cipher, &key.0, nonce, header, &mut scratch)?;
+            memory[..len].copy_from_slice(&scratch[..len]);
+        }
+        Ok(())
+    })
+}
+
+// The cipher and header length of `blob`, checking that it is of a known
+// format and long enough for the header and the tag.
+fn parse_header(blob: &[u8]) -> Result<(Cipher, usize), ShieldError> {
+    let _ = Format::of(blob)?;
+    let cipher = Cipher::from_id(blob[MAGIC.len() + 1]).ok_or(ShieldError::Encoding)?;
+    let header_len = header_len(cipher);
+    if blob.len() < header_len + cipher.tag_len() {
+        return Err(ShieldError::Encoding);
+    }
+    Ok((cipher, header_len))
+}
+
+fn header_len(cipher: Cipher) -> usize {
+    MAGIC.len() + 2 + SALT_LEN + cipher.nonce_len()
+}
+
+// Derive the key of `cipher` from the KEK and the salt in `header`. A
+// cascade derives both of its keys from the one KEK.
+fn export_key(cipher: Cipher, kek: &[u8], header: &[u8]) -> Result<Key, ShieldError> {
+    let mut key = Key::new(cipher.key_len());
+    let salt = &header[MAGIC.len() + 2..][..SALT_LEN];
+    Crypto::hkdf_sha512(kek, &[EXPORT_KEY_INFO, salt], &mut key.0)?;
+    Ok(key)
+}
@@ -0,0 +1,262 @@
+//! C API, so C and C++ programs can keep their secrets in shielded memory
+//! instead of reimplementing the scheme. Requires the `ffi` feature.
+//!
+//! Build the crate as a static or dynamic library, e.g. with
+//! `cargo rustc --release --features ffi --crate-type staticlib`, and include
+//! `include/shielded.h`.
+//!
+//! A handle returned by [`shielded_new`](fn.shielded_new.html) is owned by the
+//! caller until it is passed to [`shielded_free`](fn.shielded_free.html), and
+//! must not be used from two threads at the same time. The functions which can
+//! fail return [`SHIELDED_OK`](constant.SHIELDED_OK.html) or one of the
+//! negative `SHIELDED_ERR_*` codes, which
+//! [`shielded_strerror`](fn.shielded_strerror.html) describes.
+//!
+//! ```c
+//! #include <stdio.h>
+//! #include "shielded.h"
+//!
+//! static void print(void *ctx, const uint8_t *data, size_t len) {
+//!     fwrite(data, 1, len, (FILE *)ctx);
+//! }
+//!
+//! int main(void) {
+//!     shielded_t *shielded;
+//!     int err = shielded_new((const uint8_t *)"secret", 6, &shielded);
+//!     if (err == SHIELDED_OK) {
+//!         err = shielded_expose(shielded, print, stdout);
+//!         shielded_free(shielded);
+//!     }
+//!     if (err != SHIELDED_OK) {
+//!         fprintf(stderr, "%s\n", shielded_strerror(err));
+//!     }
+//!     return err;
+//! }
+//! ```
+
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+
+use crate::{ShieldError, Shielded};
+
+/// Success.
+pub const SHIELDED_OK: c_int = 0;
+/// See [`ShieldError::Tamper`](../enum.ShieldError.html#variant.Tamper).
+pub const SHIELDED_ERR_TAMPER: c_int = -1;
+/// See [`ShieldError::Rng`](../enum.ShieldError.html#variant.Rng).
+pub const SHIELDED_ERR_RNG: c_int = -2;
+/// See [`ShieldError::Crypto`](../enum.ShieldError.html#variant.Crypto).
+pub const SHIELDED_ERR_CRYPTO: c_int = -3;
+/// See [`ShieldError::Lock`](../enum.ShieldError.html#variant.Lock).
+pub const SHIELDED_ERR_LOCK: c_int = -4;
+/// See [`ShieldError::Backend`](../enum.ShieldError.html#variant.Backend).
+pub const SHIELDED_ERR_BACKEND: c_int = -5;
+/// See [`ShieldError::Encoding`](../enum.ShieldError.html#variant.Encoding).
+pub const SHIELDED_ERR_ENCODING: c_int = -6;
+/// See [`ShieldError::Expired`](../enum.ShieldError.html#variant.Expired).
+pub const SHIELDED_ERR_EXPIRED: c_int = -7;
+/// See
+/// [`ShieldError::PolicyViolation`](../enum.ShieldError.html#variant.PolicyViolation).
+pub const SHIELDED_ERR_POLICY_VIOLATION: c_int = -8;
+/// See [`ShieldError::UnknownKey`](../enum.ShieldError.html#variant.UnknownKey).
+pub const SHIELDED_ERR_UNKNOWN_KEY: c_int = -9;
+/// See [`ShieldError::Denied`](../enum.ShieldError.html#variant.Denied).
+pub const SHIELDED_ERR_DENIED: c_int = -10;
+/// See [`ShieldError::Disconnected`](../enum.ShieldError.html#variant.Disconnected).
+pub const SHIELDED_ERR_DISCONNECTED: c_int = -11;
+/// A required pointer argument was null.
+pub const SHIELDED_ERR_NULL: c_int = -12;
+/// The call panicked, e.g. on running out of memory. The handle stays valid,
+/// but its memory may have been wiped.
+pub const SHIELDED_ERR_PANIC: c_int = -13;
+/// See [`ShieldError::Unprotected`](../enum.ShieldError.html#variant.Unprotected).
+pub const SHIELDED_ERR_UNPROTECTED: c_int = -14;
+/// See [`ShieldError::Poisoned`](../enum.ShieldError.html#variant.Poisoned).
+pub const SHIELDED_ERR_POISONED: c_int = -15;
+/// See [`ShieldError::Unsupported`](../enum.ShieldError.html#variant.Unsupported).
+pub const SHIELDED_ERR_UNSUPPORTED: c_int = -16;
+
+/// Opaque handle to [`Shielded`](../struct.Shielded.html) memory, `shielded_t`
+/// in C.
+pub struct ShieldedHandle(Shielded);
+
+/// Called with the unshielded content and the caller's context.
+pub type ExposeFn = extern "C" fn(ctx: *mut c_void, data: *const u8, len: usize);
+
+/// Called with the unshielded content for modification and the caller's
+/// context.
+pub type ExposeMutFn = extern "C" fn(ctx: *mut c_void, data: *mut u8, len: usize);
+
+/// Shield a copy of the `len` bytes at `data` and store the handle to it in
+/// `out`. `data` may be null if `len` is zero. The caller should wipe its own
+/// copy afterwards.
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes and `out` for a write of a
+/// pointer.
+#[no_mangle]
+pub unsafe extern "C" fn shielded_new(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut ShieldedHandle,
+) -> c_int {
+    if out.is_null() || (data.is_null() && len > 0) {
+        return SHIELDED_ERR_NULL;
+    }
+    let content = if len == 0 {
+        &[][..]
+    } else {
+        slice::from_raw_parts(data, len)
+    };
+    call(|| {
+        // Copy right into the memory to be shielded, not through the heap.
+        let shielded = Shielded::with_content(len, &Shielded::builder(), |memory| {
+            memory[..len].copy_from_slice(content);
+            Ok(())
+        })?;
+        *out = Box::into_raw(Box::new(ShieldedHandle(shielded)));
+        Ok(())
+    })
+}
+
+/// Wipe and free the shielded memory. Does nothing if `handle` is null.
+///
+/// # Safety
+///
+/// `handle` must be null or returned by
+/// [`shielded_new`](fn.shielded_new.html) and not freed yet. It must not be
+/// used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn shielded_free(handle: *mut ShieldedHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Length of the content in bytes, zero if `handle` is null.
+///
+/// # Safety
+///
+/// `handle` must be null or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn shielded_len(handle: *const ShieldedHandle) -> usize {
+    handle.as_ref().map_or(0, |handle| handle.0.len())
+}
+
+/// Unshield the memory, call `f` with `ctx` and the content, and shield the
+/// memory again. The content is only valid for the duration of the call.
+///
+/// # Safety
+///
+/// `handle` must be a valid handle. `f` must not keep the content nor unwind.
+#[no_mangle]
+pub unsafe extern "C" fn shielded_expose(
+    handle: *mut ShieldedHandle,
+    f: Option<ExposeFn>,
+    ctx: *mut c_void,
+) -> c_int {
+    let (handle, f) = match (handle.as_mut(), f) {
+        (Some(handle), Some(f)) => (handle, f),
+        _ => return SHIELDED_ERR_NULL,
+    };
+    call(|| {
+        handle
+            .0
+            .try_expose(|content| f(ctx, content.as_ptr(), content.len()))
+    })
+}
+
+/// Like [`shielded_expose`](fn.shielded_expose.html), but `f` may modify the
+/// content in place.
+///
+/// # Safety
+///
+/// `handle` must be a valid handle. `f` must not keep the content nor unwind.
+#[no_mangle]
+pub unsafe extern "C" fn shielded_expose_mut(
+    handle: *mut ShieldedHandle,
+    f: Option<ExposeMutFn>,
+    ctx: *mut c_void,
+) -> c_int {
+    let (handle, f) = match (handle.as_mut(), f) {
+        (Some(handle), Some(f)) => (handle, f),
+        _ => return SHIELDED_ERR_NULL,
+    };
+    call(|| {
+        handle
+            .0
+            .try_expose_mut(|content| f(ctx, content.as_mut_ptr(), content.len()))
+    })
+}
+
+/// Check that the shielded memory is intact without exposing it.
+///
+/// # Safety
+///
+/// `handle` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn shielded_verify(handle: *mut ShieldedHandle) -> c_int {
+    match handle.as_mut() {
+        Some(handle) => call(|| handle.0.verify()),
+        None => SHIELDED_ERR_NULL,
+    }
+}
+
+/// A static, NUL-terminated description of the result `code`.
+#[no_mangle]
+pub extern "C" fn shielded_strerror(code: c_int) -> *const c_char {
+    let msg: &'static [u8] = match code {
+        SHIELDED_OK => b"success\0",
+        SHIELDED_ERR_TAMPER => b"shielded memory failed authentication\0",
+        SHIELDED_ERR_RNG => b"secure random number generator failed\0",
+        SHIELDED_ERR_CRYPTO => b"cryptographic operation failed\0",
+        SHIELDED_ERR_LOCK => b"failed to lock memory\0",
+        SHIELDED_ERR_BACKEND => b"prekey backend failed\0",
+        SHIELDED_ERR_ENCODING => b"failed to encode or decode value\0",
+        SHIELDED_ERR_EXPIRED => b"shielded memory has expired\0",
+        SHIELDED_ERR_POLICY_VIOLATION => b"exposure policy violated\0",
+        SHIELDED_ERR_UNKNOWN_KEY => b"unknown key\0",
+        SHIELDED_ERR_DENIED => b"request denied\0",
+        SHIELDED_ERR_DISCONNECTED => b"agent has stopped\0",
+        SHIELDED_ERR_NULL => b"null pointer argument\0",
+        SHIELDED_ERR_PANIC => b"internal error\0",
+        SHIELDED_ERR_UNPROTECTED => b"required memory protection unavailable\0",
+        SHIELDED_ERR_POISONED => b"shielded memory is poisoned\0",
+        _ => b"unknown error\0",
+    };
+    msg.as_ptr().cast()
+}
+
+// Run `f`, turning its error or panic into a result code. Panics must not
+// unwind into C.
+fn call<F>(f: F) -> c_int
+where
+    F: FnOnce() -> Result<(), ShieldError>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => SHIELDED_OK,
+        Ok(Err(e)) => error_code(e),
+        Err(_) => SHIELDED_ERR_PANIC,
+    }
+}
+
+fn error_code(e: ShieldError) -> c_int {
+    match e {
+        ShieldError::Tamper => SHIELDED_ERR_TAMPER,
+        ShieldError::Rng => SHIELDED_ERR_RNG,
+        ShieldError::Crypto => SHIELDED_ERR_CRYPTO,
+        ShieldError::Lock => SHIELDED_ERR_LOCK,
+        ShieldError::Backend => SHIELDED_ERR_BACKEND,
+        ShieldError::Encoding => SHIELDED_ERR_ENCODING,
+        ShieldError::Expired => SHIELDED_ERR_EXPIRED,
+        ShieldError::PolicyViolation => SHIELDED_ERR_POLICY_VIOLATION,
+        ShieldError::UnknownKey => SHIELDED_ERR_UNKNOWN_KEY,
+        ShieldError::Denied => SHIELDED_ERR_DENIED,
+        ShieldError::Disconnected => SHIELDED_ERR_DISCONNECTED,
+        ShieldError::Unprotected => SHIELDED_ERR_UNPROTECTED,
+        ShieldError::Poisoned => SHIELDED_ERR_POISONED,
+        ShieldError::Unsupported => SHIELDED_ERR_UNSUPPORTED,
+    }
+}
@@ -0,0 +1,117 @@
+//! Files holding the content of shielded memory, protected by a passphrase.
+//!
+//! The file format is:
+//!
+//! ```text
+//! magic "shlp" | version 1 | m_cost | t_cost | p_cost | salt | exported memory
+//! ```
+//!
+//! The memory is exported under a key-encryption key derived from the
+//! passphrase with Argon2id, using the salt and the cost parameters as 32-bit
+//! big-endian integers.
+
+use std::convert::TryInto;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+
+use crate::crypto::KEY_LEN;
+use crate::{fill_random, Key, ShieldError, Shielded, ShieldedBuilder};
+
+const MAGIC: &[u8] = b"shlp";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 3 * 4 + SALT_LEN;
+
+impl Shielded {
+    /// Write the content to the file at `path`, encrypted under a key derived
+    /// from `passphrase` with Argon2id. The file is replaced if it exists,
+    /// and created readable by its owner only on Unix. Read it back with
+    /// [`open_from_file`](#method.open_from_file).
+    ///
+    /// Errors of the shielded memory are returned as
+    /// [`io::ErrorKind::InvalidData`](https://doc.rust-lang.org/std/io/enum.ErrorKind.html)
+    /// wrapping a [`ShieldError`](enum.ShieldError.html).
+    pub fn seal_to_file<P: AsRef<Path>>(&mut self, path: P, passphrase: &[u8]) -> io::Result<()> {
+        let params = Params::default();
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        header.extend_from_slice(&params.m_cost().to_be_bytes());
+        header.extend_from_slice(&params.t_cost().to_be_bytes());
+        header.extend_from_slice(&params.p_cost().to_be_bytes());
+        header.resize(HEADER_LEN, 0);
+        fill_random(
+            self.entropy.as_deref(),
+            &mut header[HEADER_LEN - SALT_LEN..],
+        )
+        .map_err(io_error)?;
+
+        let kek = passphrase_key(passphrase, &header).map_err(io_error)?;
+        let blob = self.try_export(&kek.0).map_err(io_error)?;
+
+        let mut options = OpenOptions::new();
+        let _ = options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            let _ = options.mode(0o600);
+        }
+        let mut file = options.open(path)?;
+        file.write_all(&header)?;
+        file.write_all(&blob)?;
+        file.sync_all()
+    }
+
+    /// Construct `Shielded` memory with the default settings holding the
+    /// content of the file at `path` written by
+    /// [`seal_to_file`](#method.seal_to_file) with `passphrase`. See
+    /// [`ShieldedBuilder::open_from_file`](struct.ShieldedBuilder.html#method.open_from_file)
+    /// for other settings.
+    ///
+    /// A wrong passphrase or a modified file is reported as
+    /// [`io::ErrorKind::InvalidData`](https://doc.rust-lang.org/std/io/enum.ErrorKind.html)
+    /// wrapping [`ShieldError::Tamper`](enum.ShieldError.html#variant.Tamper).
+    pub fn open_from_file<P: AsRef<Path>>(path: P, passphrase: &[u8]) -> io::Result<Self> {
+        Self::builder().open_from_file(path, passphrase)
+    }
+}
+
+pub(crate) fn open_from_file(
+    path: &Path,
+    passphrase: &[u8],
+    builder: &ShieldedBuilder,
+) -> io::Result<Shielded> {
+    let data = fs::read(path)?;
+    if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC || data[MAGIC.len()] != VERSION {
+        return Err(io_error(ShieldError::Encoding));
+    }
+
+    let (header, blob) = data.split_at(HEADER_LEN);
+    let kek = passphrase_key(passphrase, header).map_err(io_error)?;
+    crate::export::import(blob, &kek.0, builder).map_err(io_error)
+}
+
+// Derive the key-encryption key from `passphrase` with the parameters and
+// salt in `header`.
+fn passphrase_key(passphrase: &[u8], header: &[u8]) -> Result<Key, ShieldError> {
+    let param = |i: usize| {
+        let start = MAGIC.len() + 1 + 4 * i;
+        u32::from_be_bytes(header[start..start + 4].try_into().expect("4 bytes"))
+    };
+    let params = Params::new(param(0), param(1), param(2), Some(KEY_LEN))
+        .map_err(|_| ShieldError::Encoding)?;
+    let salt = &header[HEADER_LEN - SALT_LEN..];
+
+    let mut key = Key::new(KEY_LEN);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase, salt, &mut key.0)
+        .map_err(|_| ShieldError::Crypto)?;
+    Ok(key)
+}
+
+fn io_error(e: ShieldError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
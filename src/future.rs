@@ -0,0 +1,60 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use crate::{ShieldError, Shielded};
+
+/// Future returned by [`Shielded::expose_async`](struct.Shielded.html#method.expose_async).
+#[must_use = "futures do nothing unless polled"]
+pub struct ExposeAsync<'a, F>(TryExposeAsync<'a, F>);
+
+impl<'a, F> ExposeAsync<'a, F> {
+    pub(crate) fn new(shielded: &'a mut Shielded, f: F) -> Self {
+        ExposeAsync(TryExposeAsync::new(shielded, f))
+    }
+}
+
+impl<'a, R, F> Future for ExposeAsync<'a, F>
+where
+    F: FnMut(&[u8], &mut Context<'_>) -> Poll<R> + Unpin,
+{
+    type Output = R;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<R> {
+        Pin::new(&mut self.0)
+            .poll(cx)
+            .map(|result| result.expect("unshield memory"))
+    }
+}
+
+/// Future returned by
+/// [`Shielded::try_expose_async`](struct.Shielded.html#method.try_expose_async).
+#[must_use = "futures do nothing unless polled"]
+pub struct TryExposeAsync<'a, F> {
+    shielded: &'a mut Shielded,
+    f: F,
+}
+
+impl<'a, F> TryExposeAsync<'a, F> {
+    pub(crate) fn new(shielded: &'a mut Shielded, f: F) -> Self {
+        TryExposeAsync { shielded, f }
+    }
+}
+
+impl<'a, R, F> Future for TryExposeAsync<'a, F>
+where
+    F: FnMut(&[u8], &mut Context<'_>) -> Poll<R> + Unpin,
+{
+    type Output = Result<R, ShieldError>;
+
+    // The memory is unshielded only for the duration of a single poll, and
+    // shielded again by the guard before returning, even on `Pending`.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let unshielded = match this.shielded.try_unshield() {
+            Ok(unshielded) => unshielded,
+            Err(e) => return Poll::Ready(Err(e)),
+        };
+        (this.f)(unshielded.as_ref(), cx).map(Ok)
+    }
+}
@@ -0,0 +1,299 @@
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use zeroize::Zeroize;
+
+use crate::layout::Layout;
+use crate::mem::SecretBuf;
+use crate::padding::Padding;
+use crate::{
+    seal_chunk, with_authenticated, ChunkKey, LockMode, ShieldError, Shielded, ShieldedBuilder,
+    UnShieldedRange,
+};
+
+impl Shielded {
+    /// Construct a new `Shielded` memory holding everything read from
+    /// `reader`. The data is read right into memory which is locked according
+    /// to the builder and excluded from core dumps, so it never passes
+    /// through an ordinary `Vec` to be wiped by the caller. See
+    /// [`ShieldedBuilder::build_from_reader`](struct.ShieldedBuilder.html#method.build_from_reader)
+    /// for other settings.
+    ///
+    /// Errors of the shielded memory are returned as
+    /// [`io::ErrorKind::Other`](https://doc.rust-lang.org/std/io/enum.ErrorKind.html)
+    /// wrapping a [`ShieldError`](enum.ShieldError.html).
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut shielded = Shielded::from_reader(&b"secret"[..]).unwrap();
+    /// assert_eq!(b"secret", shielded.unshield().as_ref());
+    /// ```
+    pub fn from_reader<R: Read>(reader: R) -> io::Result<Self> {
+        Self::builder().build_from_reader(reader)
+    }
+
+    /// Construct a new `Shielded` memory holding the content of the file at
+    /// `path`, read like [`from_reader`](#method.from_reader) does. See
+    /// [`ShieldedBuilder::build_from_file`](struct.ShieldedBuilder.html#method.build_from_file)
+    /// for other settings.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::builder().build_from_file(path)
+    }
+}
+
+pub(crate) fn read_to_shielded<R: Read>(
+    reader: R,
+    builder: ShieldedBuilder,
+) -> io::Result<Shielded> {
+    let mut buffer = builder.buffer();
+    let _ = buffer.read_from(reader)?;
+    buffer.finish().map_err(io::Error::other)
+}
+
+// Chunk size of streamed memory, unless the builder sets one.
+pub(crate) const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Writes data into [`Shielded`](struct.Shielded.html) memory, encrypting it
+/// chunk by chunk as it arrives.
+///
+/// At most one chunk of the written data is kept unencrypted at any time,
+/// so large secrets never exist decrypted as a whole. Create it with
+/// [`ShieldedBuilder::writer`](struct.ShieldedBuilder.html#method.writer) and
+/// call [`finish`](#method.finish) to get the `Shielded` memory.
+///
+/// ```
+/// use std::io::Write;
+///
+/// use shielded::Shielded;
+///
+/// let mut writer = Shielded::builder().writer().unwrap();
+/// writer.write_all(b"hello ").unwrap();
+/// writer.write_all(b"world").unwrap();
+/// let mut shielded = writer.finish().unwrap();
+/// assert_eq!(b"hello world", shielded.unshield().as_ref());
+/// ```
+pub struct ShieldedWriter {
+    shielded: Shielded,
+    key: ChunkKey,
+    chunk_size: usize,
+    padding: Padding,
+    // The plaintext of the chunk being written, `pending` bytes long.
+    chunk: SecretBuf,
+    pending: usize,
+    // Total length of the plaintext written so far, including `pending`.
+    len: usize,
+}
+
+impl ShieldedWriter {
+    pub(crate) fn new(builder: &ShieldedBuilder) -> Result<Self, ShieldError> {
+        let chunk_size = builder.chunk_size.unwrap_or(STREAM_CHUNK_SIZE);
+        let builder = builder.clone().chunk_size(chunk_size);
+
+        let mut shielded =
+            Shielded::with_memory(SecretBuf::new(0, builder.memory_options()), &builder)?;
+        let chunk = SecretBuf::new(chunk_size, shielded.memory.options());
+        if builder.lock == LockMode::Required && !chunk.is_locked() {
+            return Err(ShieldError::Lock);
+        }
+        let key = shielded.rekey()?;
+
+        Ok(Self {
+            shielded,
+            key,
+            chunk_size,
+            padding: builder.padding,
+            chunk,
+            pending: 0,
+            len: 0,
+        })
+    }
+
+    /// Encrypt the remaining data and return the `Shielded` memory holding
+    /// everything written.
+    pub fn finish(mut self) -> Result<Shielded, ShieldError> {
+        let padded_len = self.padding.padded_len(self.len);
+        let mut padding = self.padding.padding(self.len);
+        while self.len < padded_len {
+            let _ = self.append(padded_len - self.len, |chunk| {
+                chunk.iter_mut().zip(&mut padding).for_each(|(b, p)| *b = p)
+            })?;
+        }
+
+        // Even empty memory has a chunk, and a partial chunk is sealed last.
+        if self.pending > 0 || self.len == 0 {
+            self.seal_pending()?;
+        }
+        self.shielded.protect_prekey()?;
+
+        let layout = Layout::new(
+            self.len,
+            Some(self.chunk_size),
+            self.shielded.cipher.tag_len(),
+        );
+        debug_assert_eq!(self.shielded.memory.len(), layout.memory_len());
+
+        Ok(self.shielded)
+    }
+
+    // Append up to `len` bytes, as many as fit in the pending chunk, written
+    // by `fill`. The chunk is sealed once it is full. Returns the number of
+    // bytes appended.
+    fn append<F>(&mut self, len: usize, fill: F) -> Result<usize, ShieldError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let n = len.min(self.chunk_size - self.pending);
+        fill(&mut self.chunk[self.pending..self.pending + n]);
+        self.pending += n;
+        self.len += n;
+
+        if self.pending == self.chunk_size {
+            self.seal_pending()?;
+        }
+        Ok(n)
+    }
+
+    // Append the pending chunk to the shielded memory, encrypted.
+    fn seal_pending(&mut self) -> Result<(), ShieldError> {
+        let index = (self.len - self.pending) / self.chunk_size;
+        let layout = Layout::new(
+            self.len,
+            Some(self.chunk_size),
+            self.shielded.cipher.tag_len(),
+        );
+        let sealed = layout.sealed(index);
+
+        let memory = &mut self.shielded.memory;
+        if sealed.end > memory.capacity() {
+            // Grow geometrically, so streaming large secrets doesn't copy the
+            // memory over and over.
+            let mut grown = memory.resized(sealed.end.max(2 * memory.capacity()));
+            if self.shielded.lock == LockMode::Required && !grown.is_locked() {
+                return Err(ShieldError::Lock);
+            }
+            grown.set_len(memory.len());
+            *memory = grown;
+        }
+        memory.set_len(sealed.end);
+        memory[sealed.start..sealed.start + self.pending]
+            .copy_from_slice(&self.chunk[..self.pending]);
+        self.chunk[..self.pending].zeroize();
+        self.pending = 0;
+
+        let shielded = &mut self.shielded;
+        let (cipher, key, nonce) = (shielded.cipher, &self.key, &shielded.nonce.0);
+        let (aad, memory) = (&shielded.aad, &mut shielded.memory[sealed]);
+        shielded.prekey.with_key(key, |prekey| {
+            with_authenticated(prekey, aad, |authenticated| {
+                seal_chunk(cipher, key, nonce, prekey, authenticated, index, memory)
+            })
+        })
+    }
+}
+
+impl Write for ShieldedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.append(buf.len(), |chunk| {
+            chunk.copy_from_slice(&buf[..chunk.len()])
+        })
+        .map_err(io_error)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads the content of [`Shielded`](struct.Shielded.html) memory, decrypting
+/// it chunk by chunk.
+///
+/// Only the chunk being read is decrypted, into a copy of its own which is
+/// wiped once it has been consumed. Memory without a [chunk
+/// size](struct.ShieldedBuilder.html#method.chunk_size) is a single chunk and
+/// is decrypted as a whole. Returned by
+/// [`Shielded::reader`](struct.Shielded.html#method.reader).
+pub struct ShieldedReader<'a> {
+    shielded: &'a mut Shielded,
+    layout: Layout,
+    // Length of the content, found on the first read.
+    len: Option<usize>,
+    // The next chunk to decrypt.
+    index: usize,
+    chunk: Option<UnShieldedRange>,
+    // Bytes of `chunk` already read.
+    consumed: usize,
+}
+
+impl<'a> ShieldedReader<'a> {
+    pub(crate) fn new(shielded: &'a mut Shielded) -> Self {
+        let layout = shielded.layout();
+        Self {
+            shielded,
+            layout,
+            len: None,
+            index: 0,
+            chunk: None,
+            consumed: 0,
+        }
+    }
+}
+
+impl<'a> Read for ShieldedReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let exhausted = match &self.chunk {
+            Some(chunk) => self.consumed == chunk.len(),
+            None => true,
+        };
+        if exhausted {
+            // Wipe the consumed chunk before decrypting the next one.
+            self.chunk = None;
+            let len = match self.len {
+                Some(len) => len,
+                None => {
+                    self.shielded.begin_use().map_err(io_error)?;
+                    let len = self.shielded.content_len().map_err(io_error)?;
+                    self.len = Some(len);
+                    len
+                }
+            };
+            if self.index == self.layout.chunks() {
+                return Ok(0);
+            }
+            let range = self.layout.plaintext(self.index);
+            if range.start >= len {
+                return Ok(0);
+            }
+            let range = range.start..range.end.min(len);
+            let chunk = self.shielded.unshield_range_uncounted(range);
+            self.chunk = Some(chunk.map_err(io_error)?);
+            self.index += 1;
+            self.consumed = 0;
+        }
+
+        let chunk = match &self.chunk {
+            Some(chunk) => &chunk[self.consumed..],
+            None => return Ok(0),
+        };
+        let n = buf.len().min(chunk.len());
+        buf[..n].copy_from_slice(&chunk[..n]);
+        self.consumed += n;
+        Ok(n)
+    }
+}
+
+impl<'a> Drop for ShieldedReader<'a> {
+    fn drop(&mut self) {
+        if self.len.is_some() {
+            self.shielded.end_use();
+        }
+    }
+}
+
+fn io_error(e: ShieldError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
@@ -0,0 +1,156 @@
+//! Keys derived from a passphrase with Argon2id straight into shielded
+//! memory.
+//!
+//! The memory Argon2id works in, which holds values the key can be
+//! recomputed from, is allocated like the shielded memory and wiped
+//! afterwards, and the key is written right into the memory which is then
+//! shielded. scrypt isn't offered, as its implementation allocates its
+//! working memory itself, out of reach of the wipe.
+
+use core::slice;
+
+use argon2::{Algorithm, Argon2, Block, Params, Version};
+use zeroize::Zeroize;
+
+use crate::mem::SecretBuf;
+use crate::{LockMode, ShieldError, Shielded, ShieldedBuilder};
+
+/// The salt and Argon2id cost parameters for deriving a key from a
+/// passphrase with
+/// [`Shielded::from_passphrase`](struct.Shielded.html#method.from_passphrase).
+/// Requires the `passphrase` feature.
+///
+/// The costs default to the OWASP recommendation of 19 MiB of memory, 2
+/// passes and 1 lane, and the key to 32 bytes.
+///
+/// ```
+/// use shielded::KdfParams;
+///
+/// let params = KdfParams::new(b"per-user salt")
+///     .costs(64 * 1024, 3, 4)
+///     .output_len(64);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KdfParams {
+    salt: Vec<u8>,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    output_len: usize,
+}
+
+impl KdfParams {
+    /// Parameters with `salt` and the default costs. The salt should be
+    /// random, at least 16 bytes, and stored along with the costs to derive
+    /// the same key again. Argon2id requires at least 8 bytes.
+    pub fn new(salt: &[u8]) -> Self {
+        Self {
+            salt: salt.to_vec(),
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+            output_len: Params::DEFAULT_OUTPUT_LEN,
+        }
+    }
+
+    /// Set the memory in KiB, the number of passes and the number of lanes.
+    pub fn costs(mut self, m_cost: u32, t_cost: u32, p_cost: u32) -> Self {
+        self.m_cost = m_cost;
+        self.t_cost = t_cost;
+        self.p_cost = p_cost;
+        self
+    }
+
+    /// Set the length of the derived key in bytes, at least 4.
+    pub fn output_len(mut self, output_len: usize) -> Self {
+        self.output_len = output_len;
+        self
+    }
+}
+
+impl Shielded {
+    /// Construct a new `Shielded` memory holding the key derived from
+    /// `passphrase` with Argon2id and `params`, wiping `passphrase`. Requires
+    /// the `passphrase` feature. See
+    /// [`ShieldedBuilder::build_from_passphrase`](struct.ShieldedBuilder.html#method.build_from_passphrase)
+    /// for other settings.
+    ///
+    /// The key is written straight into the memory which is then shielded,
+    /// and the working memory of Argon2id is allocated like that memory, i.e.
+    /// locked according to the builder and excluded from core dumps, and
+    /// wiped afterwards. The key never exists in an unprotected buffer.
+    ///
+    /// ```
+    /// use shielded::{KdfParams, Shielded};
+    ///
+    /// let params = KdfParams::new(b"per-user salt").costs(1024, 1, 1);
+    /// let mut key = Shielded::from_passphrase(&mut b"hunter2".to_owned(), &params);
+    /// assert_eq!(32, key.len());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parameters are invalid or the memory can't be shielded.
+    /// See [`try_from_passphrase`](#method.try_from_passphrase) for a
+    /// fallible version.
+    pub fn from_passphrase(passphrase: &mut [u8], params: &KdfParams) -> Self {
+        Self::try_from_passphrase(passphrase, params).expect("shield new memory")
+    }
+
+    /// Construct a new `Shielded` memory holding the key derived from
+    /// `passphrase` like [`from_passphrase`](#method.from_passphrase),
+    /// returning [`ShieldError::Crypto`](enum.ShieldError.html#variant.Crypto)
+    /// if the parameters are invalid, or another error if the memory can't be
+    /// shielded. `passphrase` is wiped either way.
+    pub fn try_from_passphrase(
+        passphrase: &mut [u8],
+        params: &KdfParams,
+    ) -> Result<Self, ShieldError> {
+        Self::builder().build_from_passphrase(passphrase, params)
+    }
+}
+
+pub(crate) fn derive(
+    passphrase: &mut [u8],
+    params: &KdfParams,
+    builder: &ShieldedBuilder,
+) -> Result<Shielded, ShieldError> {
+    let result = derive_key(passphrase, params, builder);
+    passphrase.zeroize();
+    result
+}
+
+fn derive_key(
+    passphrase: &[u8],
+    params: &KdfParams,
+    builder: &ShieldedBuilder,
+) -> Result<Shielded, ShieldError> {
+    let argon2_params = Params::new(
+        params.m_cost,
+        params.t_cost,
+        params.p_cost,
+        Some(params.output_len),
+    )
+    .map_err(|_| ShieldError::Crypto)?;
+    let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params);
+
+    let blocks = argon2.params().block_count();
+    let mut memory = SecretBuf::new(blocks * Block::SIZE, builder.memory_options());
+    if builder.lock == LockMode::Required && !memory.is_locked() {
+        return Err(ShieldError::Lock);
+    }
+    // Aligned to 64 bytes at least, so aligned for blocks, and any bytes are
+    // a valid block.
+    let blocks = unsafe { slice::from_raw_parts_mut(memory.as_mut_ptr().cast::<Block>(), blocks) };
+
+    Shielded::with_content(params.output_len, builder, |key| {
+        argon2
+            .hash_password_into_with_memory(
+                passphrase,
+                &params.salt,
+                &mut key[..params.output_len],
+                blocks,
+            )
+            .map_err(|_| ShieldError::Crypto)
+    })
+}
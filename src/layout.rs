@@ -0,0 +1,96 @@
+//! Placement of the encrypted chunks in the shielded memory.
+//!
+//! The plaintext is split into chunks which are encrypted separately, so parts
+//! of it can be decrypted without touching the rest. Shielded, every chunk is
+//! followed by its encryption tag. Unshielded, the plaintext is contiguous at
+//! the start of the memory. Without a chunk size the whole plaintext is a
+//! single chunk.
+
+use core::ops::Range;
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Layout {
+    len: usize,
+    chunk_len: usize,
+    tag_len: usize,
+}
+
+impl Layout {
+    /// The layout of a plaintext of `len` bytes, with tags of `tag_len`
+    /// bytes.
+    pub(crate) fn new(len: usize, chunk_size: Option<usize>, tag_len: usize) -> Self {
+        Self {
+            len,
+            chunk_len: chunk_size.unwrap_or(len).max(1),
+            tag_len,
+        }
+    }
+
+    /// The layout of the plaintext kept in shielded memory of `memory_len`
+    /// bytes.
+    pub(crate) fn from_memory_len(
+        memory_len: usize,
+        chunk_size: Option<usize>,
+        tag_len: usize,
+    ) -> Self {
+        let len = match chunk_size {
+            None => memory_len - tag_len,
+            Some(chunk_len) => {
+                let sealed_len = chunk_len + tag_len;
+                let full = memory_len / sealed_len;
+                match memory_len % sealed_len {
+                    0 => full * chunk_len,
+                    rest => full * chunk_len + rest - tag_len,
+                }
+            }
+        };
+        Self::new(len, chunk_size, tag_len)
+    }
+
+    /// Length of the plaintext.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Number of chunks. Even an empty plaintext has one chunk, so it is still
+    /// authenticated.
+    pub(crate) fn chunks(&self) -> usize {
+        self.len.div_ceil(self.chunk_len).max(1)
+    }
+
+    /// Length of the shielded memory holding the plaintext and a tag for
+    /// every chunk.
+    pub(crate) fn memory_len(&self) -> usize {
+        self.chunks()
+            .checked_mul(self.tag_len)
+            .and_then(|tags| tags.checked_add(self.len))
+            .expect("capacity overflow")
+    }
+
+    /// Length of a shielded chunk, the ciphertext followed by its tag. The
+    /// last chunk may be shorter.
+    pub(crate) fn sealed_chunk_len(&self) -> usize {
+        self.chunk_len + self.tag_len
+    }
+
+    /// Position of chunk `index` in the plaintext.
+    pub(crate) fn plaintext(&self, index: usize) -> Range<usize> {
+        let start = index * self.chunk_len;
+        start..self.len.min(start + self.chunk_len)
+    }
+
+    /// Position of the ciphertext of chunk `index`, followed by its tag, in
+    /// the shielded memory.
+    pub(crate) fn sealed(&self, index: usize) -> Range<usize> {
+        let start = index * self.sealed_chunk_len();
+        start..start + self.plaintext(index).len() + self.tag_len
+    }
+
+    /// The chunks covering `range` of the plaintext.
+    pub(crate) fn covering(&self, range: &Range<usize>) -> Range<usize> {
+        if range.start >= range.end {
+            return 0..0;
+        }
+        range.start / self.chunk_len..(range.end - 1) / self.chunk_len + 1
+    }
+}
@@ -11,14 +11,42 @@
 //! decrypted on demand and encrypted again after memory is no longer needed.
 //!
 //! The memory protection is achieved by generating a 16kB secure random prekey
-//! which is then hashed with SHA512 to construct an encryption key for
-//! ChaCha20-Poly1305 cipher. This cipher is then used to encrypt the contents
-//! of memory in-place.
+//! from which HKDF-SHA512 derives an encryption key for ChaCha20-Poly1305
+//! cipher. This cipher is then used to encrypt the contents of memory in-place.
 //!
 //! Attackers must recover the entire prekey with high accuracy before they can
 //! attempt to decrypt the shielded memory, but the current generation of
 //! attacks have bit error rates that, when applied cumulatively to the entire
 //! prekey, make this unlikely.
+//!
+//! The prekey, nonce and encrypted memory are kept in pages of their own which
+//! are excluded from core dumps where the platform supports it, and can be
+//! locked into RAM with [`ShieldedBuilder`](struct.ShieldedBuilder.html).
+//!
+//! A process forked on Unix starts with a copy of the prekey and nonce of its
+//! parent. The first unshield operation in the child shields the memory again
+//! under a new prekey and nonce before decrypting it for use, so a child, e.g.
+//! a less privileged worker, doesn't keep using the keys of its parent.
+//!
+//! The cryptography comes from `ring` by default. Disabling default features
+//! and enabling the `rustcrypto` feature switches to the pure Rust RustCrypto
+//! crates instead, for targets where `ring` doesn't build.
+//!
+//! Without the default `std` feature the crate is `no_std` and only requires
+//! `alloc`, e.g. for embedded targets with no operating system. There is no
+//! system random number generator then, so an entropy source has to be passed
+//! to [`ShieldedBuilder::entropy`](struct.ShieldedBuilder.html#method.entropy).
+//! See the [`entropy`](entropy/index.html) module.
+//!
+//! On WebAssembly, with the `rustcrypto` feature, the memory is still
+//! encrypted at rest, but there are no pages to lock, guard or exclude from
+//! dumps. `wasm32-unknown-unknown` takes its random numbers from
+//! `crypto.getRandomValues` with the `js` feature, or from an entropy source
+//! without `std`. The browser has no clock, so neither
+//! [`ReshieldPolicy::After`](enum.ReshieldPolicy.html#variant.After) nor
+//! time limits nor audit events work there. There is no
+//! [`agent`](agent/index.html) nor `Rotator` on WebAssembly, which have threads
+//! of their own.
 
 #![forbid(
     anonymous_parameters,
@@ -33,109 +61,1838 @@
     variant_size_differences,
     warnings
 )]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+use core::ops::{Deref, DerefMut, Range};
+use core::task::{Context, Poll};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+
+// The agent runs on threads of its own, which WebAssembly doesn't have.
+#[cfg(all(feature = "std", not(target_family = "wasm")))]
+pub mod agent;
+mod arena;
+pub mod audit;
+pub mod backend;
+mod buffer;
+mod builder;
+#[cfg(feature = "std")]
+mod cell;
+mod crypto;
+mod derive;
+pub mod entropy;
+#[cfg(feature = "std")]
+mod env;
+mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "passphrase")]
+mod file;
+mod future;
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "passphrase")]
+mod kdf;
+mod layout;
+mod mac;
+mod mem;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+mod padding;
+#[cfg(feature = "bytemuck")]
+mod pod;
+mod prekey;
+mod protection;
+#[cfg(feature = "std")]
+pub mod registry;
+#[cfg(all(feature = "auto-rotate", not(target_family = "wasm")))]
+mod rotate;
+#[cfg(feature = "rustls")]
+pub mod rustls;
+#[cfg(feature = "secrecy")]
+pub mod secrecy;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod shamir;
+#[cfg(feature = "ed25519")]
+mod signing;
+mod small;
+mod store;
+mod string;
+#[cfg(feature = "totp")]
+mod totp;
+#[cfg(feature = "serde")]
+mod value;
+
+pub use arena::{ArenaHandle, ShieldedArena};
+pub use buffer::ShieldedBuffer;
+#[cfg(feature = "std")]
+pub use builder::ExposurePolicy;
+pub use builder::{LockMode, ReshieldPolicy, ShieldedBuilder};
+#[cfg(feature = "std")]
+pub use cell::ShieldedCell;
+pub use export::Format;
+pub use future::{ExposeAsync, TryExposeAsync};
+#[cfg(feature = "std")]
+pub use io::{ShieldedReader, ShieldedWriter};
+#[cfg(feature = "passphrase")]
+pub use kdf::KdfParams;
+pub use padding::Padding;
+#[cfg(feature = "bytemuck")]
+pub use pod::{ShieldedBox, UnShieldedBox};
+pub use protection::{ProtectionRequirements, ProtectionStatus};
+#[cfg(feature = "std")]
+pub use registry::wipe_all;
+#[cfg(all(feature = "auto-rotate", not(target_family = "wasm")))]
+pub use rotate::Rotator;
+#[cfg(feature = "ed25519")]
+pub use signing::ShieldedSigningKey;
+pub use small::{ShieldedSmall, UnShieldedSmall};
+pub use store::ShieldedStore;
+pub use string::{ShieldedString, UnShieldedString};
+#[cfg(feature = "totp")]
+pub use totp::{ShieldedTotp, TotpAlgorithm};
+#[cfg(feature = "serde")]
+pub use value::{ShieldedValue, UnShieldedValue};
+
+use audit::{Audit, AuditEvent};
+use backend::{Aead, Backend};
+use entropy::EntropySource;
+use mem::{BufOptions, SecretBuf, CANARY_LEN};
+use prekey::PreKey;
+
+pub use crypto::Cipher;
 
-use ring::aead::{self, BoundKey, OpeningKey, SealingKey, UnboundKey};
-use ring::digest;
-use ring::rand::{SecureRandom, SystemRandom};
+use crypto::{Crypto, CryptoBackend, KEY_LEN, MAX_NONCE_LEN};
+use layout::Layout;
+use padding::Unpad;
 
-use aead::CHACHA20_POLY1305 as SHIELD_CIPHER;
-use digest::SHA512 as SHIELD_PREKEY_HASH;
 const SHIELD_PREKEY_LEN: usize = 16 * 1024;
+const SHIELD_PREKEY_MIN_LEN: usize = 1024;
+
+// HKDF info string for the encryption key, followed by the caller's context.
+const SHIELD_KEY_INFO: &[u8] = b"shielded 1 encryption key";
+
+// HMAC message committing to the encryption key, see `commit`.
+const KEY_COMMITMENT_INFO: &[u8] = b"shielded 1 key commitment";
 
 // Used for allocations to mark allocated but not populated memory regions
 const MAGIC_BYTE: u8 = 0xDF;
 
-struct PreKey(Vec<u8>);
-struct Key(Vec<u8>);
-struct Nonce(Vec<u8>);
+// Shieldings under one prekey with a nonce sequence. The sequence counts in
+// the bytes of the nonce before the last eight, which `chunk_nonce` leaves to
+// the chunk index, so at least four bytes with every cipher.
+const NONCE_SEQUENCE_LEN: u64 = u32::MAX as u64;
+
+/// Errors returned by the fallible operations on [`Shielded`](struct.Shielded.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShieldError {
+    /// Authentication of the shielded memory failed on decryption. The
+    /// ciphertext, the prekey or the nonce has been modified since the memory
+    /// was shielded.
+    Tamper,
+    /// The system's secure random number generator failed.
+    Rng,
+    /// Setting up the cipher or encrypting the memory failed.
+    Crypto,
+    /// Locking the memory into RAM was required but the platform refused.
+    Lock,
+    /// The [`Backend`](backend/trait.Backend.html) protecting the prekey
+    /// failed.
+    Backend,
+    /// Serializing or deserializing a typed value failed, or imported data
+    /// isn't exported memory.
+    Encoding,
+    /// The shielded memory outlived its time-to-live or was unshielded as
+    /// many times as allowed, and has been wiped. See
+    /// [`ShieldedBuilder::time_to_live`](struct.ShieldedBuilder.html#method.time_to_live)
+    /// and
+    /// [`ShieldedBuilder::max_uses`](struct.ShieldedBuilder.html#method.max_uses).
+    Expired,
+    /// An exposure broke the
+    /// [`ExposurePolicy`](struct.ExposurePolicy.html), or an earlier one did
+    /// and poisoned the memory.
+    PolicyViolation,
+    /// The [`Agent`](agent/struct.Agent.html) holds no key of the requested
+    /// name.
+    UnknownKey,
+    /// The confirmation callback of the [`Agent`](agent/struct.Agent.html)
+    /// denied the request.
+    Denied,
+    /// The [`Agent`](agent/struct.Agent.html) has stopped.
+    Disconnected,
+    /// The platform couldn't provide a protection of the memory required by
+    /// [`ProtectionRequirements`](struct.ProtectionRequirements.html).
+    Unprotected,
+    /// The shielded memory failed authentication earlier and has been wiped.
+    /// It stays unusable until it is given new content with
+    /// [`Shielded::recover_from`](struct.Shielded.html#method.recover_from).
+    Poisoned,
+    /// The [`Cipher`](enum.Cipher.html) isn't available with the enabled
+    /// features of the crate.
+    Unsupported,
+}
+
+impl fmt::Display for ShieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            ShieldError::Tamper => "shielded memory failed authentication",
+            ShieldError::Rng => "secure random number generator failed",
+            ShieldError::Crypto => "cryptographic operation failed",
+            ShieldError::Lock => "failed to lock memory",
+            ShieldError::Backend => "prekey backend failed",
+            ShieldError::Encoding => "failed to encode or decode value",
+            ShieldError::Expired => "shielded memory has expired",
+            ShieldError::PolicyViolation => "exposure policy violated",
+            ShieldError::UnknownKey => "unknown key",
+            ShieldError::Denied => "request denied",
+            ShieldError::Disconnected => "agent has stopped",
+            ShieldError::Unprotected => "required memory protection unavailable",
+            ShieldError::Poisoned => "shielded memory is poisoned",
+            ShieldError::Unsupported => "cipher not supported",
+        };
+        f.write_str(msg)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ShieldError {}
+
+// Transient key material, never on the ordinary heap: like the prekey it is
+// kept in pages of its own, locked into RAM where the platform allows it,
+// excluded from core dumps and wiped when dropped.
+struct Key(SecretBuf);
+struct Nonce(SecretBuf);
+
+impl Key {
+    // A key of `len` bytes, to be filled by the caller.
+    fn new(len: usize) -> Self {
+        Key(SecretBuf::new(len, BufOptions::new(true)))
+    }
+}
 
 /// A construct holding a piece of memory encrypted.
 pub struct Shielded {
     prekey: PreKey,
     nonce: Nonce,
-    memory: Vec<u8>,
+    // The ciphertext of every chunk followed by its encryption tag, see
+    // `Layout`.
+    memory: SecretBuf,
+    // Placed after the prekey and the memory while shielded, see
+    // `check_canaries`.
+    canary: [u8; CANARY_LEN],
+    chunk_size: Option<usize>,
+    padding: Padding,
+    backend: Option<Arc<dyn Backend>>,
+    // Whether the prekey is protected by the backend.
+    prekey_protected: bool,
+    lock: LockMode,
+    protection: ProtectionRequirements,
+    cipher: Cipher,
+    context: Vec<u8>,
+    // The caller's associated data, authenticated with every chunk.
+    aad: Vec<u8>,
+    entropy: Option<Arc<dyn EntropySource>>,
+    policy: ReshieldPolicy,
+    // Exposures since the prekey was last generated.
+    exposures: u64,
+    // Whether the nonce under a kept prekey counts up instead of being random.
+    nonce_sequence: bool,
+    // The commitment to the current encryption key, if enabled, checked
+    // before decrypting.
+    commitment: Option<[u8; 32]>,
+    // Only kept for `ReshieldPolicy::After`, as there is no clock e.g. in the
+    // browser.
+    #[cfg(feature = "std")]
+    rekeyed_at: Option<std::time::Instant>,
+    // The encryption key, kept only while the memory is unshielded and only
+    // if the policy allows shielding it again without a new prekey.
+    exposed_key: Option<ChunkKey>,
+    // Unshield operations so far, and how many are allowed.
+    uses: u64,
+    max_uses: Option<u64>,
+    #[cfg(feature = "std")]
+    expires_at: Option<std::time::Instant>,
+    // Whether the memory has been wiped on expiry.
+    expired: bool,
+    // Whether the memory has been wiped after failing authentication.
+    poisoned: bool,
+    audit: Option<Arc<dyn Audit>>,
+    label: String,
+    #[cfg(feature = "std")]
+    exposure: ExposurePolicy,
+    // When the current in-place exposure has to end by, see `start_exposure`.
+    #[cfg(feature = "std")]
+    exposure_deadline: Option<std::time::Instant>,
+    // Whether an exposure broke the policy, poisoning the memory.
+    #[cfg(feature = "std")]
+    violated: bool,
+    #[cfg(feature = "metrics")]
+    metrics: metrics::Metrics,
+    // The process the prekey was generated in, see `begin_use`.
+    #[cfg(all(unix, feature = "std"))]
+    pid: u32,
 }
 
 impl Shielded {
     /// Construct a new `Shielded` memory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory can't be shielded. See
+    /// [`try_new`](#method.try_new) for a fallible version.
     pub fn new(buf: Vec<u8>) -> Self {
-        let buf_len = buf.len();
-        let mut shielded = Self {
-            prekey: PreKey(vec![MAGIC_BYTE; SHIELD_PREKEY_LEN]),
-            nonce: Nonce(vec![MAGIC_BYTE; SHIELD_CIPHER.nonce_len()]),
-            memory: buf,
+        Self::try_new(buf).expect("shield new memory")
+    }
+
+    /// Construct a new `Shielded` memory, returning an error if the memory
+    /// can't be shielded.
+    pub fn try_new(buf: Vec<u8>) -> Result<Self, ShieldError> {
+        Self::builder().build(buf)
+    }
+
+    /// Construct a new `Shielded` memory encrypted with `cipher` instead of
+    /// the default ChaCha20-Poly1305.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory can't be shielded. Use
+    /// [`builder`](#method.builder) for a fallible version.
+    pub fn with_cipher(cipher: Cipher, buf: Vec<u8>) -> Self {
+        Self::builder()
+            .cipher(cipher)
+            .build(buf)
+            .expect("shield new memory")
+    }
+
+    /// Create a [`ShieldedBuilder`](struct.ShieldedBuilder.html) for
+    /// constructing `Shielded` memory with non-default settings.
+    pub fn builder() -> ShieldedBuilder {
+        ShieldedBuilder::new()
+    }
+
+    pub(crate) fn with_builder(
+        mut buf: Vec<u8>,
+        builder: &ShieldedBuilder,
+    ) -> Result<Self, ShieldError> {
+        // The plaintext is copied into memory and the caller's buffer is
+        // wiped.
+        let result = Self::with_content(buf.len(), builder, |memory| {
+            memory[..buf.len()].copy_from_slice(&buf);
+            Ok(())
+        });
+        buf.zeroize();
+        result
+    }
+
+    // Construct `Shielded` holding `len` bytes of content and shield it. The
+    // content is written by `fill` at the start of the memory it is given,
+    // which has room for at least an encryption tag after the content.
+    pub(crate) fn with_content<F>(
+        len: usize,
+        builder: &ShieldedBuilder,
+        fill: F,
+    ) -> Result<Self, ShieldError>
+    where
+        F: FnOnce(&mut [u8]) -> Result<(), ShieldError>,
+    {
+        // Room for the padding and the encryption tags.
+        let padded_len = builder.padding.padded_len(len);
+        let layout = Layout::new(padded_len, builder.chunk_size, builder.cipher.tag_len());
+        let mut memory = SecretBuf::new(layout.memory_len(), builder.memory_options());
+        fill(&mut memory)?;
+        builder.padding.pad(&mut memory[..padded_len], len);
+
+        let mut shielded = Self::with_memory(memory, builder)?;
+        shielded.shield()?;
+
+        Ok(shielded)
+    }
+
+    // Construct `Shielded` around `memory`, with a prekey and nonce yet to be
+    // generated.
+    pub(crate) fn with_memory(
+        memory: SecretBuf,
+        builder: &ShieldedBuilder,
+    ) -> Result<Self, ShieldError> {
+        let mut canary = [0u8; CANARY_LEN];
+        fill_random(builder.entropy.as_deref(), &mut canary)?;
+
+        let shielded = Self {
+            prekey: PreKey::new(
+                builder.prekey_len,
+                builder.cipher.keys(),
+                builder.prekey_fragments,
+                builder
+                    .backend
+                    .as_ref()
+                    .map_or(0, |backend| backend.extra_len()),
+                builder.prekey_options(),
+                builder.entropy.as_deref(),
+            )?,
+            nonce: Nonce(SecretBuf::new(
+                builder.cipher.nonce_len(),
+                builder.nonce_options(),
+            )),
+            memory,
+            canary,
+            chunk_size: builder.chunk_size,
+            padding: builder.padding,
+            backend: builder.backend.clone(),
+            prekey_protected: false,
+            lock: builder.lock,
+            protection: builder.protection,
+            cipher: builder.cipher,
+            context: builder.context.clone(),
+            aad: builder.aad.clone(),
+            entropy: builder.entropy.clone(),
+            policy: builder.policy,
+            exposures: 0,
+            nonce_sequence: builder.nonce_sequence,
+            commitment: builder.key_commitment.then_some([0; 32]),
+            #[cfg(feature = "std")]
+            rekeyed_at: None,
+            exposed_key: None,
+            uses: 0,
+            max_uses: builder.max_uses,
+            #[cfg(feature = "std")]
+            expires_at: builder.ttl.map(|ttl| std::time::Instant::now() + ttl),
+            expired: false,
+            poisoned: false,
+            audit: builder.audit.clone(),
+            label: builder.label.clone(),
+            #[cfg(feature = "std")]
+            exposure: builder.exposure,
+            #[cfg(feature = "std")]
+            exposure_deadline: None,
+            #[cfg(feature = "std")]
+            violated: false,
+            #[cfg(feature = "metrics")]
+            metrics: metrics::Metrics::new(&builder.label),
+            #[cfg(all(unix, feature = "std"))]
+            pid: std::process::id(),
         };
 
-        shielded.shield(None);
+        if builder.lock == LockMode::Required && !shielded.is_locked() {
+            return Err(ShieldError::Lock);
+        }
+        if !shielded.protection_status().satisfies(&shielded.protection) {
+            return Err(ShieldError::Unprotected);
+        }
 
-        // Encryption tag is added to the memory so it should be longer than
-        // buf.
-        debug_assert!(shielded.memory.len() > buf_len);
+        Ok(shielded)
+    }
 
-        shielded
+    /// Length of the content in bytes. Known without decrypting the memory.
+    ///
+    /// With [padding](struct.ShieldedBuilder.html#method.padding) this is the
+    /// padded length, the exact length is only known after decrypting.
+    pub fn len(&self) -> usize {
+        self.layout().len()
     }
 
-    fn shield(&mut self, payload_len: Option<usize>) {
-        let rng = ring::rand::SystemRandom::new();
-        let prekey = new_prekey(&rng);
-        let nonce_bytes = new_nonce(&rng);
-        let key = new_key(&prekey);
-        let unbound_key = UnboundKey::new(&SHIELD_CIPHER, &key.0).expect("new UnboundKey");
-        let nonce = aead::Nonce::try_assume_unique_for_key(&nonce_bytes.0).expect("new Nonce");
-        let nonce_sequence = OneNonceSequence::new(nonce);
-        let mut sealing_key = SealingKey::new(unbound_key, nonce_sequence);
+    /// Returns `true` if the content is empty. Known without decrypting the
+    /// memory, and never `true` with
+    /// [padding](struct.ShieldedBuilder.html#method.padding).
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 
-        // Add prekey into additionally authenticated data. This authenticates
-        // the prekey, but doesn't encrypt it. If the authentication check fails
-        // on decryption, something has modified the prekey kept in memory.
-        let aad = aead::Aad::from(&prekey.0);
+    /// Returns `true` if the memory failed authentication and has been
+    /// poisoned, see [`recover_from`](#method.recover_from).
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
 
-        if let Some(len) = payload_len {
-            // Encryption tag is added to self.memory so it appears longer than
-            // what the real content is.
-            self.memory.truncate(len);
+    /// Returns `true` if the prekey, nonce and shielded memory are all locked
+    /// into RAM. See [`LockMode`](enum.LockMode.html).
+    pub fn is_locked(&self) -> bool {
+        self.prekey.is_locked() && self.nonce.0.is_locked() && self.memory.is_locked()
+    }
+
+    /// Which protections the platform applied to the prekey, nonce and
+    /// memory, e.g. to warn when a secret may be swapped out. See
+    /// [`ShieldedBuilder::require_protection`](struct.ShieldedBuilder.html#method.require_protection)
+    /// to fail construction without them instead.
+    pub fn protection_status(&self) -> ProtectionStatus {
+        ProtectionStatus::of_all(self.prekey.fragments(), &[&self.nonce.0, &self.memory])
+    }
+
+    /// A short identifier of the shielded memory for logs, to tell which
+    /// secret an entry is about without revealing anything about it.
+    ///
+    /// This is the first 8 bytes of the SHA-256 hash of the ciphertext, not
+    /// of the content, so it changes whenever the memory is shielded again
+    /// under a new nonce, e.g. after every unshield, and two memories with
+    /// the same content have different fingerprints. Also shown by the
+    /// `Debug` implementation, which never shows the content.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let shielded = Shielded::new(b"secret".to_vec());
+    /// println!("using key {:016x}", shielded.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let hash = Crypto::sha256(&self.memory);
+        u64::from_be_bytes(hash[..8].try_into().expect("8 bytes"))
+    }
+
+    // Encrypt the plaintext in `memory` under a freshly generated prekey and
+    // nonce.
+    fn shield(&mut self) -> Result<(), ShieldError> {
+        let key = self.rekey()?;
+        self.seal(&key)
+    }
+
+    // Encrypt the plaintext in `memory` under `key` and the current nonce.
+    fn seal(&mut self, key: &ChunkKey) -> Result<(), ShieldError> {
+        let layout = self.layout();
+
+        // Spread the plaintext out, making room for the encryption tag after
+        // every chunk. Going backwards, no chunk is overwritten before it has
+        // been moved.
+        for index in (0..layout.chunks()).rev() {
+            let plaintext = layout.plaintext(index);
+            let start = layout.sealed(index).start;
+            self.memory.copy_within(plaintext, start);
         }
 
-        sealing_key
-            .seal_in_place_append_tag(aad, &mut self.memory)
-            .expect("seal in place");
-        self.prekey = prekey;
-        self.nonce = nonce_bytes;
+        let (cipher, nonce, memory) = (self.cipher, &self.nonce.0, &mut self.memory);
+        let aad = &self.aad;
+        self.prekey.with_key(key, |prekey| {
+            with_authenticated(prekey, aad, |authenticated| {
+                try_for_each_chunk(memory, &layout, |index, sealed| {
+                    seal_chunk(cipher, key, nonce, prekey, authenticated, index, sealed)
+                })
+            })
+        })?;
+
+        self.protect_prekey()
+    }
+
+    // Generate a fresh prekey and nonce, returning the encryption key derived
+    // from them. A backend encrypting itself gets the prekey right away.
+    pub(crate) fn rekey(&mut self) -> Result<ChunkKey, ShieldError> {
+        self.prekey.exclude_from_wipe();
+        self.nonce.0.exclude_from_wipe();
+        let entropy = self.entropy.as_deref();
+        self.prekey
+            .with_mut(|prekey| fill_random(entropy, prekey))?;
+        fill_random(self.entropy.as_deref(), &mut self.nonce.0)?;
+        self.exposures = 0;
+        #[cfg(feature = "std")]
+        {
+            if let ReshieldPolicy::After(_) = self.policy {
+                self.rekeyed_at = Some(std::time::Instant::now());
+            }
+        }
+
+        debug_assert!(self.prekey.len() >= SHIELD_PREKEY_MIN_LEN);
+        debug_assert_eq!(self.nonce.0.len(), self.cipher.nonce_len());
+
+        let key = match &self.backend {
+            Some(backend) if backend.encrypts() => {
+                self.prekey
+                    .with_protected_mut(|prekey| backend.protect(prekey))?;
+                self.prekey_protected = true;
+                ChunkKey::Backend(backend.clone(), self.context.clone())
+            }
+            _ => self
+                .prekey
+                .with(|prekey| new_key(self.cipher, prekey, &self.context))
+                .map(ChunkKey::Local)?,
+        };
+        if self.commitment.is_some() {
+            self.commitment = Some(self.commit(&key));
+        }
+        Ok(key)
+    }
+
+    // The encryption key of the shielded memory. Unless the backend encrypts
+    // itself, the prekey is restored from the backend to derive it.
+    fn restore_key(&mut self) -> Result<ChunkKey, ShieldError> {
+        let key = match &self.backend {
+            Some(backend) if backend.encrypts() => {
+                ChunkKey::Backend(backend.clone(), self.context.clone())
+            }
+            backend => {
+                if let Some(backend) = backend {
+                    self.prekey
+                        .with_protected_mut(|prekey| backend.unprotect(prekey))?;
+                    self.prekey_protected = false;
+                }
+                self.prekey
+                    .with(|prekey| new_key(self.cipher, prekey, &self.context))
+                    .map(ChunkKey::Local)?
+            }
+        };
+        if let Some(commitment) = self.commitment {
+            if !bool::from(self.commit(&key).ct_eq(&commitment)) {
+                return Err(ShieldError::Tamper);
+            }
+        }
+        Ok(key)
+    }
+
+    // Commit to the encryption key `key`, so that only the exact key it was
+    // shielded under opens the memory, even with a cipher which isn't
+    // key-committing. A backend encrypting itself never hands out the key,
+    // its prekey is committed to instead.
+    fn commit(&self, key: &ChunkKey) -> [u8; 32] {
+        match key {
+            ChunkKey::Local(key) => Crypto::hmac_sha256(&key.0, KEY_COMMITMENT_INFO),
+            ChunkKey::Backend(..) => self
+                .prekey
+                .with_protected(|prekey| Crypto::hmac_sha256(prekey, KEY_COMMITMENT_INFO)),
+        }
+    }
 
-        debug_assert_eq!(self.prekey.0.len(), SHIELD_PREKEY_LEN);
-        debug_assert_eq!(self.nonce.0.len(), SHIELD_CIPHER.nonce_len());
+    // Hand the prekey to the backend once the memory is shielded with it, and
+    // place the canaries after the prekey and the memory in its final length.
+    // Only then may `wipe_all` wipe them.
+    pub(crate) fn protect_prekey(&mut self) -> Result<(), ShieldError> {
+        self.prekey.place_canary(&self.canary);
+        self.memory.place_canary(&self.canary);
+        if let (false, Some(backend)) = (self.prekey_protected, &self.backend) {
+            self.prekey
+                .with_protected_mut(|prekey| backend.protect(prekey))?;
+            self.prekey_protected = true;
+        }
+        self.include_in_wipe();
+        self.audit(|audit, event| audit.on_shield(event));
+        #[cfg(feature = "metrics")]
+        self.metrics.shielded(self.len());
+        Ok(())
     }
 
     /// Decrypt the Shielded content in-place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_unshield`](#method.try_unshield) for a fallible version.
     pub fn unshield(&mut self) -> UnShielded<'_> {
-        let key = new_key(&self.prekey);
-        let unbound_key = UnboundKey::new(&SHIELD_CIPHER, &key.0).expect("new UnboundKey");
-        let nonce = aead::Nonce::try_assume_unique_for_key(&self.nonce.0).expect("new Nonce");
-        let nonce_sequence = OneNonceSequence::new(nonce);
-        let mut opening_key = OpeningKey::new(unbound_key, nonce_sequence);
-        let aad = aead::Aad::from(&self.prekey.0);
-
-        let plaintext = opening_key
-            .open_in_place(aad, &mut self.memory)
-            .expect("open in place");
-
-        UnShielded {
-            plaintext_len: plaintext.len(),
+        self.try_unshield().expect("unshield memory")
+    }
+
+    /// Decrypt the Shielded content in-place, returning an error if the
+    /// shielded memory fails authentication.
+    pub fn try_unshield(&mut self) -> Result<UnShielded<'_>, ShieldError> {
+        self.begin_use()?;
+        let plaintext_len = self.unshield_in_place()?;
+        #[cfg(feature = "std")]
+        self.start_exposure(None);
+        Ok(UnShielded {
+            plaintext_len,
+            shielded: self,
+        })
+    }
+
+    /// Decrypt the Shielded content in-place like
+    /// [`unshield`](#method.unshield), for a guard which must not live longer
+    /// than `max`, e.g. to catch a guard accidentally kept for the life of a
+    /// connection.
+    ///
+    /// A guard living longer still shields the memory again when dropped,
+    /// but poisons it like an
+    /// [`ExposurePolicy::max_duration`](struct.ExposurePolicy.html#method.max_duration)
+    /// does: every later exposure fails with
+    /// [`ShieldError::PolicyViolation`](enum.ShieldError.html#variant.PolicyViolation),
+    /// and the [`Audit`](audit/trait.Audit.html) hook, if any, is told with
+    /// [`on_violation`](audit/trait.Audit.html#method.on_violation). The
+    /// shorter of `max` and the duration of the policy applies.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use shielded::Shielded;
+    ///
+    /// let mut shielded = Shielded::new(b"secret".to_vec());
+    /// let unshielded = shielded.unshield_for(Duration::from_secs(1));
+    /// assert_eq!(b"secret", unshielded.as_ref());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_unshield_for`](#method.try_unshield_for) for a fallible version.
+    #[cfg(feature = "std")]
+    pub fn unshield_for(&mut self, max: std::time::Duration) -> UnShielded<'_> {
+        self.try_unshield_for(max).expect("unshield memory")
+    }
+
+    /// Decrypt the Shielded content in-place for a guard which must not live
+    /// longer than `max` like [`unshield_for`](#method.unshield_for),
+    /// returning an error if the shielded memory fails authentication.
+    #[cfg(feature = "std")]
+    pub fn try_unshield_for(
+        &mut self,
+        max: std::time::Duration,
+    ) -> Result<UnShielded<'_>, ShieldError> {
+        self.begin_use()?;
+        let plaintext_len = self.unshield_in_place()?;
+        self.start_exposure(Some(max));
+        Ok(UnShielded {
+            plaintext_len,
+            shielded: self,
+        })
+    }
+
+    /// Decrypt only `range` of the Shielded content, e.g. a single record of
+    /// a large buffer. With a [chunk
+    /// size](struct.ShieldedBuilder.html#method.chunk_size) set, only the
+    /// chunks covering `range` are decrypted, into a copy of their own, and
+    /// the memory stays shielded.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut shielded = Shielded::builder()
+    ///     .chunk_size(4096)
+    ///     .build(vec![7u8; 64 * 1024])
+    ///     .unwrap();
+    /// assert_eq!(&[7u8; 16][..], &*shielded.unshield_range(10_000..10_016));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or the shielded memory fails
+    /// authentication. See [`try_unshield_range`](#method.try_unshield_range)
+    /// for a fallible version.
+    pub fn unshield_range(&mut self, range: Range<usize>) -> UnShieldedRange {
+        self.try_unshield_range(range).expect("unshield memory")
+    }
+
+    /// Decrypt only `range` of the Shielded content like
+    /// [`unshield_range`](#method.unshield_range), returning an error if the
+    /// shielded memory fails authentication.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn try_unshield_range(
+        &mut self,
+        range: Range<usize>,
+    ) -> Result<UnShieldedRange, ShieldError> {
+        self.begin_use()?;
+        let result = self.unshield_range_uncounted(range);
+        self.end_use();
+        result
+    }
+
+    // Decrypt `range` like `try_unshield_range`, without counting it as a use.
+    pub(crate) fn unshield_range_uncounted(
+        &mut self,
+        range: Range<usize>,
+    ) -> Result<UnShieldedRange, ShieldError> {
+        let len = self.content_len()?;
+        assert!(
+            range.start <= range.end && range.end <= len,
+            "range out of bounds"
+        );
+
+        let (buf, start) = self.open_range(&range)?;
+        Ok(UnShieldedRange {
+            buf,
+            range: start..start + range.len(),
+        })
+    }
+
+    /// Decrypt a window of the Shielded content, `range`, which can then be
+    /// moved over the content, e.g. to consult the pages of a large shielded
+    /// database or keystore in turn. Like with
+    /// [`unshield_range`](#method.unshield_range), only the chunks covering
+    /// the window are decrypted, into a copy of their own, and the memory
+    /// stays shielded. Moving the window within the decrypted chunks decrypts
+    /// nothing.
+    ///
+    /// The view counts as a single use however often it is moved. The copy
+    /// is wiped when the view is dropped.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let content: Vec<u8> = (0..64 * 1024).map(|i| (i / 4096) as u8).collect();
+    /// let mut shielded = Shielded::builder()
+    ///     .chunk_size(4096)
+    ///     .build(content)
+    ///     .unwrap();
+    /// let mut view = shielded.view(0..16);
+    /// assert_eq!(&[0u8; 16][..], &*view);
+    /// view.set_window(5 * 4096..5 * 4096 + 16);
+    /// assert_eq!(&[5u8; 16][..], &*view);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or the shielded memory fails
+    /// authentication. See [`try_view`](#method.try_view) for a fallible
+    /// version.
+    pub fn view(&mut self, range: Range<usize>) -> UnShieldedView<'_> {
+        self.try_view(range).expect("unshield memory")
+    }
+
+    /// Decrypt a window of the Shielded content like
+    /// [`view`](#method.view), returning an error if the shielded memory
+    /// fails authentication.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn try_view(&mut self, range: Range<usize>) -> Result<UnShieldedView<'_>, ShieldError> {
+        self.begin_use()?;
+        let mut view = UnShieldedView {
+            buf: SecretBuf::new(0, self.memory.options()),
+            start: 0,
+            window: 0..0,
+            len: 0,
+            shielded: self,
+        };
+        // Dropping the view on failure ends the use.
+        view.len = view.shielded.content_len()?;
+        view.try_set_window(range)?;
+        Ok(view)
+    }
+
+    /// Decrypt the Shielded content into `out`, a buffer the caller controls,
+    /// returning the length of the content. Bytes of `out` past it are left
+    /// alone.
+    ///
+    /// The content is decrypted chunk by chunk into scratch memory of its
+    /// own, which is wiped right away, and the memory itself stays shielded.
+    /// Wiping `out` is up to the caller.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut shielded = Shielded::new(b"secret".to_vec());
+    /// let mut out = [0u8; 16];
+    /// let len = shielded.unshield_into(&mut out);
+    /// assert_eq!(b"secret", &out[..len]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out` is shorter than the content or the shielded memory
+    /// fails authentication. See
+    /// [`try_unshield_into`](#method.try_unshield_into) for a fallible
+    /// version.
+    pub fn unshield_into(&mut self, out: &mut [u8]) -> usize {
+        self.try_unshield_into(out).expect("unshield memory")
+    }
+
+    /// Decrypt the Shielded content into `out` like
+    /// [`unshield_into`](#method.unshield_into), returning an error if the
+    /// shielded memory fails authentication.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out` is shorter than the content.
+    pub fn try_unshield_into(&mut self, out: &mut [u8]) -> Result<usize, ShieldError> {
+        self.begin_use()?;
+        let result = self.open_into(out);
+        self.end_use();
+        result
+    }
+
+    // Decrypt the content into `out`, leaving `memory` shielded.
+    fn open_into(&mut self, out: &mut [u8]) -> Result<usize, ShieldError> {
+        let len = self.content_len()?;
+        assert!(out.len() >= len, "output buffer too small");
+
+        let layout = self.layout();
+        self.open_chunks(layout.covering(&(0..len)), |index, plaintext| {
+            let range = layout.plaintext(index);
+            let end = range.end.min(len);
+            out[range.start..end].copy_from_slice(&plaintext[..end - range.start]);
+        })?;
+        Ok(len)
+    }
+
+    /// Decrypt the Shielded content into a plain `Vec`, for handing the
+    /// secret off to an API which needs to own it.
+    ///
+    /// The content is decrypted chunk by chunk straight into the returned
+    /// `Vec`, and the shielded memory, prekey and nonce are wiped as `self`
+    /// is consumed, whether the conversion succeeds or not. The `Vec` is
+    /// ordinary heap memory, so wiping it is up to the caller, e.g. by
+    /// wrapping it in [`zeroize::Zeroizing`].
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let shielded = Shielded::new(b"secret".to_vec());
+    /// assert_eq!(b"secret", &shielded.into_inner()[..]);
+    /// ```
+    ///
+    /// [`zeroize::Zeroizing`]: https://docs.rs/zeroize/1/zeroize/struct.Zeroizing.html
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_into_inner`](#method.try_into_inner) for a fallible version.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.try_into_inner().expect("unshield memory")
+    }
+
+    /// Decrypt the Shielded content into a plain `Vec` like
+    /// [`into_inner`](#method.into_inner), returning an error if the shielded
+    /// memory fails authentication.
+    pub fn try_into_inner(mut self) -> Result<Vec<u8>, ShieldError> {
+        self.begin_use()?;
+        let len = self.content_len()?;
+        let mut buf = vec![0; len];
+        let _ = self.open_into(&mut buf)?;
+        self.expire();
+        Ok(buf)
+    }
+
+    /// Copy the Shielded memory, e.g. to hand the same secret to two
+    /// subsystems which should not share any key material.
+    ///
+    /// The content is decrypted chunk by chunk straight into the memory of
+    /// the copy, which is then shielded under a prekey and nonce of its own.
+    /// The memory of `self` stays shielded. The copy has the same settings,
+    /// and inherits the uses and time left before `self` expires.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut shielded = Shielded::new(b"secret".to_vec());
+    /// let mut copy = shielded.try_clone().unwrap();
+    /// assert_eq!(shielded.unshield().as_ref(), copy.unshield().as_ref());
+    /// ```
+    pub fn try_clone(&mut self) -> Result<Self, ShieldError> {
+        self.begin_use()?;
+        let result = self
+            .clone_memory()
+            .and_then(|memory| self.with_copy(memory));
+        self.end_use();
+        result
+    }
+
+    /// Give the memory new content, e.g. re-read from its source after the
+    /// memory has been poisoned, wiping `buf`.
+    ///
+    /// Whenever the memory fails authentication, because its ciphertext,
+    /// prekey, nonce or a canary was modified, the operation returns
+    /// [`ShieldError::Tamper`](enum.ShieldError.html#variant.Tamper), the
+    /// memory, prekey and nonce are wiped and the memory is poisoned: every
+    /// later operation fails with
+    /// [`ShieldError::Poisoned`](enum.ShieldError.html#variant.Poisoned), and
+    /// the [`Audit`](audit/trait.Audit.html) hook, if any, is told with
+    /// [`on_poison`](audit/trait.Audit.html#method.on_poison). The content
+    /// is then shielded under a new prekey and nonce with the same settings,
+    /// keeping the uses and time left before the memory expires. Also
+    /// replaces the content of memory which isn't poisoned.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut shielded = Shielded::new(b"secret".to_vec());
+    /// if shielded.is_poisoned() {
+    ///     shielded.recover_from(b"secret".to_vec());
+    /// }
+    /// assert_eq!(b"secret", shielded.unshield().as_ref());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory can't be shielded. See
+    /// [`try_recover_from`](#method.try_recover_from) for a fallible version.
+    pub fn recover_from(&mut self, buf: Vec<u8>) {
+        self.try_recover_from(buf).expect("shield new memory")
+    }
+
+    /// Give the memory new content like
+    /// [`recover_from`](#method.recover_from), returning an error if it
+    /// can't be shielded. The memory is left as it was then.
+    pub fn try_recover_from(&mut self, mut buf: Vec<u8>) -> Result<(), ShieldError> {
+        let padded_len = self.padding.padded_len(buf.len());
+        let layout = Layout::new(padded_len, self.chunk_size, self.cipher.tag_len());
+        let mut memory = SecretBuf::new(layout.memory_len(), self.memory.options());
+        memory[..buf.len()].copy_from_slice(&buf);
+        self.padding.pad(&mut memory[..padded_len], buf.len());
+        buf.zeroize();
+
+        *self = self.with_copy(memory)?;
+        Ok(())
+    }
+
+    // Decrypt every chunk, padding included, into a new unshielded memory,
+    // leaving `memory` shielded.
+    fn clone_memory(&mut self) -> Result<SecretBuf, ShieldError> {
+        let layout = self.layout();
+        let mut memory = SecretBuf::new(self.memory.len(), self.memory.options());
+        self.check_buf(&memory)?;
+
+        self.open_chunks(0..layout.chunks(), |index, plaintext| {
+            memory[layout.plaintext(index)].copy_from_slice(plaintext);
+        })?;
+        Ok(memory)
+    }
+
+    // Construct `Shielded` with the settings of `self` around the unshielded
+    // `memory`, and shield it under a new prekey and nonce.
+    fn with_copy(&self, memory: SecretBuf) -> Result<Self, ShieldError> {
+        let mut canary = [0u8; CANARY_LEN];
+        fill_random(self.entropy.as_deref(), &mut canary)?;
+
+        let mut shielded = Self {
+            prekey: self
+                .prekey
+                .new_like(self.cipher.keys(), self.entropy.as_deref())?,
+            nonce: Nonce(SecretBuf::new(self.nonce.0.len(), self.nonce.0.options())),
+            memory,
+            canary,
+            chunk_size: self.chunk_size,
+            padding: self.padding,
+            backend: self.backend.clone(),
+            prekey_protected: false,
+            lock: self.lock,
+            protection: self.protection,
+            cipher: self.cipher,
+            context: self.context.clone(),
+            aad: self.aad.clone(),
+            entropy: self.entropy.clone(),
+            policy: self.policy,
+            exposures: 0,
+            nonce_sequence: self.nonce_sequence,
+            commitment: self.commitment.map(|_| [0; 32]),
+            #[cfg(feature = "std")]
+            rekeyed_at: None,
+            exposed_key: None,
+            uses: self.uses,
+            max_uses: self.max_uses,
+            #[cfg(feature = "std")]
+            expires_at: self.expires_at,
+            expired: false,
+            poisoned: false,
+            audit: self.audit.clone(),
+            label: self.label.clone(),
+            #[cfg(feature = "std")]
+            exposure: self.exposure,
+            #[cfg(feature = "std")]
+            exposure_deadline: None,
+            #[cfg(feature = "std")]
+            violated: false,
+            #[cfg(feature = "metrics")]
+            metrics: metrics::Metrics::new(&self.label),
+            #[cfg(all(unix, feature = "std"))]
+            pid: std::process::id(),
+        };
+
+        if shielded.lock == LockMode::Required && !shielded.is_locked() {
+            return Err(ShieldError::Lock);
+        }
+        if !shielded.protection_status().satisfies(&shielded.protection) {
+            return Err(ShieldError::Unprotected);
+        }
+        shielded.shield()?;
+        Ok(shielded)
+    }
+
+    /// Check that the shielded memory is intact without exposing its content.
+    ///
+    /// Every chunk is decrypted into scratch memory of its own, which is
+    /// wiped right away, and the memory itself stays shielded. Long running
+    /// programs can call this periodically to notice corrupted memory, for
+    /// example from Rowhammer, before the content is needed.
+    ///
+    /// Returns [`ShieldError::Tamper`](enum.ShieldError.html#variant.Tamper)
+    /// if the ciphertext or the prekey has been modified, or a
+    /// [canary](#method.check_canaries) has changed.
+    pub fn verify(&mut self) -> Result<(), ShieldError> {
+        let chunks = self.layout().chunks();
+        self.open_chunks(0..chunks, |_, _| ())
+    }
+
+    /// Check the canaries after the prekey and the shielded memory without
+    /// decrypting anything.
+    ///
+    /// The slack between the end of the prekey and of the memory and the
+    /// guard pages following them is filled with a random canary whenever
+    /// the memory is shielded. A changed canary means a stray write or a bit
+    /// flip, e.g. from Rowhammer, has hit the pages of the secret. The
+    /// canaries are also checked before every decryption, which fails then
+    /// without running the AEAD. Much cheaper than [`verify`](#method.verify),
+    /// but doesn't notice changes to the ciphertext itself.
+    ///
+    /// Returns [`ShieldError::Tamper`](enum.ShieldError.html#variant.Tamper)
+    /// if a canary has changed.
+    pub fn check_canaries(&self) -> Result<(), ShieldError> {
+        if self.prekey.check_canary(&self.canary) && self.memory.check_canary(&self.canary) {
+            Ok(())
+        } else {
+            Err(ShieldError::Tamper)
+        }
+    }
+
+    /// Compare the Shielded content with `candidate` in constant time,
+    /// without exposing the content.
+    ///
+    /// The content is decrypted chunk by chunk into scratch memory of its
+    /// own, which is wiped right away, and the memory itself stays shielded.
+    /// Only the length is compared in variable time, it isn't secret. With
+    /// [padding](struct.ShieldedBuilder.html#method.padding) the padded
+    /// lengths are compared instead.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut password = Shielded::new(b"hunter2".to_vec());
+    /// assert!(password.ct_eq(b"hunter2"));
+    /// assert!(!password.ct_eq(b"hunter3"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_ct_eq`](#method.try_ct_eq) for a fallible version.
+    pub fn ct_eq(&mut self, candidate: &[u8]) -> bool {
+        self.try_ct_eq(candidate).expect("unshield memory")
+    }
+
+    /// Compare the Shielded content with `candidate` in constant time like
+    /// [`ct_eq`](#method.ct_eq), returning an error if the shielded memory
+    /// fails authentication.
+    pub fn try_ct_eq(&mut self, candidate: &[u8]) -> Result<bool, ShieldError> {
+        self.begin_use()?;
+        let result = self.compare(candidate);
+        self.end_use();
+        result
+    }
+
+    /// Compare the Shielded content with the content of `other` in constant
+    /// time, without exposing either, e.g. to find duplicates in a store of
+    /// secrets or check that a rotated secret has changed.
+    ///
+    /// The content of `other` is decrypted chunk by chunk into scratch memory
+    /// of its own, locked like `other` is, and compared like with
+    /// [`ct_eq`](#method.ct_eq). The scratch memory is wiped right away and
+    /// both memories stay shielded. The comparison counts as a use of both.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut a = Shielded::new(b"hunter2".to_vec());
+    /// let mut b = Shielded::new(b"hunter2".to_vec());
+    /// assert!(a.ct_eq_shielded(&mut b));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if either shielded memory fails authentication. See
+    /// [`try_ct_eq_shielded`](#method.try_ct_eq_shielded) for a fallible
+    /// version.
+    pub fn ct_eq_shielded(&mut self, other: &mut Shielded) -> bool {
+        self.try_ct_eq_shielded(other).expect("unshield memory")
+    }
+
+    /// Compare the Shielded content with the content of `other` in constant
+    /// time like [`ct_eq_shielded`](#method.ct_eq_shielded), returning an
+    /// error if either shielded memory fails authentication.
+    pub fn try_ct_eq_shielded(&mut self, other: &mut Shielded) -> Result<bool, ShieldError> {
+        other.begin_use()?;
+        let result = other.content_len().and_then(|len| {
+            let (buf, start) = other.open_range(&(0..len))?;
+            Ok((buf, start..start + len))
+        });
+        other.end_use();
+
+        let (buf, range) = result?;
+        self.try_ct_eq(&buf[range])
+    }
+
+    // Compare the content with `candidate` in constant time.
+    fn compare(&mut self, candidate: &[u8]) -> Result<bool, ShieldError> {
+        let layout = self.layout();
+        if self.padding.padded_len(candidate.len()) != layout.len() {
+            return Ok(false);
+        }
+
+        // Compare the padded plaintext with the candidate padded the same way.
+        let mut padded_candidate = candidate
+            .iter()
+            .copied()
+            .chain(self.padding.padding(candidate.len()));
+        let mut equal = Choice::from(1);
+        self.open_chunks(0..layout.chunks(), |_, plaintext| {
+            for (a, b) in plaintext.iter().zip(&mut padded_candidate) {
+                equal &= a.ct_eq(&b);
+            }
+        })?;
+        Ok(equal.into())
+    }
+
+    /// Return a reader over the Shielded content, decrypting it one chunk at a
+    /// time. See [`ShieldedReader`](struct.ShieldedReader.html).
+    #[cfg(feature = "std")]
+    pub fn reader(&mut self) -> ShieldedReader<'_> {
+        ShieldedReader::new(self)
+    }
+
+    /// Decrypt the Shielded content in-place for modification. Unlike
+    /// [`unshield`](#method.unshield), the returned guard allows the content
+    /// to be resized before it is encrypted again.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_unshield_mut`](#method.try_unshield_mut) for a fallible version.
+    pub fn unshield_mut(&mut self) -> UnShieldedMut<'_> {
+        self.try_unshield_mut().expect("unshield memory")
+    }
+
+    /// Decrypt the Shielded content in-place for modification, returning an
+    /// error if the shielded memory fails authentication.
+    pub fn try_unshield_mut(&mut self) -> Result<UnShieldedMut<'_>, ShieldError> {
+        self.begin_use()?;
+        let plaintext_len = self.unshield_in_place()?;
+        #[cfg(feature = "std")]
+        self.start_exposure(None);
+        Ok(UnShieldedMut {
+            plaintext_len,
             shielded: self,
+        })
+    }
+
+    /// Call `f` with the decrypted content and encrypt it again once `f`
+    /// returns. The content is encrypted again even if `f` panics, so the
+    /// plaintext can't outlive the call.
+    ///
+    /// ```
+    /// let mut shielded = shielded::Shielded::new(b"secret".to_vec());
+    /// let len = shielded.expose(|secret| secret.len());
+    /// assert_eq!(6, len);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_expose`](#method.try_expose) for a fallible version.
+    pub fn expose<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        self.try_expose(f).expect("unshield memory")
+    }
+
+    /// Call `f` with the decrypted content like [`expose`](#method.expose),
+    /// returning an error if the shielded memory fails authentication.
+    pub fn try_expose<R, F>(&mut self, f: F) -> Result<R, ShieldError>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        let unshielded = self.try_unshield()?;
+        Ok(f(unshielded.as_ref()))
+    }
+
+    /// Return a future polling `f` with the decrypted content, for use in
+    /// asynchronous code.
+    ///
+    /// The content is decrypted only while `f` runs, and encrypted again
+    /// before every poll returns, including when `f` returns `Poll::Pending`.
+    /// No plaintext is left behind while the task is parked or after the
+    /// future is dropped. `f` is polled like a future and must arrange for
+    /// `cx` to be woken when it returns `Pending`, e.g. by forwarding it to
+    /// `AsyncWrite::poll_write`.
+    ///
+    /// # Panics
+    ///
+    /// The future panics if the shielded memory fails authentication. See
+    /// [`try_expose_async`](#method.try_expose_async) for a fallible version.
+    pub fn expose_async<R, F>(&mut self, f: F) -> ExposeAsync<'_, F>
+    where
+        F: FnMut(&[u8], &mut Context<'_>) -> Poll<R> + Unpin,
+    {
+        ExposeAsync::new(self, f)
+    }
+
+    /// Return a future polling `f` with the decrypted content like
+    /// [`expose_async`](#method.expose_async), resolving to an error if the
+    /// shielded memory fails authentication.
+    pub fn try_expose_async<R, F>(&mut self, f: F) -> TryExposeAsync<'_, F>
+    where
+        F: FnMut(&[u8], &mut Context<'_>) -> Poll<R> + Unpin,
+    {
+        TryExposeAsync::new(self, f)
+    }
+
+    /// Call `f` with the decrypted content for modification and encrypt the
+    /// possibly modified content again once `f` returns or panics.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_expose_mut`](#method.try_expose_mut) for a fallible version.
+    pub fn expose_mut<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        self.try_expose_mut(f).expect("unshield memory")
+    }
+
+    /// Call `f` with the decrypted content for modification like
+    /// [`expose_mut`](#method.expose_mut), returning an error if the shielded
+    /// memory fails authentication.
+    pub fn try_expose_mut<R, F>(&mut self, f: F) -> Result<R, ShieldError>
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut unshielded = self.try_unshield_mut()?;
+        Ok(f(&mut unshielded))
+    }
+
+    /// Append `data` to the content, e.g. a token to a growing list, and
+    /// shield the memory again.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut tokens = Shielded::new(b"token1".to_vec());
+    /// tokens.extend_from_slice(b";token2");
+    /// assert_eq!(b"token1;token2", tokens.unshield().as_ref());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication or the memory
+    /// needs to grow and can't be locked as required. See
+    /// [`try_extend_from_slice`](#method.try_extend_from_slice) for a
+    /// fallible version.
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        self.try_extend_from_slice(data).expect("extend memory")
+    }
+
+    /// Append `data` to the content like
+    /// [`extend_from_slice`](#method.extend_from_slice), returning an error
+    /// if the shielded memory fails authentication.
+    ///
+    /// Returns [`ShieldError::Lock`](enum.ShieldError.html#variant.Lock) if
+    /// the memory needs to grow, locking is required and the new memory can't
+    /// be locked. The content is left unchanged then.
+    pub fn try_extend_from_slice(&mut self, data: &[u8]) -> Result<(), ShieldError> {
+        self.try_unshield_mut()?.extend_from_slice(data)
+    }
+
+    /// Append `data` to the content like
+    /// [`extend_from_slice`](#method.extend_from_slice) and wipe `data`
+    /// afterwards, so the caller's copy doesn't outlive it.
+    ///
+    /// # Panics
+    ///
+    /// Panics like [`extend_from_slice`](#method.extend_from_slice). See
+    /// [`try_append`](#method.try_append) for a fallible version.
+    pub fn append(&mut self, data: &mut [u8]) {
+        self.try_append(data).expect("extend memory")
+    }
+
+    /// Append `data` to the content and wipe it like
+    /// [`append`](#method.append), returning an error like
+    /// [`try_extend_from_slice`](#method.try_extend_from_slice). `data` is
+    /// only wiped if it has been appended.
+    pub fn try_append(&mut self, data: &mut [u8]) -> Result<(), ShieldError> {
+        self.try_extend_from_slice(data)?;
+        data.zeroize();
+        Ok(())
+    }
+
+    /// Shorten the content to `len` bytes, wiping the rest, and shield the
+    /// memory again. Has no effect if `len` is greater than the current
+    /// length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_truncate`](#method.try_truncate) for a fallible version.
+    pub fn truncate(&mut self, len: usize) {
+        self.try_truncate(len).expect("unshield memory")
+    }
+
+    /// Shorten the content like [`truncate`](#method.truncate), returning an
+    /// error if the shielded memory fails authentication.
+    pub fn try_truncate(&mut self, len: usize) -> Result<(), ShieldError> {
+        self.try_unshield_mut()?.truncate(len);
+        Ok(())
+    }
+
+    // Restore the prekey from the backend and decrypt `memory` in-place,
+    // returning the length of the plaintext.
+    fn unshield_in_place(&mut self) -> Result<usize, ShieldError> {
+        if self.poisoned {
+            return Err(ShieldError::Poisoned);
+        }
+        if self.expired {
+            return Err(ShieldError::Expired);
+        }
+        self.exclude_from_wipe();
+        let result = self
+            .check_canaries()
+            .and_then(|_| self.restore_key())
+            .and_then(|key| self.open(key));
+        if result.is_err() && !self.prekey_protected {
+            // Don't leave the prekey exposed if the memory stays shielded.
+            if let Some(backend) = &self.backend {
+                let prekey = &mut self.prekey;
+                self.prekey_protected = prekey
+                    .with_protected_mut(|prekey| backend.protect(prekey))
+                    .is_ok();
+            }
+        }
+        match result {
+            Err(ShieldError::Tamper) => self.poison(),
+            Err(_) => self.include_in_wipe(),
+            Ok(_) => {}
         }
+        result
     }
-}
 
-impl From<Vec<u8>> for Shielded {
-    fn from(buf: Vec<u8>) -> Self {
-        Shielded::new(buf)
+    // Shield the memory again after it has been unshielded, under a new
+    // prekey unless the policy allows keeping the current one. The nonce is
+    // always new, as the content may have been modified. Never leave the
+    // plaintext behind: if shielding fails, the plaintext is wiped. The prekey
+    // and nonce no longer match the memory then, so any later unshield fails
+    // with `ShieldError::Tamper`.
+    fn reshield(&mut self) {
+        #[cfg(feature = "std")]
+        {
+            let deadline = self.exposure_deadline.take();
+            if deadline.is_some_and(|deadline| std::time::Instant::now() > deadline) {
+                self.violated = true;
+                self.audit(|audit, event| audit.on_violation(event));
+            }
+        }
+        #[cfg(feature = "metrics")]
+        self.metrics.exposure_ended();
+
+        if self.expiry_due() {
+            self.expire();
+            return;
+        }
+
+        self.exposures = self.exposures.saturating_add(1);
+        let result = match self.exposed_key.take() {
+            Some(key) if !self.rekey_due() => {
+                let nonce = if self.nonce_sequence {
+                    next_nonce(&mut self.nonce.0);
+                    Ok(())
+                } else {
+                    fill_random(self.entropy.as_deref(), &mut self.nonce.0)
+                };
+                nonce.and_then(|_| self.seal(&key))
+            }
+            _ => self.shield(),
+        };
+        if result.is_err() {
+            self.wipe();
+        }
+    }
+
+    // Note when an in-place exposure allowed to last at most `max` has to end
+    // by, if the policy or `max` limit it. Checked in `reshield`.
+    #[cfg(feature = "std")]
+    fn start_exposure(&mut self, max: Option<std::time::Duration>) {
+        let max = match (self.exposure.max_duration, max) {
+            (Some(policy), Some(max)) => Some(policy.min(max)),
+            (policy, max) => policy.or(max),
+        };
+        self.exposure_deadline = max.map(|max| std::time::Instant::now() + max);
+        #[cfg(feature = "metrics")]
+        self.metrics.exposure_started();
+    }
+
+    // Fail unless `buf`, newly allocated to hold content, is locked if
+    // required and protected as the requirements say.
+    fn check_buf(&self, buf: &SecretBuf) -> Result<(), ShieldError> {
+        if self.lock == LockMode::Required && !buf.is_locked() {
+            return Err(ShieldError::Lock);
+        }
+        if !ProtectionStatus::of(buf).satisfies(&self.protection.for_memory()) {
+            return Err(ShieldError::Unprotected);
+        }
+        Ok(())
+    }
+
+    // Count an unshield operation, or fail if the memory has expired or the
+    // exposure policy forbids it. In a forked child, shield the memory under
+    // a new prekey and nonce first.
+    pub(crate) fn begin_use(&mut self) -> Result<(), ShieldError> {
+        if self.poisoned {
+            return Err(ShieldError::Poisoned);
+        }
+        if !self.expired && self.expiry_due() {
+            self.expire();
+        }
+        if self.expired {
+            return Err(ShieldError::Expired);
+        }
+        #[cfg(feature = "std")]
+        {
+            let on_thread = self
+                .exposure
+                .thread
+                .is_none_or(|thread| thread == std::thread::current().id());
+            if self.violated || !on_thread {
+                return Err(ShieldError::PolicyViolation);
+            }
+        }
+        #[cfg(all(unix, feature = "std"))]
+        {
+            // Forked since the prekey was generated, so another process holds
+            // the same prekey and nonce.
+            let pid = std::process::id();
+            if self.pid != pid {
+                self.prekey.unshare();
+                self.nonce.0.unshare();
+                self.memory.unshare();
+                self.rotate()?;
+                self.pid = pid;
+            }
+        }
+        self.uses = self.uses.saturating_add(1);
+        self.audit(|audit, event| audit.on_unshield(event));
+        #[cfg(feature = "metrics")]
+        self.metrics.unshielded();
+        Ok(())
+    }
+
+    // Wipe the memory after an unshield operation if it has expired.
+    pub(crate) fn end_use(&mut self) {
+        if self.expiry_due() {
+            self.expire();
+        }
+    }
+
+    // Whether the memory has outlived its time-to-live or its uses.
+    fn expiry_due(&self) -> bool {
+        #[cfg(feature = "std")]
+        {
+            if let Some(expires_at) = self.expires_at {
+                if std::time::Instant::now() >= expires_at {
+                    return true;
+                }
+            }
+        }
+        self.max_uses.is_some_and(|max| self.uses >= max)
+    }
+
+    // Wipe the memory, prekey and nonce for good.
+    fn expire(&mut self) {
+        self.destroy();
+        self.expired = true;
+        self.audit(|audit, event| audit.on_wipe(event));
+    }
+
+    // Wipe the memory, prekey and nonce after the memory failed
+    // authentication, as none of them can be trusted anymore.
+    fn poison(&mut self) {
+        self.destroy();
+        self.poisoned = true;
+        self.audit(|audit, event| audit.on_poison(event));
+    }
+
+    fn destroy(&mut self) {
+        #[cfg(feature = "metrics")]
+        self.metrics.released();
+        self.exclude_from_wipe();
+        self.memory.zeroize();
+        self.discard_prekey();
+        self.prekey.zeroize();
+        self.nonce.0.zeroize();
+        self.exposed_key = None;
+    }
+
+    // Keep `wipe_all` away from the memory, prekey and nonce while they are
+    // worked on in-place.
+    fn exclude_from_wipe(&mut self) {
+        self.memory.exclude_from_wipe();
+        self.prekey.exclude_from_wipe();
+        self.nonce.0.exclude_from_wipe();
+    }
+
+    // Let `wipe_all` wipe the memory, prekey and nonce again.
+    fn include_in_wipe(&mut self) {
+        self.memory.include_in_wipe();
+        self.prekey.include_in_wipe();
+        self.nonce.0.include_in_wipe();
+    }
+
+    // Let the backend release the protected prekey before it is wiped.
+    fn discard_prekey(&mut self) {
+        if let (true, Some(backend)) = (self.prekey_protected, &self.backend) {
+            self.prekey
+                .with_protected_mut(|prekey| backend.discard(prekey));
+        }
+        self.prekey_protected = false;
+    }
+
+    // Wipe the memory, which can't be decrypted anymore afterwards.
+    fn wipe(&mut self) {
+        self.memory.zeroize();
+        self.audit(|audit, event| audit.on_wipe(event));
+    }
+
+    // Report an event to the audit hook, if any.
+    fn audit<F>(&self, f: F)
+    where
+        F: FnOnce(&dyn Audit, &AuditEvent<'_>),
+    {
+        if let Some(audit) = &self.audit {
+            f(audit.as_ref(), &AuditEvent::new(&self.label));
+        }
+    }
+
+    // Shield the memory again under a new prekey and nonce, regardless of the
+    // policy. If that fails the plaintext is wiped like in `reshield`.
+    pub(crate) fn rotate(&mut self) -> Result<(), ShieldError> {
+        let _ = self.unshield_in_place()?;
+        self.exposed_key = None;
+        let result = self.shield();
+        if result.is_err() {
+            self.wipe();
+        }
+        result
+    }
+
+    // Whether the policy calls for a new prekey on the next shielding.
+    fn rekey_due(&self) -> bool {
+        if self.nonce_sequence && self.exposures >= NONCE_SEQUENCE_LEN {
+            return true;
+        }
+        match self.policy {
+            ReshieldPolicy::Always => true,
+            ReshieldPolicy::Every(n) => self.exposures >= n,
+            #[cfg(feature = "std")]
+            ReshieldPolicy::After(duration) => {
+                self.rekeyed_at.is_none_or(|at| at.elapsed() >= duration)
+            }
+        }
+    }
+
+    // Change the length of the unshielded plaintext from `old_len` to
+    // `new_len`, keeping room for the encryption tags after it. Plaintext cut
+    // off is wiped. If the memory has to be reallocated, the old allocation is
+    // wiped when dropped.
+    fn resize_plaintext(&mut self, old_len: usize, new_len: usize) -> Result<(), ShieldError> {
+        let padded_len = self.padding.padded_len(new_len);
+        let memory_len =
+            Layout::new(padded_len, self.chunk_size, self.cipher.tag_len()).memory_len();
+
+        if new_len < old_len {
+            self.memory[new_len..].zeroize();
+        }
+
+        if memory_len <= self.memory.capacity() {
+            self.memory.set_len(memory_len);
+        } else {
+            let memory = self.memory.resized(memory_len);
+            self.check_buf(&memory)?;
+            self.memory = memory;
+        }
+
+        self.padding.pad(&mut self.memory[..padded_len], new_len);
+        Ok(())
+    }
+
+    // Decrypt `memory` in-place under `key`, returning the length of the
+    // plaintext. If any chunk fails to decrypt, the memory is wiped so no
+    // chunk is left decrypted.
+    fn open(&mut self, key: ChunkKey) -> Result<usize, ShieldError> {
+        let layout = self.layout();
+
+        let (cipher, nonce, memory) = (self.cipher, &self.nonce.0, &mut self.memory);
+        let aad = &self.aad;
+        let result = self.prekey.with_key(&key, |prekey| {
+            with_authenticated(prekey, aad, |authenticated| {
+                try_for_each_chunk(memory, &layout, |index, sealed| {
+                    open_chunk(cipher, &key, nonce, prekey, authenticated, index, sealed)
+                        .map(|_| ())
+                })
+            })
+        });
+        if let Err(e) = result {
+            self.wipe();
+            return Err(e);
+        }
+
+        // Gather the decrypted chunks into a contiguous plaintext. Going
+        // forwards, no chunk is overwritten before it has been moved.
+        for index in 0..layout.chunks() {
+            let plaintext = layout.plaintext(index);
+            let start = layout.sealed(index).start;
+            self.memory
+                .copy_within(start..start + plaintext.len(), plaintext.start);
+        }
+
+        let len = if self.padding == Padding::Off {
+            layout.len()
+        } else {
+            let mut unpad = Unpad::new(0);
+            unpad.update(&self.memory[..layout.len()]);
+            match unpad.finish() {
+                Ok(len) => len,
+                Err(e) => {
+                    self.wipe();
+                    return Err(e);
+                }
+            }
+        };
+
+        if self.policy != ReshieldPolicy::Always {
+            self.exposed_key = Some(key);
+        }
+        Ok(len)
+    }
+
+    // Length of the content. With padding, the chunks which may hold the
+    // padding are decrypted to find it.
+    pub(crate) fn content_len(&mut self) -> Result<usize, ShieldError> {
+        let layout = self.layout();
+        if self.padding == Padding::Off {
+            return Ok(layout.len());
+        }
+
+        let tail = self.padding.min_len(layout.len())..layout.len();
+        let chunks = layout.covering(&tail);
+        let mut unpad = Unpad::new(layout.plaintext(chunks.start).start);
+        self.open_chunks(chunks, |_, plaintext| unpad.update(plaintext))?;
+        unpad.finish()
+    }
+
+    // Decrypt the chunks covering `range` into a buffer of their own, leaving
+    // `memory` shielded. Returns the buffer and the position of `range` in
+    // it.
+    fn open_range(&mut self, range: &Range<usize>) -> Result<(SecretBuf, usize), ShieldError> {
+        let layout = self.layout();
+        debug_assert!(range.start <= range.end && range.end <= layout.len());
+
+        let chunks = layout.covering(range);
+        if chunks.is_empty() {
+            return Ok((SecretBuf::new(0, self.memory.options()), 0));
+        }
+        let first = layout.plaintext(chunks.start).start;
+        let last = layout.plaintext(chunks.end - 1).end;
+
+        let mut buf = SecretBuf::new(last - first, self.memory.options());
+        self.check_buf(&buf)?;
+
+        self.open_chunks(chunks, |index, plaintext| {
+            let start = layout.plaintext(index).start - first;
+            buf[start..start + plaintext.len()].copy_from_slice(plaintext);
+        })?;
+        Ok((buf, range.start - first))
+    }
+
+    // Decrypt `chunks` one at a time into scratch memory, handing the index
+    // and plaintext of each to `f`. `memory` stays shielded and the scratch
+    // memory is wiped afterwards.
+    fn open_chunks<F>(&mut self, chunks: Range<usize>, mut f: F) -> Result<(), ShieldError>
+    where
+        F: FnMut(usize, &[u8]),
+    {
+        if self.poisoned {
+            return Err(ShieldError::Poisoned);
+        }
+        if self.expired {
+            return Err(ShieldError::Expired);
+        }
+        if let Err(e) = self.check_canaries() {
+            self.poison();
+            return Err(e);
+        }
+
+        let layout = self.layout();
+        let scratch_len = layout.sealed_chunk_len().min(self.memory.len());
+        let mut scratch = SecretBuf::new(scratch_len, self.memory.options());
+        self.check_buf(&scratch)?;
+
+        self.exclude_from_wipe();
+        let result = self.restore_key().and_then(|key| {
+            let (cipher, nonce, memory, aad) =
+                (self.cipher, &self.nonce.0, &self.memory, &self.aad);
+            self.prekey.with_key(&key, |prekey| {
+                with_authenticated(prekey, aad, |authenticated| {
+                    for index in chunks {
+                        let sealed = layout.sealed(index);
+                        let chunk = &mut scratch[..sealed.len()];
+                        chunk.copy_from_slice(&memory[sealed]);
+                        let len =
+                            open_chunk(cipher, &key, nonce, prekey, authenticated, index, chunk)?;
+                        f(index, &chunk[..len]);
+                        chunk.zeroize();
+                    }
+                    Ok(())
+                })
+            })
+        });
+
+        if let (false, Some(backend)) = (self.prekey_protected, &self.backend) {
+            if let Err(e) = self
+                .prekey
+                .with_protected_mut(|prekey| backend.protect(prekey))
+            {
+                // The prekey can't be protected again, don't keep anything
+                // it could decrypt.
+                self.wipe();
+                self.include_in_wipe();
+                return Err(e);
+            }
+            self.prekey_protected = true;
+        }
+
+        match result {
+            Err(ShieldError::Tamper) => self.poison(),
+            _ => self.include_in_wipe(),
+        }
+        result
+    }
+
+    // The layout of the plaintext in `memory`, shielded or not.
+    fn layout(&self) -> Layout {
+        Layout::from_memory_len(self.memory.len(), self.chunk_size, self.cipher.tag_len())
     }
 }
 
 impl Drop for Shielded {
     fn drop(&mut self) {
-        let rng = ring::rand::SystemRandom::new();
-        rng.fill(&mut self.memory).expect("rng fill memory in drop");
+        // The buffers wipe themselves, only the backend and the event are
+        // left.
+        self.discard_prekey();
+        #[cfg(feature = "metrics")]
+        self.metrics.released();
+        if !self.expired && !self.poisoned {
+            self.audit(|audit, event| audit.on_wipe(event));
+        }
+    }
+}
+
+impl fmt::Debug for Shielded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Shielded")
+            .field("label", &self.label)
+            .field("len", &self.len())
+            .field("cipher", &self.cipher)
+            .field("fingerprint", &format_args!("{:016x}", self.fingerprint()))
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<Vec<u8>> for Shielded {
+    fn from(buf: Vec<u8>) -> Self {
+        Shielded::new(buf)
     }
 }
 
@@ -164,39 +1921,358 @@ impl<'a> AsMut<[u8]> for UnShielded<'a> {
 
 impl<'a> Drop for UnShielded<'a> {
     fn drop(&mut self) {
-        self.shielded.shield(Some(self.plaintext_len));
+        self.shielded.reshield();
+    }
+}
+
+/// A decrypted copy of a range of [`Shielded`](struct.Shielded.html) memory,
+/// returned by [`unshield_range`](struct.Shielded.html#method.unshield_range).
+///
+/// The shielded memory itself stays encrypted. The copy can't be modified and
+/// is wiped when `UnShieldedRange` goes out of scope or is dropped.
+pub struct UnShieldedRange {
+    buf: SecretBuf,
+    range: Range<usize>,
+}
+
+impl Deref for UnShieldedRange {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf[self.range.clone()]
+    }
+}
+
+impl AsRef<[u8]> for UnShieldedRange {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+/// A movable window of decrypted [`Shielded`](struct.Shielded.html) content,
+/// returned by [`view`](struct.Shielded.html#method.view).
+///
+/// The shielded memory itself stays encrypted. The decrypted chunks covering
+/// the window can't be modified and are wiped when the window moves past
+/// them and when `UnShieldedView` goes out of scope or is dropped.
+pub struct UnShieldedView<'a> {
+    shielded: &'a mut Shielded,
+    // The decrypted chunks covering the window, starting at content offset
+    // `start`.
+    buf: SecretBuf,
+    start: usize,
+    window: Range<usize>,
+    // Length of the whole content.
+    len: usize,
+}
+
+impl<'a> UnShieldedView<'a> {
+    /// The range of the content the view currently shows.
+    pub fn window(&self) -> Range<usize> {
+        self.window.clone()
+    }
+
+    /// Move the window to `range` of the content, decrypting the chunks
+    /// covering it unless they are decrypted already.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or the shielded memory fails
+    /// authentication. See [`try_set_window`](#method.try_set_window) for a
+    /// fallible version.
+    pub fn set_window(&mut self, range: Range<usize>) {
+        self.try_set_window(range).expect("unshield memory")
+    }
+
+    /// Move the window like [`set_window`](#method.set_window), returning an
+    /// error if the shielded memory fails authentication. The window is left
+    /// where it was then.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn try_set_window(&mut self, range: Range<usize>) -> Result<(), ShieldError> {
+        assert!(
+            range.start <= range.end && range.end <= self.len,
+            "range out of bounds"
+        );
+        let decrypted = self.start..self.start + self.buf.len();
+        if decrypted.start > range.start || range.end > decrypted.end {
+            let (buf, start) = self.shielded.open_range(&range)?;
+            self.buf = buf;
+            self.start = range.start - start;
+        }
+        self.window = range;
+        Ok(())
+    }
+}
+
+impl<'a> Deref for UnShieldedView<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.buf[self.window.start - self.start..self.window.end - self.start]
+    }
+}
+
+impl<'a> AsRef<[u8]> for UnShieldedView<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<'a> Drop for UnShieldedView<'a> {
+    fn drop(&mut self) {
+        self.shielded.end_use();
+    }
+}
+
+/// UnShielded memory which can be modified and resized. After `UnShieldedMut`
+/// goes out of scope or is dropped, the `Shielded` is reinitialized with new
+/// cryptographic keys and the possibly modified contents are encrypted again.
+pub struct UnShieldedMut<'a> {
+    // After decryption this `Shielded.memory[..plaintext_len]` contains the
+    // unecrypted content.
+    shielded: &'a mut Shielded,
+    plaintext_len: usize,
+}
+
+impl<'a> UnShieldedMut<'a> {
+    /// Shorten the content to `len` bytes, wiping the rest. Has no effect if
+    /// `len` is greater than the current length.
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.plaintext_len {
+            self.shielded
+                .resize_plaintext(self.plaintext_len, len)
+                .expect("shrink in place");
+            self.plaintext_len = len;
+        }
+    }
+
+    /// Append `data` to the content.
+    ///
+    /// Returns [`ShieldError::Lock`](enum.ShieldError.html#variant.Lock) if
+    /// the memory needs to grow, locking is required and the new memory can't
+    /// be locked. The content is left unchanged then.
+    pub fn extend_from_slice(&mut self, data: &[u8]) -> Result<(), ShieldError> {
+        let old_len = self.plaintext_len;
+        let new_len = old_len.checked_add(data.len()).expect("capacity overflow");
+        self.shielded.resize_plaintext(old_len, new_len)?;
+        self.shielded.memory[old_len..new_len].copy_from_slice(data);
+        self.plaintext_len = new_len;
+        Ok(())
+    }
+
+    /// Resize the content to `len` bytes, filling new bytes with `value`.
+    ///
+    /// Fails like [`extend_from_slice`](#method.extend_from_slice).
+    pub fn resize(&mut self, len: usize, value: u8) -> Result<(), ShieldError> {
+        let old_len = self.plaintext_len;
+        self.shielded.resize_plaintext(old_len, len)?;
+        if len > old_len {
+            self.shielded.memory[old_len..len].fill(value);
+        }
+        self.plaintext_len = len;
+        Ok(())
+    }
+}
+
+impl<'a> Deref for UnShieldedMut<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.shielded.memory[..self.plaintext_len]
+    }
+}
+
+impl<'a> DerefMut for UnShieldedMut<'a> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.shielded.memory[..self.plaintext_len]
+    }
+}
+
+impl<'a> AsRef<[u8]> for UnShieldedMut<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<'a> AsMut<[u8]> for UnShieldedMut<'a> {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl<'a> Drop for UnShieldedMut<'a> {
+    fn drop(&mut self) {
+        self.shielded.reshield();
     }
 }
 
-fn new_prekey(rng: &SystemRandom) -> PreKey {
-    let mut k = vec![MAGIC_BYTE; SHIELD_PREKEY_LEN];
-    rng.fill(&mut k).expect("rng fill prekey");
-    PreKey(k)
+// Fill `buf` from the caller's entropy source, or from the operating system if
+// there is none.
+pub(crate) fn fill_random(
+    entropy: Option<&dyn EntropySource>,
+    buf: &mut [u8],
+) -> Result<(), ShieldError> {
+    match entropy {
+        Some(entropy) => entropy.fill(buf),
+        #[cfg(feature = "std")]
+        None => crypto::os_random(buf),
+        #[cfg(not(feature = "std"))]
+        None => Err(ShieldError::Rng),
+    }
+}
+
+// The key the chunks are encrypted under.
+pub(crate) enum ChunkKey {
+    // Derived from the prekey in process memory.
+    Local(Key),
+    // Kept by a backend which encrypts itself, derived from the prekey it
+    // protects and the caller's context.
+    Backend(Arc<dyn Backend>, Vec<u8>),
+}
+
+// Call `f` with the additionally authenticated data of every chunk: the
+// prekey, followed by the caller's associated data in scratch memory if there
+// is any. This authenticates the prekey, but doesn't encrypt it. If the
+// authentication check fails on decryption, something has modified the prekey
+// kept in memory, or the memory has been moved to a `Shielded` with other
+// associated data.
+pub(crate) fn with_authenticated<R, F>(prekey: &[u8], aad: &[u8], f: F) -> R
+where
+    F: FnOnce(&[u8]) -> R,
+{
+    if aad.is_empty() {
+        return f(prekey);
+    }
+    let mut authenticated = SecretBuf::new(prekey.len() + aad.len(), BufOptions::new(true));
+    authenticated[..prekey.len()].copy_from_slice(prekey);
+    authenticated[prekey.len()..].copy_from_slice(aad);
+    f(&authenticated)
 }
 
-fn new_nonce(rng: &SystemRandom) -> Nonce {
-    let mut n = vec![MAGIC_BYTE; SHIELD_CIPHER.nonce_len()];
-    rng.fill(&mut n).expect("rng fill");
-    Nonce(n)
+// Encrypt chunk `index` in-place. `sealed` holds the plaintext of the chunk
+// followed by room for its encryption tag. `authenticated` is the prekey
+// followed by the caller's associated data, see `with_authenticated`.
+fn seal_chunk(
+    cipher: Cipher,
+    key: &ChunkKey,
+    nonce: &[u8],
+    prekey: &[u8],
+    authenticated: &[u8],
+    index: usize,
+    sealed: &mut [u8],
+) -> Result<(), ShieldError> {
+    let (payload, tag) = sealed.split_at_mut(sealed.len() - cipher.tag_len());
+    let nonce = chunk_nonce(nonce, index);
+    match key {
+        ChunkKey::Local(key) => {
+            Crypto::seal(cipher, &key.0, nonce.as_ref(), authenticated, payload, tag)
+        }
+        ChunkKey::Backend(backend, context) => {
+            let aad = &authenticated[prekey.len()..];
+            let aead = Aead::new(cipher, nonce.as_ref(), context, aad);
+            backend.seal(prekey, &aead, payload, tag)
+        }
+    }
 }
 
-fn new_key(prekey: &PreKey) -> Key {
-    let d = digest::digest(&SHIELD_PREKEY_HASH, &prekey.0);
-    let k = d.as_ref()[0..SHIELD_CIPHER.key_len()].to_owned();
-    Key(k)
+// Decrypt chunk `index` in-place, returning the length of its plaintext.
+fn open_chunk(
+    cipher: Cipher,
+    key: &ChunkKey,
+    nonce: &[u8],
+    prekey: &[u8],
+    authenticated: &[u8],
+    index: usize,
+    sealed: &mut [u8],
+) -> Result<usize, ShieldError> {
+    let nonce = chunk_nonce(nonce, index);
+    match key {
+        ChunkKey::Local(key) => Crypto::open(cipher, &key.0, nonce.as_ref(), authenticated, sealed),
+        ChunkKey::Backend(backend, context) => {
+            let aad = &authenticated[prekey.len()..];
+            let aead = Aead::new(cipher, nonce.as_ref(), context, aad);
+            backend.open(prekey, &aead, sealed)
+        }
+    }
 }
 
-// This struct and following impls' are borrowed from Ring's tests.
-struct OneNonceSequence(Option<aead::Nonce>);
+// Call `f` with the index and the shielded form of every chunk in `memory`,
+// stopping at the first error. With the `rayon` feature the chunks are
+// processed in parallel.
+fn try_for_each_chunk<F>(memory: &mut [u8], layout: &Layout, f: F) -> Result<(), ShieldError>
+where
+    F: Fn(usize, &mut [u8]) -> Result<(), ShieldError> + Send + Sync,
+{
+    #[cfg(feature = "rayon")]
+    return memory
+        .par_chunks_mut(layout.sealed_chunk_len())
+        .enumerate()
+        .try_for_each(|(index, sealed)| f(index, sealed));
+    #[cfg(not(feature = "rayon"))]
+    return memory
+        .chunks_mut(layout.sealed_chunk_len())
+        .enumerate()
+        .try_for_each(|(index, sealed)| f(index, sealed));
+}
 
-impl OneNonceSequence {
-    fn new(nonce: aead::Nonce) -> Self {
-        Self(Some(nonce))
+// The nonce of chunk `index`: the nonce of the memory with the index mixed into
+// its last bytes. The first chunk uses the nonce of the memory unchanged.
+fn chunk_nonce(nonce: &[u8], index: usize) -> ChunkNonce {
+    let mut chunk_nonce = ChunkNonce([0u8; MAX_NONCE_LEN], nonce.len());
+    let bytes = &mut chunk_nonce.0[..nonce.len()];
+    bytes.copy_from_slice(nonce);
+    let tail = bytes.len() - 8;
+    for (b, i) in bytes[tail..].iter_mut().zip((index as u64).to_be_bytes()) {
+        *b ^= i;
     }
+    chunk_nonce
+}
+
+struct ChunkNonce([u8; MAX_NONCE_LEN], usize);
+
+impl AsRef<[u8]> for ChunkNonce {
+    fn as_ref(&self) -> &[u8] {
+        &self.0[..self.1]
+    }
+}
+
+// Count the nonce up by one, as a big-endian number in its bytes before the
+// last eight. Those are left to `chunk_nonce`, so every chunk of every
+// shielding in the sequence gets a nonce of its own.
+fn next_nonce(nonce: &mut [u8]) {
+    let counter = nonce.len() - 8;
+    for b in nonce[..counter].iter_mut().rev() {
+        *b = b.wrapping_add(1);
+        if *b != 0 {
+            break;
+        }
+    }
+}
+
+// Derive the encryption key from the prekey with HKDF, bound to this crate and
+// to the caller's context. Every key of `cipher` is derived from a part of the
+// prekey of its own, so the keys are independent. The key is wiped when
+// dropped.
+pub(crate) fn new_key(cipher: Cipher, prekey: &[u8], context: &[u8]) -> Result<Key, ShieldError> {
+    let mut key = Key::new(cipher.key_len());
+    derive_key(cipher, prekey, context, &mut key.0)?;
+    Ok(key)
 }
 
-impl aead::NonceSequence for OneNonceSequence {
-    fn advance(&mut self) -> Result<aead::Nonce, ring::error::Unspecified> {
-        self.0.take().ok_or(ring::error::Unspecified)
+// Derive the encryption key like `new_key`, into `key` of the key length of
+// `cipher`.
+pub(crate) fn derive_key(
+    cipher: Cipher,
+    prekey: &[u8],
+    context: &[u8],
+    key: &mut [u8],
+) -> Result<(), ShieldError> {
+    let parts = prekey.chunks_exact(prekey.len() / cipher.keys());
+    for (part, key) in parts.zip(key.chunks_exact_mut(KEY_LEN)) {
+        Crypto::hkdf_sha512(part, &[SHIELD_KEY_INFO, context], key)?;
     }
+    Ok(())
 }
@@ -0,0 +1,48 @@
+use crate::crypto::{Crypto, CryptoBackend};
+use crate::{ShieldError, Shielded};
+
+impl Shielded {
+    /// Compute the HMAC-SHA-256 of `message` with the content as the key. The
+    /// key is only decrypted for the computation and never handed out.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut key = Shielded::new(b"key".to_vec());
+    /// let mac = key.hmac_sha256(b"The quick brown fox jumps over the lazy dog");
+    /// assert_eq!(0xf7, mac[0]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_hmac_sha256`](#method.try_hmac_sha256) for a fallible version.
+    pub fn hmac_sha256(&mut self, message: &[u8]) -> [u8; 32] {
+        self.try_hmac_sha256(message).expect("unshield memory")
+    }
+
+    /// Compute the HMAC-SHA-256 of `message` like
+    /// [`hmac_sha256`](#method.hmac_sha256), returning an error if the
+    /// shielded memory fails authentication.
+    pub fn try_hmac_sha256(&mut self, message: &[u8]) -> Result<[u8; 32], ShieldError> {
+        self.try_expose(|key| Crypto::hmac_sha256(key, message))
+    }
+
+    /// Compute the HMAC-SHA-512 of `message` with the content as the key, like
+    /// [`hmac_sha256`](#method.hmac_sha256).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_hmac_sha512`](#method.try_hmac_sha512) for a fallible version.
+    pub fn hmac_sha512(&mut self, message: &[u8]) -> [u8; 64] {
+        self.try_hmac_sha512(message).expect("unshield memory")
+    }
+
+    /// Compute the HMAC-SHA-512 of `message` like
+    /// [`hmac_sha512`](#method.hmac_sha512), returning an error if the
+    /// shielded memory fails authentication.
+    pub fn try_hmac_sha512(&mut self, message: &[u8]) -> Result<[u8; 64], ShieldError> {
+        self.try_expose(|key| Crypto::hmac_sha512(key, message))
+    }
+}
@@ -0,0 +1,633 @@
+//! Page-aligned storage for the prekey, nonce and shielded memory.
+//!
+//! Every buffer gets pages of its own, allocated straight from the operating
+//! system rather than from the heap, so heap grooming can't place other data
+//! right next to it. Locking and dump exclusion work on whole pages, so
+//! unlocking one buffer must never unlock parts of another buffer sharing the
+//! same page.
+//!
+//! The pages are bracketed by inaccessible guard pages where the platform
+//! allows it, so a linear overread or overwrite running off the end of a
+//! neighbouring allocation faults instead of reaching the contents. The slack
+//! after the contents, up to the trailing guard page, can hold a canary to
+//! notice stray writes and bit flips.
+//!
+//! On 64-bit Unix, buffers can also be placed at a random address, away from
+//! the heap and the other mappings which the kernel packs together.
+
+use alloc::alloc::{handle_alloc_error, Layout};
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use core::slice;
+
+use zeroize::Zeroize;
+
+use crate::MAGIC_BYTE;
+
+// Used when the platform can't tell its page size.
+const FALLBACK_PAGE_SIZE: usize = 4096;
+
+/// Length of a canary. Every buffer has at least this much slack after it.
+pub(crate) const CANARY_LEN: usize = 16;
+
+/// How a [`SecretBuf`] is allocated.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct BufOptions {
+    lock: bool,
+    random_placement: bool,
+    #[cfg(feature = "memfd-secret")]
+    memfd_secret: bool,
+}
+
+impl BufOptions {
+    pub(crate) fn new(lock: bool) -> Self {
+        Self {
+            lock,
+            random_placement: false,
+            #[cfg(feature = "memfd-secret")]
+            memfd_secret: false,
+        }
+    }
+
+    /// Ask for the buffer to be mapped at a random address where the
+    /// platform supports it, see `sys::alloc_pages`.
+    pub(crate) fn random_placement(mut self, random_placement: bool) -> Self {
+        self.random_placement = random_placement;
+        self
+    }
+
+    /// Place the buffer in a `memfd_secret(2)` mapping when the kernel
+    /// supports it, falling back to an ordinary allocation otherwise.
+    #[cfg(feature = "memfd-secret")]
+    pub(crate) fn memfd_secret(mut self, memfd_secret: bool) -> Self {
+        self.memfd_secret = memfd_secret;
+        self
+    }
+}
+
+/// An owned, page-aligned byte buffer between guard pages, which is
+/// optionally locked into RAM, excluded from core dumps where the platform
+/// supports it and always wiped when dropped. Without guard pages it comes
+/// from the heap instead, aligned to 64 bytes.
+pub(crate) struct SecretBuf {
+    ptr: NonNull<u8>,
+    len: usize,
+    // Size of the accessible memory, a multiple of the page size between
+    // guard pages. The guard pages of `guard` bytes each come before and
+    // after.
+    size: usize,
+    guard: usize,
+    backing: Backing,
+    locked: bool,
+    dump_excluded: bool,
+    // The entry of the pages in the registry wiped by `wipe_all`, if any.
+    #[cfg(feature = "std")]
+    registered: Option<usize>,
+    options: BufOptions,
+}
+
+#[derive(Clone, Copy)]
+enum Backing {
+    Pages,
+    #[cfg(all(feature = "memfd-secret", target_os = "linux"))]
+    MemfdSecret,
+}
+
+// SecretBuf owns its allocation exclusively, just like a `Vec<u8>`.
+unsafe impl Send for SecretBuf {}
+unsafe impl Sync for SecretBuf {}
+
+impl SecretBuf {
+    /// Allocate a buffer of `len` bytes filled with `MAGIC_BYTE`. If locking
+    /// is requested, the buffer is locked into RAM if the platform allows it.
+    pub(crate) fn new(len: usize, options: BufOptions) -> Self {
+        let page_size = page_size();
+        // Always leave room for a canary, which also makes empty buffers
+        // allocate. Whole pages are only needed between guard pages.
+        let size = len.checked_add(CANARY_LEN).expect("capacity overflow");
+        let size = if sys::GUARD_PAGES {
+            round_up(size, page_size)
+        } else {
+            size
+        };
+
+        #[cfg(all(feature = "memfd-secret", target_os = "linux"))]
+        {
+            if options.memfd_secret {
+                let random = options.random_placement;
+                if let Some(ptr) = unsafe { memfd_secret::map(size, page_size, random) } {
+                    // Secret memory is never swapped nor dumped, it is
+                    // implicitly locked by the kernel.
+                    let mut buf = Self {
+                        ptr,
+                        len,
+                        size,
+                        guard: page_size,
+                        backing: Backing::MemfdSecret,
+                        locked: true,
+                        dump_excluded: true,
+                        #[cfg(feature = "std")]
+                        registered: None,
+                        options,
+                    };
+                    buf.pages_mut().fill(MAGIC_BYTE);
+                    #[cfg(feature = "std")]
+                    {
+                        buf.registered = crate::registry::register(ptr, size);
+                    }
+                    return buf;
+                }
+            }
+        }
+
+        let ptr = match unsafe { sys::alloc_pages(size, page_size, options.random_placement) } {
+            Some(ptr) => ptr,
+            None => {
+                let layout = Layout::from_size_align(size, page_size).expect("page layout");
+                handle_alloc_error(layout)
+            }
+        };
+        let dump_excluded = unsafe { sys::exclude_from_dump(ptr, size) };
+
+        let mut buf = Self {
+            ptr,
+            len,
+            size,
+            guard: page_size,
+            backing: Backing::Pages,
+            locked: false,
+            dump_excluded,
+            #[cfg(feature = "std")]
+            registered: None,
+            options,
+        };
+        buf.pages_mut().fill(MAGIC_BYTE);
+        if options.lock {
+            buf.locked = unsafe { sys::lock(buf.ptr, size) };
+        }
+        #[cfg(feature = "std")]
+        {
+            buf.registered = crate::registry::register(ptr, size);
+        }
+        buf
+    }
+
+    /// Allocate a new buffer of `len` bytes with the same options as this one
+    /// and copy over as much of the contents as fits.
+    pub(crate) fn resized(&self, len: usize) -> Self {
+        let mut buf = Self::new(len, self.options);
+        let n = len.min(self.len);
+        buf[..n].copy_from_slice(&self[..n]);
+        buf
+    }
+
+    /// Move the contents, including the slack after them, into a new
+    /// allocation with the same options, in a child process forked since
+    /// this buffer was allocated. The old allocation may be shared with the
+    /// parent, which still uses it, so it is only wiped if it is private to
+    /// this process. The new one is locked again, as locks aren't inherited.
+    #[cfg(all(unix, feature = "std"))]
+    pub(crate) fn unshare(&mut self) {
+        let mut buf = Self::new(self.capacity(), self.options);
+        buf.set_len(self.len);
+        buf.pages_mut().copy_from_slice(self.pages_mut());
+        let old = core::mem::replace(self, buf);
+        match old.backing {
+            Backing::Pages => drop(old),
+            #[cfg(all(feature = "memfd-secret", target_os = "linux"))]
+            Backing::MemfdSecret => {
+                old.unregister();
+                unsafe { memfd_secret::unmap(old.ptr, old.size, old.guard) };
+                core::mem::forget(old);
+            }
+        }
+    }
+
+    /// The options this buffer was allocated with.
+    pub(crate) fn options(&self) -> BufOptions {
+        self.options
+    }
+
+    /// Whether the pages of this buffer are locked into RAM.
+    pub(crate) fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Whether the pages of this buffer are left out of core dumps.
+    pub(crate) fn is_dump_excluded(&self) -> bool {
+        self.dump_excluded
+    }
+
+    /// Whether the buffer is bracketed by guard pages.
+    pub(crate) fn has_guard_pages(&self) -> bool {
+        sys::GUARD_PAGES
+    }
+
+    /// Whether the buffer lives in a `memfd_secret(2)` mapping.
+    pub(crate) fn is_memfd_secret(&self) -> bool {
+        match self.backing {
+            Backing::Pages => false,
+            #[cfg(all(feature = "memfd-secret", target_os = "linux"))]
+            Backing::MemfdSecret => true,
+        }
+    }
+
+    /// The length the buffer can be set to without reallocating.
+    pub(crate) fn capacity(&self) -> usize {
+        self.size - CANARY_LEN
+    }
+
+    /// Change the length of the buffer within its capacity. Bytes exposed by
+    /// growing the buffer keep whatever they held before, which may be part
+    /// of a canary.
+    pub(crate) fn set_len(&mut self, len: usize) {
+        assert!(len <= self.capacity(), "SecretBuf length exceeds capacity");
+        self.len = len;
+    }
+
+    /// Fill the slack after the buffer up to the end of its pages with
+    /// `canary`, repeated.
+    pub(crate) fn place_canary(&mut self, canary: &[u8; CANARY_LEN]) {
+        let len = self.len;
+        self.pages_mut()[len..]
+            .iter_mut()
+            .zip(canary.iter().cycle())
+            .for_each(|(byte, canary)| *byte = *canary);
+    }
+
+    /// Whether the slack after the buffer still holds `canary` as placed by
+    /// [`place_canary`](#method.place_canary), compared in constant time.
+    pub(crate) fn check_canary(&self, canary: &[u8; CANARY_LEN]) -> bool {
+        let slack =
+            unsafe { slice::from_raw_parts(self.ptr.as_ptr().add(self.len), self.size - self.len) };
+        let diff = slack
+            .iter()
+            .zip(canary.iter().cycle())
+            .fold(0, |diff, (byte, canary)| diff | (byte ^ canary));
+        diff == 0
+    }
+
+    /// Keep `wipe_all` away from the buffer while it is worked on, e.g.
+    /// while it holds decrypted content. Every buffer starts out excluded.
+    pub(crate) fn exclude_from_wipe(&mut self) {
+        #[cfg(feature = "std")]
+        if let Some(index) = self.registered {
+            crate::registry::exclude(index);
+        }
+    }
+
+    /// Let `wipe_all` wipe the buffer once it only holds a prekey, nonce or
+    /// ciphertext, wiping it right away if `wipe_all` was called while it
+    /// was excluded.
+    pub(crate) fn include_in_wipe(&mut self) {
+        #[cfg(feature = "std")]
+        if let Some(index) = self.registered {
+            crate::registry::include(index);
+        }
+    }
+
+    // Take the pages out of the registry before they are freed.
+    fn unregister(&self) {
+        #[cfg(feature = "std")]
+        if let Some(index) = self.registered {
+            crate::registry::unregister(index);
+        }
+    }
+
+    // The whole allocation, including the slack after `len`.
+    fn pages_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.size) }
+    }
+}
+
+impl Deref for SecretBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for SecretBuf {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for SecretBuf {
+    fn drop(&mut self) {
+        self.pages_mut().zeroize();
+        self.unregister();
+        match self.backing {
+            Backing::Pages => unsafe {
+                if self.locked {
+                    sys::unlock(self.ptr, self.size);
+                }
+                sys::free_pages(self.ptr, self.size, self.guard);
+            },
+            #[cfg(all(feature = "memfd-secret", target_os = "linux"))]
+            Backing::MemfdSecret => unsafe { memfd_secret::unmap(self.ptr, self.size, self.guard) },
+        }
+    }
+}
+
+fn round_up(len: usize, page_size: usize) -> usize {
+    len.div_ceil(page_size) * page_size
+}
+
+fn page_size() -> usize {
+    match sys::page_size() {
+        Some(size) if size.is_power_of_two() => size,
+        _ => FALLBACK_PAGE_SIZE,
+    }
+}
+
+// Secret memory areas are removed from the kernel's direct map, so not even
+// the kernel can read them. Available since Linux 5.14.
+#[cfg(all(feature = "memfd-secret", target_os = "linux"))]
+mod memfd_secret {
+    use core::ptr::NonNull;
+
+    use super::sys;
+
+    pub(super) unsafe fn map(size: usize, guard: usize, random: bool) -> Option<NonNull<u8>> {
+        // Fails with ENOSYS on older kernels and when secretmem is disabled.
+        let fd = libc::syscall(libc::SYS_memfd_secret, libc::O_CLOEXEC);
+        if fd < 0 {
+            return None;
+        }
+        let fd = fd as libc::c_int;
+
+        // The secret memory is mapped over the middle of a reserved region,
+        // whose remaining pages serve as the guard pages.
+        let base = sys::reserve(size, guard, random);
+        let ptr = match base {
+            Some(base) if libc::ftruncate(fd, size as libc::off_t) == 0 => libc::mmap(
+                base.as_ptr().add(guard).cast(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_FIXED,
+                fd,
+                0,
+            ),
+            _ => libc::MAP_FAILED,
+        };
+        // The mapping keeps the secret memory alive.
+        let _ = libc::close(fd);
+
+        if ptr == libc::MAP_FAILED {
+            if let Some(base) = base {
+                sys::release(base, size, guard);
+            }
+            None
+        } else {
+            NonNull::new(ptr.cast())
+        }
+    }
+
+    pub(super) unsafe fn unmap(ptr: NonNull<u8>, size: usize, guard: usize) {
+        sys::free_pages(ptr, size, guard);
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use core::ptr::{self, NonNull};
+
+    // OpenBSD leaves concealed mappings out of core dumps.
+    #[cfg(target_os = "openbsd")]
+    const MAP_CONCEAL: libc::c_int = libc::MAP_CONCEAL;
+    #[cfg(not(target_os = "openbsd"))]
+    const MAP_CONCEAL: libc::c_int = 0;
+
+    pub(super) const GUARD_PAGES: bool = true;
+
+    pub(super) fn page_size() -> Option<usize> {
+        let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if size > 0 {
+            Some(size as usize)
+        } else {
+            None
+        }
+    }
+
+    // Map `size` bytes with a guard page of `guard` bytes on both sides, all
+    // inaccessible, at a random address if `random`. Returns the start of the
+    // first guard page.
+    pub(super) unsafe fn reserve(size: usize, guard: usize, random: bool) -> Option<NonNull<u8>> {
+        let total = size.checked_add(guard.checked_mul(2)?)?;
+        let hint = if random {
+            random_address(guard)
+        } else {
+            ptr::null_mut()
+        };
+        // Without MAP_FIXED the address is only a hint, the kernel picks
+        // another one if it is taken or out of range.
+        let ptr = libc::mmap(
+            hint,
+            total,
+            libc::PROT_NONE,
+            libc::MAP_PRIVATE | libc::MAP_ANON | MAP_CONCEAL,
+            -1,
+            0,
+        );
+        if ptr == libc::MAP_FAILED {
+            None
+        } else {
+            NonNull::new(ptr.cast())
+        }
+    }
+
+    // Unmap a region from `reserve`.
+    pub(super) unsafe fn release(base: NonNull<u8>, size: usize, guard: usize) {
+        let _ = libc::munmap(base.as_ptr().cast(), size + 2 * guard);
+    }
+
+    pub(super) unsafe fn alloc_pages(
+        size: usize,
+        guard: usize,
+        random: bool,
+    ) -> Option<NonNull<u8>> {
+        let base = reserve(size, guard, random)?;
+        let ptr = base.as_ptr().add(guard);
+        if libc::mprotect(ptr.cast(), size, libc::PROT_READ | libc::PROT_WRITE) != 0 {
+            release(base, size, guard);
+            return None;
+        }
+        NonNull::new(ptr)
+    }
+
+    pub(super) unsafe fn free_pages(ptr: NonNull<u8>, size: usize, guard: usize) {
+        let base = NonNull::new_unchecked(ptr.as_ptr().sub(guard));
+        release(base, size, guard);
+    }
+
+    // A random page-aligned address between 64GB and 32TB, below where the
+    // kernel places the heap and the other mappings on 64-bit platforms, or
+    // null to let the kernel choose. The randomness only hides the location,
+    // it isn't key material.
+    #[cfg(all(feature = "std", target_pointer_width = "64"))]
+    fn random_address(page_size: usize) -> *mut libc::c_void {
+        const LOW: u64 = 1 << 36;
+        const HIGH: u64 = 1 << 45;
+
+        let mut random = [0u8; 8];
+        if crate::crypto::os_random(&mut random).is_err() {
+            return ptr::null_mut();
+        }
+        let offset = u64::from_ne_bytes(random) % (HIGH - LOW);
+        let address = (LOW + offset) as usize & !(page_size - 1);
+        address as *mut libc::c_void
+    }
+
+    #[cfg(not(all(feature = "std", target_pointer_width = "64")))]
+    fn random_address(_page_size: usize) -> *mut libc::c_void {
+        ptr::null_mut()
+    }
+
+    // Whether the pages are left out of core dumps now. Concealed mappings
+    // already are on OpenBSD.
+    pub(super) unsafe fn exclude_from_dump(ptr: NonNull<u8>, size: usize) -> bool {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        return libc::madvise(ptr.as_ptr().cast(), size, libc::MADV_DONTDUMP) == 0;
+        #[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+        return libc::madvise(ptr.as_ptr().cast(), size, libc::MADV_NOCORE) == 0;
+        #[cfg(not(any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "dragonfly"
+        )))]
+        {
+            let _ = (ptr, size);
+            MAP_CONCEAL != 0
+        }
+    }
+
+    pub(super) unsafe fn lock(ptr: NonNull<u8>, len: usize) -> bool {
+        libc::mlock(ptr.as_ptr().cast(), len) == 0
+    }
+
+    pub(super) unsafe fn unlock(ptr: NonNull<u8>, len: usize) {
+        let _ = libc::munlock(ptr.as_ptr().cast(), len);
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use core::convert::TryFrom;
+    use core::mem::MaybeUninit;
+    use core::ptr::{self, NonNull};
+
+    use windows_sys::Win32::System::ErrorReporting::{
+        WerRegisterExcludedMemoryBlock, WerUnregisterExcludedMemoryBlock,
+    };
+    use windows_sys::Win32::System::Memory::{
+        VirtualAlloc, VirtualFree, VirtualLock, VirtualUnlock, MEM_COMMIT, MEM_RELEASE,
+        MEM_RESERVE, PAGE_NOACCESS, PAGE_READWRITE,
+    };
+    use windows_sys::Win32::System::SystemInformation::{GetSystemInfo, SYSTEM_INFO};
+
+    pub(super) const GUARD_PAGES: bool = true;
+
+    pub(super) fn page_size() -> Option<usize> {
+        let mut info = MaybeUninit::<SYSTEM_INFO>::zeroed();
+        let info = unsafe {
+            GetSystemInfo(info.as_mut_ptr());
+            info.assume_init()
+        };
+        Some(info.dwPageSize as usize)
+    }
+
+    // Reserve room for the guard pages too, but commit only the pages in
+    // between. Reserved pages fault on access.
+    // The placement isn't randomized, VirtualAlloc fails instead of picking
+    // another address when the requested one is taken.
+    pub(super) unsafe fn alloc_pages(
+        size: usize,
+        guard: usize,
+        _random: bool,
+    ) -> Option<NonNull<u8>> {
+        let total = size.checked_add(guard.checked_mul(2)?)?;
+        let base = VirtualAlloc(ptr::null(), total, MEM_RESERVE, PAGE_NOACCESS);
+        if base.is_null() {
+            return None;
+        }
+        let ptr = VirtualAlloc(
+            base.cast::<u8>().add(guard).cast(),
+            size,
+            MEM_COMMIT,
+            PAGE_READWRITE,
+        );
+        if ptr.is_null() {
+            let _ = VirtualFree(base, 0, MEM_RELEASE);
+            return None;
+        }
+        NonNull::new(ptr.cast())
+    }
+
+    pub(super) unsafe fn free_pages(ptr: NonNull<u8>, _size: usize, guard: usize) {
+        let _ = WerUnregisterExcludedMemoryBlock(ptr.as_ptr().cast());
+        let _ = VirtualFree(ptr.as_ptr().sub(guard).cast(), 0, MEM_RELEASE);
+    }
+
+    // Windows Error Reporting leaves registered blocks out of the crash dumps
+    // it collects. The number of blocks is limited, so this is best effort.
+    pub(super) unsafe fn exclude_from_dump(ptr: NonNull<u8>, size: usize) -> bool {
+        match u32::try_from(size) {
+            Ok(size) => WerRegisterExcludedMemoryBlock(ptr.as_ptr().cast(), size) == 0,
+            Err(_) => false,
+        }
+    }
+
+    pub(super) unsafe fn lock(ptr: NonNull<u8>, len: usize) -> bool {
+        VirtualLock(ptr.as_ptr().cast(), len) != 0
+    }
+
+    pub(super) unsafe fn unlock(ptr: NonNull<u8>, len: usize) {
+        let _ = VirtualUnlock(ptr.as_ptr().cast(), len);
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    use alloc::alloc::{alloc, dealloc, Layout};
+    use core::ptr::NonNull;
+
+    pub(super) const GUARD_PAGES: bool = false;
+
+    // Enough for any block of Argon2 kept in a buffer.
+    pub(super) const ALIGN: usize = 64;
+
+    pub(super) fn page_size() -> Option<usize> {
+        None
+    }
+
+    // Without memory protection there are no guard pages, and the buffer
+    // comes from the heap.
+    pub(super) unsafe fn alloc_pages(
+        size: usize,
+        _guard: usize,
+        _random: bool,
+    ) -> Option<NonNull<u8>> {
+        NonNull::new(alloc(layout(size)))
+    }
+
+    pub(super) unsafe fn free_pages(ptr: NonNull<u8>, size: usize, _guard: usize) {
+        dealloc(ptr.as_ptr(), layout(size))
+    }
+
+    pub(super) unsafe fn exclude_from_dump(_ptr: NonNull<u8>, _size: usize) -> bool {
+        false
+    }
+
+    pub(super) unsafe fn lock(_ptr: NonNull<u8>, _len: usize) -> bool {
+        false
+    }
+
+    pub(super) unsafe fn unlock(_ptr: NonNull<u8>, _len: usize) {}
+
+    fn layout(size: usize) -> Layout {
+        Layout::from_size_align(size, ALIGN).expect("buffer layout")
+    }
+}
@@ -0,0 +1,99 @@
+//! Metrics of shielded memory, reported through the
+//! [metrics](https://docs.rs/metrics) facade. Requires the `metrics` feature.
+//!
+//! Every [`Shielded`](../struct.Shielded.html) reports the metrics below to
+//! the recorder installed by the application, e.g. a Prometheus exporter,
+//! with its [label](../struct.ShieldedBuilder.html#method.label) in a
+//! `label` label. Memories sharing a label are counted together, and the sum
+//! over all labels gives the totals of the process. This allows alerting
+//! when a service starts decrypting its secrets unusually often or keeps
+//! them exposed for unusually long.
+//!
+//! - [`SHIELDS`](constant.SHIELDS.html), a counter of the times the memory
+//!   has been shielded, on construction and after every exposure.
+//! - [`UNSHIELDS`](constant.UNSHIELDS.html), a counter of unshield
+//!   operations.
+//! - [`PROTECTED_BYTES`](constant.PROTECTED_BYTES.html), a gauge of the bytes
+//!   of content kept shielded.
+//! - [`EXPOSURE_SECONDS`](constant.EXPOSURE_SECONDS.html), a histogram of
+//!   how long the content was decrypted in-place, from unshielding to
+//!   shielding again.
+//!
+//! The metrics are registered with the recorder installed when the memory is
+//! constructed, so memory constructed before the recorder reports nothing.
+
+use std::time::Instant;
+
+use ::metrics::{counter, gauge, histogram, Counter, Gauge, Histogram};
+
+/// Counter of the times shielded memory has been shielded.
+pub const SHIELDS: &str = "shielded_shields_total";
+
+/// Counter of unshield operations.
+pub const UNSHIELDS: &str = "shielded_unshields_total";
+
+/// Gauge of the bytes of content kept shielded.
+pub const PROTECTED_BYTES: &str = "shielded_protected_bytes";
+
+/// Histogram of the seconds content was decrypted in-place.
+pub const EXPOSURE_SECONDS: &str = "shielded_exposure_seconds";
+
+// The metrics of one memory. The handles are registered once on
+// construction, so reporting doesn't allocate.
+pub(crate) struct Metrics {
+    shields: Counter,
+    unshields: Counter,
+    protected: Gauge,
+    exposure: Histogram,
+    // The bytes of content last reported as shielded.
+    protected_bytes: usize,
+    // When the current in-place exposure started.
+    exposed_at: Option<Instant>,
+}
+
+impl Metrics {
+    pub(crate) fn new(label: &str) -> Self {
+        let label = [("label", label.to_owned())];
+        Self {
+            shields: counter!(SHIELDS, &label),
+            unshields: counter!(UNSHIELDS, &label),
+            protected: gauge!(PROTECTED_BYTES, &label),
+            exposure: histogram!(EXPOSURE_SECONDS, &label),
+            protected_bytes: 0,
+            exposed_at: None,
+        }
+    }
+
+    // The memory has been shielded with `len` bytes of content.
+    pub(crate) fn shielded(&mut self, len: usize) {
+        self.shields.increment(1);
+        self.set_protected(len);
+    }
+
+    pub(crate) fn unshielded(&self) {
+        self.unshields.increment(1);
+    }
+
+    pub(crate) fn exposure_started(&mut self) {
+        self.exposed_at = Some(Instant::now());
+    }
+
+    pub(crate) fn exposure_ended(&mut self) {
+        if let Some(exposed_at) = self.exposed_at.take() {
+            self.exposure.record(exposed_at.elapsed());
+        }
+    }
+
+    // The memory has been wiped or dropped.
+    pub(crate) fn released(&mut self) {
+        self.set_protected(0);
+    }
+
+    fn set_protected(&mut self, len: usize) {
+        if len != self.protected_bytes {
+            self.protected.increment(len as f64);
+            self.protected.decrement(self.protected_bytes as f64);
+            self.protected_bytes = len;
+        }
+    }
+}
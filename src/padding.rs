@@ -0,0 +1,114 @@
+//! Length-hiding padding of the plaintext.
+//!
+//! The content is followed by a `0x80` marker byte and zeros up to the padded
+//! length (ISO/IEC 7816-4 padding), and the whole padded plaintext is
+//! encrypted. The length of the content is only known after decrypting the
+//! memory and finding the marker.
+
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use crate::ShieldError;
+
+const MARKER: u8 = 0x80;
+
+/// Padding of the content of a [`Shielded`](struct.Shielded.html) before it is
+/// encrypted, hiding its exact length from anyone who can read the memory.
+///
+/// Padding always adds at least one byte. Set it with
+/// [`ShieldedBuilder::padding`](struct.ShieldedBuilder.html#method.padding).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Padding {
+    /// Don't pad the content. This is the default.
+    #[default]
+    Off,
+    /// Pad the content to a multiple of this many bytes.
+    Block(usize),
+    /// Pad the content to a power of two bytes.
+    PowerOfTwo,
+}
+
+impl Padding {
+    /// Length of the padded plaintext holding `len` bytes of content.
+    pub(crate) fn padded_len(self, len: usize) -> usize {
+        let padded = match self {
+            Padding::Off => Some(len),
+            Padding::Block(block) => (len / block + 1).checked_mul(block),
+            Padding::PowerOfTwo => len
+                .checked_add(1)
+                .and_then(usize::checked_next_power_of_two),
+        };
+        padded.expect("capacity overflow")
+    }
+
+    /// Where the padding of a padded plaintext of `padded_len` bytes starts at
+    /// the earliest. The content is at least this long.
+    pub(crate) fn min_len(self, padded_len: usize) -> usize {
+        match self {
+            Padding::Off => padded_len,
+            Padding::Block(block) => padded_len - block,
+            Padding::PowerOfTwo => padded_len / 2,
+        }
+    }
+
+    /// The bytes of the padding after `len` bytes of content.
+    pub(crate) fn padding(self, len: usize) -> impl Iterator<Item = u8> {
+        let marker = match self {
+            Padding::Off => None,
+            _ => Some(MARKER),
+        };
+        marker
+            .into_iter()
+            .chain(core::iter::repeat(0))
+            .take(self.padded_len(len) - len)
+    }
+
+    /// Write the padding after `len` bytes of content at the start of `buf`,
+    /// which is as long as the padded plaintext.
+    pub(crate) fn pad(self, buf: &mut [u8], len: usize) {
+        if self != Padding::Off {
+            buf[len] = MARKER;
+            buf[len + 1..].fill(0);
+        }
+    }
+}
+
+/// Finds the length of the content in a padded plaintext, in constant time.
+pub(crate) struct Unpad {
+    // Position of the next byte.
+    pos: u64,
+    // Position of the last marker, and whether only zeros followed it.
+    marker: u64,
+    found: Choice,
+}
+
+impl Unpad {
+    /// Start scanning at position `pos` of the padded plaintext.
+    pub(crate) fn new(pos: usize) -> Self {
+        Self {
+            pos: pos as u64,
+            marker: 0,
+            found: Choice::from(0),
+        }
+    }
+
+    /// Scan the next bytes of the padded plaintext.
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            let is_marker = b.ct_eq(&MARKER);
+            self.marker.conditional_assign(&self.pos, is_marker);
+            self.found = (self.found & b.ct_eq(&0)) | is_marker;
+            self.pos += 1;
+        }
+    }
+
+    /// The length of the content, or
+    /// [`ShieldError::Tamper`](enum.ShieldError.html#variant.Tamper) if the
+    /// padding is malformed.
+    pub(crate) fn finish(self) -> Result<usize, ShieldError> {
+        if bool::from(self.found) {
+            Ok(self.marker as usize)
+        } else {
+            Err(ShieldError::Tamper)
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use alloc::boxed::Box;
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+use bytemuck::Pod;
+use zeroize::Zeroize;
+
+use crate::{ShieldError, Shielded, ShieldedBuilder};
+
+/// A fixed-layout value, such as a `[u8; 32]` key, kept in
+/// [`Shielded`](struct.Shielded.html) memory. Requires the `bytemuck`
+/// feature.
+///
+/// The bytes of the value are shielded as they are, without serialization,
+/// and the original is wiped. Unshielding copies them into a fresh value on
+/// the heap which is wiped when the returned guard is dropped, so consumers
+/// don't need to slice and parse the raw content.
+///
+/// ```
+/// use shielded::ShieldedBox;
+///
+/// let mut key = ShieldedBox::new([7u8; 32]);
+/// let unshielded = key.unshield();
+/// assert_eq!([7; 32], *unshielded);
+/// ```
+pub struct ShieldedBox<T> {
+    shielded: Shielded,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Pod> ShieldedBox<T> {
+    /// Construct a new `ShieldedBox`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value can't be shielded. See
+    /// [`try_new`](#method.try_new) for a fallible version.
+    pub fn new(value: T) -> Self {
+        Self::try_new(value).expect("shield new value")
+    }
+
+    /// Construct a new `ShieldedBox`, returning an error if the value can't
+    /// be shielded.
+    pub fn try_new(mut value: T) -> Result<Self, ShieldError> {
+        let buf = bytemuck::bytes_of(&value).to_vec();
+        bytemuck::bytes_of_mut(&mut value).zeroize();
+        Self::from_shielded(Shielded::try_new(buf)?)
+    }
+
+    /// Construct a `ShieldedBox` holding a random value, shielded with the
+    /// settings of `builder`. Every bit pattern is a valid `Pod` value.
+    pub fn random(builder: ShieldedBuilder) -> Result<Self, ShieldError> {
+        Self::from_shielded(builder.random(size_of::<T>())?)
+    }
+
+    /// Use the bytes held in `shielded` as the value.
+    ///
+    /// Returns [`ShieldError::Encoding`](enum.ShieldError.html#variant.Encoding)
+    /// if `shielded` doesn't hold exactly `size_of::<T>()` bytes.
+    pub fn from_shielded(shielded: Shielded) -> Result<Self, ShieldError> {
+        if shielded.len() != size_of::<T>() {
+            return Err(ShieldError::Encoding);
+        }
+        Ok(Self {
+            shielded,
+            marker: PhantomData,
+        })
+    }
+
+    /// Decrypt the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_unshield`](#method.try_unshield) for a fallible version.
+    pub fn unshield(&mut self) -> UnShieldedBox<'_, T> {
+        self.try_unshield().expect("unshield value")
+    }
+
+    /// Decrypt the value, returning an error if the shielded memory fails
+    /// authentication.
+    pub fn try_unshield(&mut self) -> Result<UnShieldedBox<'_, T>, ShieldError> {
+        // Zeroed in place on the heap, so the value is never copied around on
+        // the stack.
+        let mut value = bytemuck::zeroed_box::<T>();
+        self.shielded
+            .try_expose(|buf| bytemuck::bytes_of_mut(&mut *value).copy_from_slice(buf))?;
+        Ok(UnShieldedBox {
+            value,
+            marker: PhantomData,
+        })
+    }
+
+    /// Unwrap the `Shielded` memory holding the bytes of the value.
+    pub fn into_inner(self) -> Shielded {
+        self.shielded
+    }
+}
+
+/// A decrypted copy of the value kept in a
+/// [`ShieldedBox`](struct.ShieldedBox.html). The copy is wiped when
+/// `UnShieldedBox` goes out of scope or is dropped.
+pub struct UnShieldedBox<'a, T: Pod> {
+    value: Box<T>,
+    marker: PhantomData<&'a mut ShieldedBox<T>>,
+}
+
+impl<'a, T: Pod> Deref for UnShieldedBox<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T: Pod> AsRef<T> for UnShieldedBox<'a, T> {
+    fn as_ref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T: Pod> Drop for UnShieldedBox<'a, T> {
+    fn drop(&mut self) {
+        bytemuck::bytes_of_mut(&mut *self.value).zeroize();
+    }
+}
@@ -0,0 +1,280 @@
+//! Storage of the prekey, in one piece or scattered over many allocations.
+
+use core::convert::TryInto;
+use core::ops::Range;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use zeroize::Zeroize;
+
+use crate::entropy::EntropySource;
+use crate::fill_random;
+use crate::mem::{BufOptions, SecretBuf, CANARY_LEN};
+use crate::{ChunkKey, ShieldError};
+
+/// The prekey the encryption key is derived from.
+///
+/// A scattered prekey is split into fragments in pages of their own, which
+/// are allocated in random order so their placement doesn't follow the order
+/// of the prekey. The fragments are gathered into scratch memory only for the
+/// duration of each use. A prekey of several parts, one for every key of the
+/// cipher, keeps every part in fragments of its own.
+pub(crate) struct PreKey {
+    // The fragments in prekey order, a single one unless scattered.
+    fragments: Vec<SecretBuf>,
+    len: usize,
+    options: BufOptions,
+    // The bytes the backend keeps next to the protected prekey, see
+    // `Backend::extra_len`.
+    extra: Option<SecretBuf>,
+}
+
+impl PreKey {
+    /// Allocate a prekey of `parts` parts of `part_len` bytes, every part in
+    /// up to `fragments` fragments, drawing the order of the allocations from
+    /// `entropy` if there is more than one, and `extra_len` bytes for the
+    /// backend.
+    pub(crate) fn new(
+        part_len: usize,
+        parts: usize,
+        fragments: usize,
+        extra_len: usize,
+        options: BufOptions,
+        entropy: Option<&dyn EntropySource>,
+    ) -> Result<Self, ShieldError> {
+        let len = part_len * parts;
+        let extra = Some(extra_len)
+            .filter(|&len| len > 0)
+            .map(|len| SecretBuf::new(len, options));
+        if parts <= 1 && fragments <= 1 {
+            return Ok(Self {
+                fragments: vec![SecretBuf::new(len, options)],
+                len,
+                options,
+                extra,
+            });
+        }
+
+        let fragment_len = part_len.div_ceil(fragments);
+        let ranges: Vec<Range<usize>> = (0..parts)
+            .flat_map(|part| {
+                let (start, end) = (part * part_len, (part + 1) * part_len);
+                (start..end)
+                    .step_by(fragment_len)
+                    .map(move |start| start..end.min(start + fragment_len))
+            })
+            .collect();
+        let count = ranges.len();
+
+        // Shuffle the order of the allocations with Fisher-Yates. The bias of
+        // the modulo doesn't matter for placement.
+        let mut order: Vec<usize> = (0..count).collect();
+        let mut random = vec![0u8; 4 * count];
+        fill_random(entropy, &mut random)?;
+        for i in (1..count).rev() {
+            let r = u32::from_ne_bytes(random[4 * i..4 * i + 4].try_into().expect("4 bytes"));
+            order.swap(i, r as usize % (i + 1));
+        }
+
+        let mut slots: Vec<Option<SecretBuf>> = (0..count).map(|_| None).collect();
+        for index in order {
+            slots[index] = Some(SecretBuf::new(ranges[index].len(), options));
+        }
+        Ok(Self {
+            fragments: slots.into_iter().flatten().collect(),
+            len,
+            options,
+            extra,
+        })
+    }
+
+    /// Allocate a new prekey of the same length as this one, in `parts` parts
+    /// scattered into as many fragments, with the same options.
+    pub(crate) fn new_like(
+        &self,
+        parts: usize,
+        entropy: Option<&dyn EntropySource>,
+    ) -> Result<Self, ShieldError> {
+        let fragments = self.fragments.len() / parts;
+        let extra_len = self.extra.as_ref().map_or(0, |extra| extra.len());
+        Self::new(
+            self.len / parts,
+            parts,
+            fragments,
+            extra_len,
+            self.options,
+            entropy,
+        )
+    }
+
+    /// Length of the prekey in bytes.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The fragments in prekey order.
+    pub(crate) fn fragments(&self) -> &[SecretBuf] {
+        &self.fragments
+    }
+
+    /// Whether all fragments are locked into RAM.
+    pub(crate) fn is_locked(&self) -> bool {
+        self.fragments.iter().all(SecretBuf::is_locked)
+    }
+
+    /// Call `f` with the prekey, gathered into scratch memory if it is
+    /// scattered.
+    pub(crate) fn with<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        match self.fragments.as_slice() {
+            [prekey] => f(prekey),
+            _ => f(&self.gather()),
+        }
+    }
+
+    /// Call `f` with the prekey for modification, gathered into scratch
+    /// memory and scattered again afterwards if it is scattered.
+    pub(crate) fn with_mut<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        if let [prekey] = self.fragments.as_mut_slice() {
+            return f(prekey);
+        }
+        let mut scratch = self.gather();
+        let result = f(&mut scratch);
+        let mut rest: &[u8] = &scratch;
+        for fragment in &mut self.fragments {
+            let (head, tail) = rest.split_at(fragment.len());
+            fragment.copy_from_slice(head);
+            rest = tail;
+        }
+        result
+    }
+
+    /// Call `f` with the prekey followed by the bytes the backend keeps next
+    /// to it, the form the backend protects and works with.
+    pub(crate) fn with_protected<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        let extra = match &self.extra {
+            Some(extra) => extra,
+            None => return self.with(f),
+        };
+        let options = self.options;
+        self.with(|prekey| {
+            let mut scratch = SecretBuf::new(prekey.len() + extra.len(), options);
+            let (head, tail) = scratch.split_at_mut(prekey.len());
+            head.copy_from_slice(prekey);
+            tail.copy_from_slice(extra);
+            f(&scratch)
+        })
+    }
+
+    /// Call `f` with the prekey followed by the bytes the backend keeps next
+    /// to it for modification, like `with_protected`.
+    pub(crate) fn with_protected_mut<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut extra = match self.extra.take() {
+            Some(extra) => extra,
+            None => return self.with_mut(f),
+        };
+        let options = self.options;
+        let result = self.with_mut(|prekey| {
+            let mut scratch = SecretBuf::new(prekey.len() + extra.len(), options);
+            let (head, tail) = scratch.split_at_mut(prekey.len());
+            head.copy_from_slice(prekey);
+            tail.copy_from_slice(&extra);
+            let result = f(&mut scratch);
+            let (head, tail) = scratch.split_at(prekey.len());
+            prekey.copy_from_slice(head);
+            extra.copy_from_slice(tail);
+            result
+        });
+        self.extra = Some(extra);
+        result
+    }
+
+    /// Call `f` with the prekey in the form `key` is used with: protected
+    /// for a backend which encrypts itself, restored otherwise.
+    pub(crate) fn with_key<R, F>(&self, key: &ChunkKey, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        match key {
+            ChunkKey::Backend(..) => self.with_protected(f),
+            ChunkKey::Local(_) => self.with(f),
+        }
+    }
+
+    /// Place `canary` after every fragment, see `SecretBuf::place_canary`.
+    pub(crate) fn place_canary(&mut self, canary: &[u8; CANARY_LEN]) {
+        for fragment in &mut self.fragments {
+            fragment.place_canary(canary);
+        }
+    }
+
+    /// Whether the canary after every fragment is intact.
+    pub(crate) fn check_canary(&self, canary: &[u8; CANARY_LEN]) -> bool {
+        self.fragments.iter().fold(true, |intact, fragment| {
+            intact & fragment.check_canary(canary)
+        })
+    }
+
+    /// Keep `wipe_all` away from the fragments and the extra bytes, see
+    /// `SecretBuf::exclude_from_wipe`.
+    pub(crate) fn exclude_from_wipe(&mut self) {
+        self.fragments
+            .iter_mut()
+            .chain(self.extra.as_mut())
+            .for_each(SecretBuf::exclude_from_wipe);
+    }
+
+    /// Let `wipe_all` wipe the fragments and the extra bytes, see
+    /// `SecretBuf::include_in_wipe`.
+    pub(crate) fn include_in_wipe(&mut self) {
+        self.fragments
+            .iter_mut()
+            .chain(self.extra.as_mut())
+            .for_each(SecretBuf::include_in_wipe);
+    }
+
+    /// Move every fragment into an allocation of its own, see
+    /// `SecretBuf::unshare`.
+    #[cfg(all(unix, feature = "std"))]
+    pub(crate) fn unshare(&mut self) {
+        for fragment in &mut self.fragments {
+            fragment.unshare();
+        }
+        if let Some(extra) = &mut self.extra {
+            extra.unshare();
+        }
+    }
+
+    // Copy the fragments into scratch memory, which is wiped when dropped.
+    fn gather(&self) -> SecretBuf {
+        let mut scratch = SecretBuf::new(self.len, self.options);
+        let mut start = 0;
+        for fragment in &self.fragments {
+            scratch[start..start + fragment.len()].copy_from_slice(fragment);
+            start += fragment.len();
+        }
+        scratch
+    }
+}
+
+impl Zeroize for PreKey {
+    fn zeroize(&mut self) {
+        for fragment in &mut self.fragments {
+            fragment.zeroize();
+        }
+        if let Some(extra) = &mut self.extra {
+            extra.zeroize();
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! Reporting and requiring the protections of the memory behind a
+//! [`Shielded`](struct.Shielded.html).
+
+use crate::mem::SecretBuf;
+
+/// The protections the platform applied to the memory of a
+/// [`Shielded`](struct.Shielded.html), returned by
+/// [`protection_status`](struct.Shielded.html#method.protection_status).
+///
+/// Locking is best effort unless
+/// [required](struct.ShieldedBuilder.html#method.lock), and the other
+/// protections depend on the platform, so the status tells what a secret
+/// actually got.
+///
+/// ```
+/// use shielded::Shielded;
+///
+/// let shielded = Shielded::new(b"secret".to_vec());
+/// let status = shielded.protection_status();
+/// if !status.locked() {
+///     eprintln!("warning: the secret may be swapped out");
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProtectionStatus {
+    locked: bool,
+    dump_excluded: bool,
+    guard_pages: bool,
+    memfd_secret: bool,
+}
+
+impl ProtectionStatus {
+    // The protections of a single buffer.
+    pub(crate) fn of(buf: &SecretBuf) -> Self {
+        Self {
+            locked: buf.is_locked(),
+            dump_excluded: buf.is_dump_excluded(),
+            guard_pages: buf.has_guard_pages(),
+            memfd_secret: buf.is_memfd_secret(),
+        }
+    }
+
+    // The protections of all the `prekey` fragments and the other `bufs`
+    // together. Only the prekey is expected in secret memory.
+    pub(crate) fn of_all(prekey: &[SecretBuf], bufs: &[&SecretBuf]) -> Self {
+        let all = prekey.iter().chain(bufs.iter().copied()).map(Self::of);
+        Self {
+            locked: all.clone().all(|buf| buf.locked),
+            dump_excluded: all.clone().all(|buf| buf.dump_excluded),
+            guard_pages: all.clone().all(|buf| buf.guard_pages),
+            memfd_secret: prekey.iter().all(SecretBuf::is_memfd_secret),
+        }
+    }
+
+    /// Whether all the memory is locked into RAM, so it can't be swapped
+    /// out.
+    pub fn locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Whether all the memory is left out of core dumps.
+    pub fn dump_excluded(&self) -> bool {
+        self.dump_excluded
+    }
+
+    /// Whether all the memory is bracketed by inaccessible guard pages.
+    pub fn guard_pages(&self) -> bool {
+        self.guard_pages
+    }
+
+    /// Whether the prekey is kept in `memfd_secret(2)` memory, which even the
+    /// kernel can't read. Requires the `memfd-secret` feature.
+    pub fn memfd_secret(&self) -> bool {
+        self.memfd_secret
+    }
+
+    /// Whether every protection `requirements` ask for is active.
+    pub fn satisfies(&self, requirements: &ProtectionRequirements) -> bool {
+        (self.locked || !requirements.locked)
+            && (self.dump_excluded || !requirements.dump_excluded)
+            && (self.guard_pages || !requirements.guard_pages)
+            && (self.memfd_secret || !requirements.memfd_secret)
+    }
+}
+
+/// Protections the memory of a [`Shielded`](struct.Shielded.html) must get,
+/// or its construction fails with
+/// [`ShieldError::Unprotected`](enum.ShieldError.html#variant.Unprotected).
+/// Nothing is required by default. See
+/// [`ShieldedBuilder::require_protection`](struct.ShieldedBuilder.html#method.require_protection).
+///
+/// ```
+/// use shielded::{ProtectionRequirements, Shielded};
+///
+/// let requirements = ProtectionRequirements::new().guard_pages();
+/// let shielded = Shielded::builder()
+///     .require_protection(requirements)
+///     .build(b"secret".to_vec());
+/// # #[cfg(any(unix, windows))]
+/// assert!(shielded.is_ok());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProtectionRequirements {
+    pub(crate) locked: bool,
+    pub(crate) dump_excluded: bool,
+    pub(crate) guard_pages: bool,
+    pub(crate) memfd_secret: bool,
+}
+
+impl ProtectionRequirements {
+    /// Create requirements asking for nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Require all the memory to be locked into RAM. The memory is then
+    /// locked even with [`LockMode::Off`](enum.LockMode.html#variant.Off).
+    pub fn locked(mut self) -> Self {
+        self.locked = true;
+        self
+    }
+
+    /// Require all the memory to be left out of core dumps, which Linux,
+    /// FreeBSD, OpenBSD and Windows support.
+    pub fn dump_excluded(mut self) -> Self {
+        self.dump_excluded = true;
+        self
+    }
+
+    /// Require all the memory to be bracketed by guard pages, which every
+    /// Unix and Windows support.
+    pub fn guard_pages(mut self) -> Self {
+        self.guard_pages = true;
+        self
+    }
+
+    /// Require the prekey to be kept in `memfd_secret(2)` memory, which needs
+    /// the `memfd-secret` feature and Linux 5.14 or newer with secret memory
+    /// enabled.
+    pub fn memfd_secret(mut self) -> Self {
+        self.memfd_secret = true;
+        self
+    }
+
+    // The requirements of a buffer allocated for the memory after
+    // construction, which is never secret memory unless asked for on its own.
+    pub(crate) fn for_memory(self) -> Self {
+        Self {
+            memfd_secret: false,
+            ..self
+        }
+    }
+}
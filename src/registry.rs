@@ -0,0 +1,239 @@
+//! An opt-in registry of all secret memory of the process, to destroy it in
+//! one call. Requires the `std` feature.
+//!
+//! Once [`enable`](fn.enable.html)d, every buffer allocated for the prekeys,
+//! nonces and content of [`Shielded`](../struct.Shielded.html),
+//! [`ShieldedStore`](../struct.ShieldedStore.html),
+//! [`ShieldedSmall`](../struct.ShieldedSmall.html) and the other types of
+//! this crate is registered until it is freed. A process which detects it
+//! has been compromised, or is about to abort, calls
+//! [`wipe_all`](fn.wipe_all.html) to overwrite the prekeys, nonces and
+//! ciphertexts with zeros, so nothing can be decrypted anymore. Memory
+//! allocated before the registry is enabled, or while
+//! [`CAPACITY`](constant.CAPACITY.html) buffers are registered, isn't
+//! registered.
+//!
+//! Wiped memory fails authentication on its next use, with
+//! [`ShieldError::Tamper`](../enum.ShieldError.html#variant.Tamper), and is
+//! then poisoned. Content exposed at the time, and the prekey it was
+//! decrypted with, are left alone until the exposure ends, as they are
+//! borrowed, and wiped once the content is shielded again.
+//!
+//! [`wipe_on_panic`](fn.wipe_on_panic.html) and, on Unix,
+//! [`wipe_on_signal`](fn.wipe_on_signal.html) wipe everything when the
+//! process panics or receives a signal.
+//!
+//! ```
+//! use shielded::{registry, Shielded, ShieldError};
+//!
+//! registry::enable();
+//! let mut shielded = Shielded::new(b"secret".to_vec());
+//! shielded::wipe_all();
+//! assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+//! ```
+
+use core::hint;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::panic;
+
+/// Most buffers registered at a time.
+pub const CAPACITY: usize = 4096;
+
+// The states of an entry. Only the owner of a buffer moves its entry between
+// `EXCLUDED` and `INCLUDED`, and only `wipe_all` into `WIPING` and `PENDING`.
+const FREE: u8 = 0;
+const CLAIMED: u8 = 1;
+// Worked on by its owner, e.g. holding exposed content.
+const EXCLUDED: u8 = 2;
+// Excluded, and to be wiped once included again.
+const PENDING: u8 = 3;
+// Only holding a prekey, nonce or ciphertext.
+const INCLUDED: u8 = 4;
+const WIPING: u8 = 5;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// The start and size of every registered allocation. Lock-free, so a signal
+// handler can wipe it. A buffer is unregistered before it is freed, which
+// waits for any wipe of it, so the pages are always mapped while wiped.
+static REGISTRY: [Entry; CAPACITY] = [const { Entry::new() }; CAPACITY];
+
+struct Entry {
+    state: AtomicU8,
+    start: AtomicUsize,
+    size: AtomicUsize,
+}
+
+impl Entry {
+    const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(FREE),
+            start: AtomicUsize::new(0),
+            size: AtomicUsize::new(0),
+        }
+    }
+
+    fn transition(&self, from: u8, to: u8) -> bool {
+        self.state
+            .compare_exchange(from, to, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    // Overwrite the pages with zeros, while the entry is `WIPING`.
+    fn wipe(&self) {
+        let start = self.start.load(Ordering::Relaxed) as *mut u8;
+        for i in 0..self.size.load(Ordering::Relaxed) {
+            unsafe { ptr::write_volatile(start.add(i), 0) };
+        }
+    }
+}
+
+/// Register all secret memory allocated from now on, until it is freed.
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Returns `true` if the registry has been [`enable`](fn.enable.html)d.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Overwrite the prekeys, nonces and ciphertexts of every live shielded
+/// memory with zeros. Memory exposed at the time is wiped once it is
+/// shielded again. Does nothing unless the registry is
+/// [`enable`](fn.enable.html)d.
+pub fn wipe_all() {
+    for entry in REGISTRY.iter() {
+        loop {
+            match entry.state.load(Ordering::Acquire) {
+                INCLUDED => {
+                    if entry.transition(INCLUDED, WIPING) {
+                        entry.wipe();
+                        entry.state.store(INCLUDED, Ordering::Release);
+                        break;
+                    }
+                }
+                EXCLUDED => {
+                    if entry.transition(EXCLUDED, PENDING) {
+                        break;
+                    }
+                }
+                // Free, being registered, or already being wiped.
+                _ => break,
+            }
+        }
+    }
+}
+
+/// Call [`wipe_all`](fn.wipe_all.html) on any panic, before the panic hook
+/// set so far reports it.
+pub fn wipe_on_panic() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        wipe_all();
+        previous(info);
+    }));
+}
+
+/// Call [`wipe_all`](fn.wipe_all.html) when the process receives `signal`,
+/// e.g. `libc::SIGTERM`, then handle it as if no handler was installed,
+/// which terminates the process for most signals. Replaces any handler of
+/// `signal`.
+///
+/// The wipe is best-effort: memory exposed when the signal arrives, or
+/// being worked on by a thread, e.g. while it is shielded, is only marked
+/// to be wiped once that is done, which it never is if the signal
+/// terminates the process.
+#[cfg(unix)]
+pub fn wipe_on_signal(signal: libc::c_int) -> std::io::Result<()> {
+    extern "C" fn handler(signal: libc::c_int) {
+        wipe_all();
+        // The handler was reset on entry, so this is handled by default.
+        let _ = unsafe { libc::raise(signal) };
+    }
+
+    let handler: extern "C" fn(libc::c_int) = handler;
+    unsafe {
+        let mut action: libc::sigaction = core::mem::zeroed();
+        action.sa_sigaction = handler as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESETHAND;
+        let _ = libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(signal, &action, ptr::null_mut()) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+// Register the `size` bytes of pages at `ptr`, excluded from wiping, if the
+// registry is enabled and not full, returning the entry.
+pub(crate) fn register(ptr: NonNull<u8>, size: usize) -> Option<usize> {
+    if !is_enabled() {
+        return None;
+    }
+    let index = REGISTRY
+        .iter()
+        .position(|entry| entry.transition(FREE, CLAIMED))?;
+    let entry = &REGISTRY[index];
+    entry.start.store(ptr.as_ptr() as usize, Ordering::Relaxed);
+    entry.size.store(size, Ordering::Relaxed);
+    entry.state.store(EXCLUDED, Ordering::Release);
+    Some(index)
+}
+
+// Forget the pages of `index`, which are about to be freed, once any wipe of
+// them is done.
+pub(crate) fn unregister(index: usize) {
+    let entry = &REGISTRY[index];
+    loop {
+        match entry.state.load(Ordering::Acquire) {
+            WIPING => hint::spin_loop(),
+            state => {
+                if entry.transition(state, FREE) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// Keep `wipe_all` away from the pages of `index` once any wipe of them is
+// done.
+pub(crate) fn exclude(index: usize) {
+    let entry = &REGISTRY[index];
+    loop {
+        match entry.state.load(Ordering::Acquire) {
+            INCLUDED => {
+                if entry.transition(INCLUDED, EXCLUDED) {
+                    return;
+                }
+            }
+            WIPING => hint::spin_loop(),
+            _ => return,
+        }
+    }
+}
+
+// Let `wipe_all` wipe the pages of `index` again, wiping them right away if
+// it was called while they were excluded.
+pub(crate) fn include(index: usize) {
+    let entry = &REGISTRY[index];
+    loop {
+        match entry.state.load(Ordering::Acquire) {
+            PENDING => {
+                if entry.transition(PENDING, WIPING) {
+                    entry.wipe();
+                    entry.state.store(INCLUDED, Ordering::Release);
+                    return;
+                }
+            }
+            EXCLUDED => {
+                if entry.transition(EXCLUDED, INCLUDED) {
+                    return;
+                }
+            }
+            _ => return,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::ShieldedCell;
+
+/// Shields registered [`ShieldedCell`](struct.ShieldedCell.html)s again under
+/// a new prekey and nonce at a fixed interval, on a thread of its own.
+/// Requires the `auto-rotate` feature.
+///
+/// Memory which is rarely unshielded keeps its prekey for a long time, giving
+/// slow attacks on physical memory, which accumulate bits over hours, a fixed
+/// target. Rotating bounds the lifetime of every prekey to the interval,
+/// whether the memory is used or not, and regardless of the
+/// [`ReshieldPolicy`](enum.ReshieldPolicy.html).
+///
+/// Only weak references are kept, so registering doesn't keep a cell alive
+/// beyond a round in progress.
+/// A cell busy with another thread is skipped until the next round, but a
+/// cell with an [exclusive](struct.ExposurePolicy.html#method.exclusive)
+/// exposure policy fails an access racing with a rotation like any other
+/// concurrent access. Rotating doesn't count as a use of the memory. The
+/// thread stops when the `Rotator` is dropped.
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// use shielded::{Rotator, Shielded, ShieldedCell};
+///
+/// let rotator = Rotator::spawn(Duration::from_secs(60));
+/// let key = Arc::new(ShieldedCell::new(Shielded::new(b"secret".to_vec())));
+/// rotator.register(&key);
+/// ```
+pub struct Rotator {
+    cells: Arc<Mutex<Vec<Weak<ShieldedCell>>>>,
+    // Dropped to stop the thread.
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Rotator {
+    /// Start a thread rotating the registered cells every `interval`.
+    pub fn spawn(interval: Duration) -> Self {
+        let cells: Arc<Mutex<Vec<Weak<ShieldedCell>>>> = Arc::default();
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let registered = cells.clone();
+        let thread = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                rotate(&registered);
+            }
+        });
+
+        Rotator {
+            cells,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Rotate `cell` from now on, until it is dropped.
+    pub fn register(&self, cell: &Arc<ShieldedCell>) {
+        lock(&self.cells).push(Arc::downgrade(cell));
+    }
+
+    /// Number of registered cells which are still alive.
+    pub fn len(&self) -> usize {
+        lock(&self.cells)
+            .iter()
+            .filter(|cell| cell.strong_count() > 0)
+            .count()
+    }
+
+    /// Returns `true` if no registered cell is alive.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Drop for Rotator {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Rotate every registered cell which is alive and idle, forgetting the
+// dropped ones. The list is copied first, so registering doesn't wait for a
+// round to finish.
+fn rotate(cells: &Mutex<Vec<Weak<ShieldedCell>>>) {
+    let alive: Vec<Arc<ShieldedCell>> = {
+        let mut cells = lock(cells);
+        cells.retain(|cell| cell.strong_count() > 0);
+        cells.iter().filter_map(Weak::upgrade).collect()
+    };
+    for cell in alive {
+        // A failure wipes the memory, which then fails on its next use.
+        let _ = cell.rotate_if_idle();
+    }
+}
+
+// Nothing can panic while the list is locked.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,217 @@
+//! TLS private keys kept in shielded memory, for
+//! [rustls](https://docs.rs/rustls). Requires the `rustls` feature.
+//!
+//! A [`ShieldedTlsKey`](struct.ShieldedTlsKey.html) implements rustls'
+//! [`SigningKey`](https://docs.rs/rustls/latest/rustls/sign/trait.SigningKey.html),
+//! so a TLS server can keep its private key encrypted between handshakes. The
+//! key is decrypted and parsed with rustls' ring provider only for the
+//! duration of each signature.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+//! use rustls::sign::SingleCertAndKey;
+//! use rustls::ServerConfig;
+//! use shielded::rustls::ShieldedTlsKey;
+//!
+//! # fn load() -> (Vec<CertificateDer<'static>>, PrivateKeyDer<'static>) { unimplemented!() }
+//! let (cert_chain, key) = load();
+//! let key = ShieldedTlsKey::new(key).unwrap();
+//! let resolver = SingleCertAndKey::from(key.certified_key(cert_chain));
+//! let config = ServerConfig::builder()
+//!     .with_no_client_auth()
+//!     .with_cert_resolver(Arc::new(resolver));
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use ::rustls::crypto::ring::sign::any_supported_type;
+use ::rustls::pki_types::{
+    CertificateDer, PrivateKeyDer, PrivatePkcs1KeyDer, PrivatePkcs8KeyDer, PrivateSec1KeyDer,
+    SubjectPublicKeyInfoDer,
+};
+use ::rustls::sign::{CertifiedKey, Signer, SigningKey};
+use ::rustls::{Error, OtherError, SignatureAlgorithm, SignatureScheme};
+use zeroize::Zeroize;
+
+use crate::{ShieldError, ShieldedBuilder, ShieldedCell};
+
+/// The schemes rustls' ring provider can sign with, in order of preference.
+const SCHEMES: &[SignatureScheme] = &[
+    SignatureScheme::RSA_PSS_SHA512,
+    SignatureScheme::RSA_PSS_SHA384,
+    SignatureScheme::RSA_PSS_SHA256,
+    SignatureScheme::RSA_PKCS1_SHA512,
+    SignatureScheme::RSA_PKCS1_SHA384,
+    SignatureScheme::RSA_PKCS1_SHA256,
+    SignatureScheme::ECDSA_NISTP256_SHA256,
+    SignatureScheme::ECDSA_NISTP384_SHA384,
+    SignatureScheme::ECDSA_NISTP521_SHA512,
+    SignatureScheme::ED25519,
+    SignatureScheme::ED448,
+];
+
+/// A TLS private key kept in [`Shielded`](../struct.Shielded.html) memory,
+/// usable as a rustls
+/// [`SigningKey`](https://docs.rs/rustls/latest/rustls/sign/trait.SigningKey.html).
+///
+/// RSA, ECDSA and Ed25519 keys supported by rustls' ring provider are
+/// accepted. The supported signature schemes and the public key are found
+/// once on construction, so choosing a scheme in a handshake doesn't decrypt
+/// the key.
+pub struct ShieldedTlsKey {
+    key: Arc<ShieldedCell>,
+    format: KeyFormat,
+    algorithm: SignatureAlgorithm,
+    schemes: Vec<SignatureScheme>,
+    public_key: Option<Vec<u8>>,
+}
+
+impl ShieldedTlsKey {
+    /// Shield `key` with the default settings. See
+    /// [`with_builder`](#method.with_builder) for other settings.
+    pub fn new(key: PrivateKeyDer<'static>) -> Result<Self, ShieldError> {
+        Self::with_builder(key, ShieldedBuilder::new())
+    }
+
+    /// Shield `key` with the settings of `builder`. The buffer of `key` is
+    /// wiped.
+    ///
+    /// Returns [`ShieldError::Encoding`](../enum.ShieldError.html#variant.Encoding)
+    /// if `key` isn't a private key supported by rustls' ring provider.
+    pub fn with_builder(
+        mut key: PrivateKeyDer<'static>,
+        builder: ShieldedBuilder,
+    ) -> Result<Self, ShieldError> {
+        let format = KeyFormat::of(&key);
+        let shielded = builder.build(key.secret_der().to_vec());
+        key.zeroize();
+        let (format, mut shielded) = (format?, shielded?);
+
+        let (algorithm, schemes, public_key) = shielded.try_expose(|der| {
+            let key = format.load(der)?;
+            let schemes = SCHEMES
+                .iter()
+                .copied()
+                .filter(|scheme| key.choose_scheme(&[*scheme]).is_some())
+                .collect();
+            let public_key = key.public_key().map(|key| key.as_ref().to_vec());
+            Ok::<_, ShieldError>((key.algorithm(), schemes, public_key))
+        })??;
+
+        Ok(Self {
+            key: Arc::new(ShieldedCell::new(shielded)),
+            format,
+            algorithm,
+            schemes,
+            public_key,
+        })
+    }
+
+    /// Pair the key with the certificate chain `cert_chain`, leaf first, e.g.
+    /// for a rustls
+    /// [`SingleCertAndKey`](https://docs.rs/rustls/latest/rustls/sign/struct.SingleCertAndKey.html)
+    /// certificate resolver.
+    pub fn certified_key(self, cert_chain: Vec<CertificateDer<'static>>) -> CertifiedKey {
+        CertifiedKey::new(cert_chain, Arc::new(self))
+    }
+}
+
+impl SigningKey for ShieldedTlsKey {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        let scheme = self
+            .schemes
+            .iter()
+            .copied()
+            .find(|scheme| offered.contains(scheme))?;
+        Some(Box::new(ShieldedSigner {
+            key: self.key.clone(),
+            format: self.format,
+            scheme,
+        }))
+    }
+
+    fn public_key(&self) -> Option<SubjectPublicKeyInfoDer<'_>> {
+        self.public_key
+            .as_deref()
+            .map(SubjectPublicKeyInfoDer::from)
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        self.algorithm
+    }
+}
+
+impl fmt::Debug for ShieldedTlsKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShieldedTlsKey")
+            .field("algorithm", &self.algorithm)
+            .finish()
+    }
+}
+
+// Signs with one scheme, decrypting the key for every signature.
+struct ShieldedSigner {
+    key: Arc<ShieldedCell>,
+    format: KeyFormat,
+    scheme: SignatureScheme,
+}
+
+impl Signer for ShieldedSigner {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Error> {
+        let format = self.format;
+        let scheme = self.scheme;
+        self.key
+            .try_expose(|der| {
+                let signer = format
+                    .load(der)?
+                    .choose_scheme(&[scheme])
+                    .ok_or(ShieldError::Encoding)?;
+                signer.sign(message).map_err(|_| ShieldError::Crypto)
+            })
+            .and_then(|signature| signature)
+            .map_err(|e| Error::Other(OtherError(Arc::new(e))))
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+}
+
+impl fmt::Debug for ShieldedSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShieldedSigner")
+            .field("scheme", &self.scheme)
+            .finish()
+    }
+}
+
+// The encoding of the shielded DER, to parse it again for signing.
+#[derive(Clone, Copy)]
+enum KeyFormat {
+    Pkcs1,
+    Sec1,
+    Pkcs8,
+}
+
+impl KeyFormat {
+    fn of(key: &PrivateKeyDer<'_>) -> Result<Self, ShieldError> {
+        match key {
+            PrivateKeyDer::Pkcs1(_) => Ok(KeyFormat::Pkcs1),
+            PrivateKeyDer::Sec1(_) => Ok(KeyFormat::Sec1),
+            PrivateKeyDer::Pkcs8(_) => Ok(KeyFormat::Pkcs8),
+            _ => Err(ShieldError::Encoding),
+        }
+    }
+
+    fn load(self, der: &[u8]) -> Result<Arc<dyn SigningKey>, ShieldError> {
+        let der = match self {
+            KeyFormat::Pkcs1 => PrivateKeyDer::Pkcs1(PrivatePkcs1KeyDer::from(der)),
+            KeyFormat::Sec1 => PrivateKeyDer::Sec1(PrivateSec1KeyDer::from(der)),
+            KeyFormat::Pkcs8 => PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(der)),
+        };
+        any_supported_type(&der).map_err(|_| ShieldError::Encoding)
+    }
+}
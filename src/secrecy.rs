@@ -0,0 +1,128 @@
+//! Interoperability with the [secrecy](https://docs.rs/secrecy) crate.
+//! Requires the `secrecy` feature.
+//!
+//! A [`SecretSlice`](https://docs.rs/secrecy/latest/secrecy/type.SecretSlice.html)
+//! converts into [`Shielded`](../struct.Shielded.html) memory, and code
+//! written against secrecy's
+//! [`ExposeSecret`](https://docs.rs/secrecy/latest/secrecy/trait.ExposeSecret.html)
+//! can be handed the decrypted content with
+//! [`Shielded::with_secret`](../struct.Shielded.html#method.with_secret) or
+//! [`ShieldedCell::with_secret`](../struct.ShieldedCell.html#method.with_secret).
+//!
+//! ```
+//! use secrecy::{ExposeSecret, SecretSlice};
+//! use shielded::Shielded;
+//!
+//! fn password_len(password: &impl ExposeSecret<[u8]>) -> usize {
+//!     password.expose_secret().len()
+//! }
+//!
+//! let secret = SecretSlice::from(b"hunter2".to_vec());
+//! let mut shielded = Shielded::from(secret);
+//! assert_eq!(7, shielded.with_secret(|password| password_len(password)));
+//! ```
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use ::secrecy::{ExposeSecret, SecretSlice};
+
+#[cfg(feature = "std")]
+use crate::ShieldedCell;
+use crate::{ShieldError, Shielded};
+
+/// The decrypted content of [`Shielded`](../struct.Shielded.html) memory,
+/// lent to a closure as an
+/// [`ExposeSecret`](https://docs.rs/secrecy/latest/secrecy/trait.ExposeSecret.html).
+///
+/// It only lives for the duration of the closure, after which the memory is
+/// encrypted again.
+pub struct ExposedSecret<'a> {
+    content: &'a [u8],
+}
+
+impl<'a> ExposeSecret<[u8]> for ExposedSecret<'a> {
+    fn expose_secret(&self) -> &[u8] {
+        self.content
+    }
+}
+
+impl<'a> fmt::Debug for ExposedSecret<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ExposedSecret([REDACTED])")
+    }
+}
+
+impl From<SecretSlice<u8>> for Shielded {
+    /// Shield the content of `secret`, which wipes itself when dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics like [`Shielded::new`](struct.Shielded.html#method.new).
+    fn from(secret: SecretSlice<u8>) -> Self {
+        // Shielded in place, so the only plaintext copy is `secret`.
+        let mut buf = Vec::with_capacity(secret.expose_secret().len());
+        buf.extend_from_slice(secret.expose_secret());
+        drop(secret);
+        Shielded::new(buf)
+    }
+}
+
+impl Shielded {
+    /// Call `f` with the decrypted content as an
+    /// [`ExposeSecret`](https://docs.rs/secrecy/latest/secrecy/trait.ExposeSecret.html),
+    /// for code written against the secrecy crate. The content is encrypted
+    /// again once `f` returns or panics, like with
+    /// [`expose`](#method.expose). Requires the `secrecy` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_with_secret`](#method.try_with_secret) for a fallible version.
+    pub fn with_secret<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&ExposedSecret<'_>) -> R,
+    {
+        self.try_with_secret(f).expect("unshield memory")
+    }
+
+    /// Call `f` with the decrypted content as an `ExposeSecret` like
+    /// [`with_secret`](#method.with_secret), returning an error if the
+    /// shielded memory fails authentication.
+    pub fn try_with_secret<R, F>(&mut self, f: F) -> Result<R, ShieldError>
+    where
+        F: FnOnce(&ExposedSecret<'_>) -> R,
+    {
+        self.try_expose(|content| f(&ExposedSecret { content }))
+    }
+}
+
+#[cfg(feature = "std")]
+impl ShieldedCell {
+    /// Call `f` with the decrypted content as an
+    /// [`ExposeSecret`](https://docs.rs/secrecy/latest/secrecy/trait.ExposeSecret.html)
+    /// like [`Shielded::with_secret`](struct.Shielded.html#method.with_secret).
+    /// Blocks while another thread accesses the content. Requires the
+    /// `secrecy` feature.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_with_secret`](#method.try_with_secret) for a fallible version.
+    pub fn with_secret<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&ExposedSecret<'_>) -> R,
+    {
+        self.try_with_secret(f).expect("unshield memory")
+    }
+
+    /// Call `f` with the decrypted content as an `ExposeSecret` like
+    /// [`with_secret`](#method.with_secret), returning an error if the
+    /// shielded memory fails authentication.
+    pub fn try_with_secret<R, F>(&self, f: F) -> Result<R, ShieldError>
+    where
+        F: FnOnce(&ExposedSecret<'_>) -> R,
+    {
+        self.try_expose(|content| f(&ExposedSecret { content }))
+    }
+}
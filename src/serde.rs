@@ -0,0 +1,171 @@
+//! Shielded fields of serde structs. Requires the `serde` feature.
+//!
+//! [`Shielded`](../struct.Shielded.html) implements `Deserialize`, so a
+//! secret in a config file is shielded as it is deserialized, from a string
+//! or from bytes. Strings and byte buffers the deserializer hands over are
+//! wiped once shielded, and sequences of bytes are collected straight into
+//! secret memory. Borrowed input stays the caller's to wipe.
+//!
+//! `Shielded` deliberately doesn't implement `Serialize`, so its content is
+//! never written out in plaintext by accident. To persist a secret, serialize
+//! an [`Exported`](struct.Exported.html) instead, which holds the content
+//! encrypted under a key-encryption key (KEK) in the authenticated
+//! [export format](../struct.Shielded.html#method.export).
+//!
+//! ```
+//! use serde::Deserialize;
+//! use shielded::Shielded;
+//!
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     user: String,
+//!     password: Shielded,
+//! }
+//!
+//! let json = r#"{ "user": "admin", "password": "hunter2" }"#;
+//! let mut config: Config = serde_json::from_str(json).unwrap();
+//! assert_eq!("admin", config.user);
+//! assert_eq!(b"hunter2", config.password.unshield().as_ref());
+//! ```
+
+use core::fmt;
+
+use ::serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use ::serde::ser::{Serialize, Serializer};
+
+use crate::{Format, ShieldError, Shielded, ShieldedBuffer};
+
+impl<'de> Deserialize<'de> for Shielded {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_byte_buf(ShieldedVisitor)
+    }
+}
+
+struct ShieldedVisitor;
+
+impl<'de> Visitor<'de> for ShieldedVisitor {
+    type Value = Shielded;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string or bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Shielded, E> {
+        self.visit_bytes(v.as_bytes())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Shielded, E> {
+        self.visit_byte_buf(v.into_bytes())
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Shielded, E> {
+        let mut buffer = ShieldedBuffer::new();
+        buffer.extend_from_slice(v).map_err(E::custom)?;
+        buffer.finish().map_err(E::custom)
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Shielded, E> {
+        Shielded::try_new(v).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Shielded, A::Error> {
+        let mut buffer = ShieldedBuffer::new();
+        while let Some(byte) = seq.next_element::<u8>()? {
+            buffer
+                .extend_from_slice(&[byte])
+                .map_err(de::Error::custom)?;
+        }
+        buffer.finish().map_err(de::Error::custom)
+    }
+}
+
+/// The content of [`Shielded`](../struct.Shielded.html) memory exported
+/// under a key-encryption key (KEK), for serializing a secret.
+///
+/// Serialized as the bytes of the
+/// [exported blob](../struct.Shielded.html#method.export), and only
+/// deserialized from such a blob. The KEK is never serialized, and importing
+/// the blob again needs it.
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use shielded::serde::Exported;
+/// use shielded::Shielded;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct State {
+///     token: Exported,
+/// }
+///
+/// let kek = [7u8; 32];
+/// let mut token = Shielded::new(b"hunter2".to_vec());
+/// let state = State {
+///     token: Exported::new(&mut token, &kek).unwrap(),
+/// };
+/// let bytes = bincode::serialize(&state).unwrap();
+///
+/// let state: State = bincode::deserialize(&bytes).unwrap();
+/// let mut token = state.token.import(&kek).unwrap();
+/// assert_eq!(b"hunter2", token.unshield().as_ref());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Exported(Vec<u8>);
+
+impl Exported {
+    /// Export the content of `shielded` under `kek`, see
+    /// [`Shielded::try_export`](../struct.Shielded.html#method.try_export).
+    pub fn new(shielded: &mut Shielded, kek: &[u8]) -> Result<Self, ShieldError> {
+        shielded.try_export(kek).map(Exported)
+    }
+
+    /// Construct `Shielded` memory with the default settings holding the
+    /// exported content, see
+    /// [`Shielded::import`](../struct.Shielded.html#method.import).
+    pub fn import(&self, kek: &[u8]) -> Result<Shielded, ShieldError> {
+        Shielded::import(&self.0, kek)
+    }
+
+    /// The exported blob.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Serialize for Exported {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Exported {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_byte_buf(ExportedVisitor)
+    }
+}
+
+struct ExportedVisitor;
+
+impl<'de> Visitor<'de> for ExportedVisitor {
+    type Value = Exported;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("exported shielded memory")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Exported, E> {
+        self.visit_byte_buf(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Exported, E> {
+        let _ = Format::of(&v).map_err(E::custom)?;
+        Ok(Exported(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Exported, A::Error> {
+        let mut blob = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            blob.push(byte);
+        }
+        self.visit_byte_buf(blob)
+    }
+}
@@ -0,0 +1,166 @@
+//! Shamir's secret sharing of shielded memory over GF(2^8).
+//!
+//! Every byte of the content is shared with a polynomial of its own. A share
+//! is its x-coordinate followed by the y-coordinates for every byte. The
+//! arithmetic is constant time.
+
+use alloc::vec::Vec;
+
+use crate::{fill_random, Key, ShieldError, Shielded, ShieldedBuilder};
+
+impl Shielded {
+    /// Split the content into `n` shares, any `k` of which
+    /// [`combine`](#method.combine) back to the content, with Shamir's secret
+    /// sharing. Fewer than `k` shares reveal nothing about the content. Each
+    /// share is a byte longer than the content and is created right in new
+    /// `Shielded` memory with the default settings, so neither the content
+    /// nor the shares are ever handed out. See
+    /// [`ShieldedBuilder::split`](struct.ShieldedBuilder.html#method.split)
+    /// for other settings.
+    ///
+    /// ```
+    /// use shielded::Shielded;
+    ///
+    /// let mut root = Shielded::new(b"root key".to_vec());
+    /// let mut shares = root.split(5, 3);
+    /// let mut combined = Shielded::combine(&mut shares[1..4]).unwrap();
+    /// assert_eq!(b"root key", combined.unshield().as_ref());
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero or greater than `n`, the shielded memory fails
+    /// authentication or the shares can't be shielded. See
+    /// [`try_split`](#method.try_split) for a fallible version.
+    pub fn split(&mut self, n: u8, k: u8) -> Vec<Shielded> {
+        self.try_split(n, k).expect("split memory")
+    }
+
+    /// Split the content into shares like [`split`](#method.split), returning
+    /// an error if the shielded memory fails authentication or the shares
+    /// can't be shielded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero or greater than `n`.
+    pub fn try_split(&mut self, n: u8, k: u8) -> Result<Vec<Shielded>, ShieldError> {
+        split(self, n, k, &ShieldedBuilder::new())
+    }
+
+    /// Construct `Shielded` memory with the default settings holding the
+    /// content combined from `shares` created by [`split`](#method.split). See
+    /// [`ShieldedBuilder::combine`](struct.ShieldedBuilder.html#method.combine)
+    /// for other settings.
+    ///
+    /// Fewer shares than were needed when splitting combine to garbage, which
+    /// can't be detected. Returns
+    /// [`ShieldError::Encoding`](enum.ShieldError.html#variant.Encoding) if
+    /// there are no shares, they differ in length or two of them are the same
+    /// share.
+    pub fn combine(shares: &mut [Shielded]) -> Result<Self, ShieldError> {
+        Self::builder().combine(shares)
+    }
+}
+
+pub(crate) fn split(
+    shielded: &mut Shielded,
+    n: u8,
+    k: u8,
+    builder: &ShieldedBuilder,
+) -> Result<Vec<Shielded>, ShieldError> {
+    assert!(0 < k && k <= n, "k must be between 1 and n");
+    let entropy = shielded.entropy.clone();
+
+    shielded.try_expose(|secret| {
+        let len = secret.len();
+        let mut coefficients = Key::new((k as usize - 1) * len);
+        fill_random(entropy.as_deref(), &mut coefficients.0)?;
+
+        (1..=n)
+            .map(|x| {
+                Shielded::with_content(len + 1, builder, |share| {
+                    share[0] = x;
+                    for (i, y) in share[1..=len].iter_mut().enumerate() {
+                        // Horner's method, from the highest coefficient down.
+                        let mut sum = 0;
+                        for coefficient in coefficients.0.iter().skip(i).step_by(len).rev() {
+                            sum = mul(sum, x) ^ coefficient;
+                        }
+                        *y = mul(sum, x) ^ secret[i];
+                    }
+                    Ok(())
+                })
+            })
+            .collect()
+    })?
+}
+
+pub(crate) fn combine(
+    shares: &mut [Shielded],
+    builder: &ShieldedBuilder,
+) -> Result<Shielded, ShieldError> {
+    let mut xs = Vec::with_capacity(shares.len());
+    for share in shares.iter_mut() {
+        let x = share.try_expose(|share| share.first().copied())?;
+        xs.push(x.ok_or(ShieldError::Encoding)?);
+    }
+    let mut sorted = xs.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+    if sorted.len() != xs.len() || sorted.first().is_none_or(|x| *x == 0) {
+        return Err(ShieldError::Encoding);
+    }
+
+    let len = shares[0].try_expose(|share| share.len())? - 1;
+    Shielded::with_content(len, builder, |secret| {
+        let secret = &mut secret[..len];
+        secret.iter_mut().for_each(|byte| *byte = 0);
+        for (share, &x) in shares.iter_mut().zip(&xs) {
+            // The Lagrange basis polynomial of the share at zero.
+            let basis = xs
+                .iter()
+                .filter(|&&other| other != x)
+                .fold(1, |basis, &other| {
+                    mul(basis, mul(other, inverse(other ^ x)))
+                });
+            share.try_expose(|share| {
+                if share.len() != len + 1 {
+                    return Err(ShieldError::Encoding);
+                }
+                for (byte, y) in secret.iter_mut().zip(&share[1..]) {
+                    *byte ^= mul(*y, basis);
+                }
+                Ok(())
+            })??;
+        }
+        Ok(())
+    })
+}
+
+// Multiplication in GF(2^8) with the AES polynomial, without branches or
+// lookups depending on the operands.
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+// Multiplicative inverse in GF(2^8) as a^254.
+fn inverse(a: u8) -> u8 {
+    let a2 = mul(a, a);
+    let a3 = mul(a2, a);
+    let a6 = mul(a3, a3);
+    let a12 = mul(a6, a6);
+    let a15 = mul(a12, a3);
+    let a30 = mul(a15, a15);
+    let a60 = mul(a30, a30);
+    let a120 = mul(a60, a60);
+    let a126 = mul(a120, a6);
+    let a252 = mul(a126, a126);
+    mul(a252, a2)
+}
@@ -0,0 +1,83 @@
+use core::convert::TryInto;
+
+use ed25519_dalek::{Signer, SigningKey};
+
+use crate::{ShieldError, Shielded, ShieldedBuilder};
+
+/// Length of an Ed25519 seed.
+const SEED_LEN: usize = 32;
+
+/// An Ed25519 signing key whose seed is kept in
+/// [`Shielded`](struct.Shielded.html) memory. Requires the `ed25519` feature.
+///
+/// The seed is decrypted only for the duration of a signature and never
+/// handed out. The expanded signing key is wiped right after signing.
+///
+/// ```
+/// use shielded::ShieldedSigningKey;
+///
+/// let mut key = ShieldedSigningKey::generate().unwrap();
+/// let signature = key.sign(b"message");
+/// assert_eq!(64, signature.len());
+/// # drop(key.public_key());
+/// ```
+pub struct ShieldedSigningKey {
+    seed: Shielded,
+    public_key: [u8; 32],
+}
+
+impl ShieldedSigningKey {
+    /// Generate a new signing key, shielded with the default settings. See
+    /// [`with_builder`](#method.with_builder) for other settings.
+    pub fn generate() -> Result<Self, ShieldError> {
+        Self::with_builder(ShieldedBuilder::new())
+    }
+
+    /// Generate a new signing key, shielded with the settings of `builder`.
+    pub fn with_builder(builder: ShieldedBuilder) -> Result<Self, ShieldError> {
+        Self::from_shielded(builder.random(SEED_LEN)?)
+    }
+
+    /// Use the 32-byte Ed25519 seed held in `seed` as the signing key.
+    ///
+    /// Returns [`ShieldError::Encoding`](enum.ShieldError.html#variant.Encoding)
+    /// if `seed` doesn't hold 32 bytes.
+    pub fn from_shielded(mut seed: Shielded) -> Result<Self, ShieldError> {
+        let public_key =
+            seed.try_expose(|seed| signing_key(seed).map(|key| key.verifying_key().to_bytes()))??;
+        Ok(Self { seed, public_key })
+    }
+
+    /// The public key verifying the signatures.
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public_key
+    }
+
+    /// Sign `message`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_sign`](#method.try_sign) for a fallible version.
+    pub fn sign(&mut self, message: &[u8]) -> [u8; 64] {
+        self.try_sign(message).expect("unshield memory")
+    }
+
+    /// Sign `message` like [`sign`](#method.sign), returning an error if the
+    /// shielded memory fails authentication.
+    pub fn try_sign(&mut self, message: &[u8]) -> Result<[u8; 64], ShieldError> {
+        self.seed
+            .try_expose(|seed| signing_key(seed).map(|key| key.sign(message).to_bytes()))?
+    }
+
+    /// Unwrap the `Shielded` memory holding the seed.
+    pub fn into_inner(self) -> Shielded {
+        self.seed
+    }
+}
+
+// The signing key of `seed`. It wipes itself when dropped.
+pub(crate) fn signing_key(seed: &[u8]) -> Result<SigningKey, ShieldError> {
+    let seed: &[u8; SEED_LEN] = seed.try_into().map_err(|_| ShieldError::Encoding)?;
+    Ok(SigningKey::from_bytes(seed))
+}
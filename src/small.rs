@@ -0,0 +1,273 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::crypto::{Crypto, CryptoBackend, KEY_LEN, MAX_NONCE_LEN};
+use crate::entropy::EntropySource;
+use crate::mem::SecretBuf;
+use crate::protection::ProtectionStatus;
+use crate::{
+    derive_key, fill_random, Cipher, LockMode, ShieldError, ShieldedBuilder, SHIELD_PREKEY_MIN_LEN,
+};
+
+/// A secret of at most [`MAX_LEN`](#associatedconstant.MAX_LEN) bytes, e.g.
+/// a key or a token, kept encrypted like in
+/// [`Shielded`](struct.Shielded.html) memory but without its per-exposure
+/// costs.
+///
+/// The prekey and the shielded secret share a single buffer allocated once,
+/// and the nonce and the encryption key are kept in fixed-size arrays, so
+/// unshielding and shielding again never allocate. The prekey defaults to
+/// 1 KiB instead of 16 KiB. Shielding a small secret again is several times
+/// faster than with `Shielded`, see `examples/small.rs`.
+///
+/// Only some settings of a [`ShieldedBuilder`](struct.ShieldedBuilder.html)
+/// apply, see [`build_small`](struct.ShieldedBuilder.html#method.build_small).
+/// The secret is shielded under a new prekey and nonce after every exposure.
+///
+/// ```
+/// use shielded::ShieldedSmall;
+///
+/// let mut token = *b"hunter2";
+/// let mut shielded = ShieldedSmall::new(&mut token);
+/// assert_eq!([0; 7], token);
+/// assert_eq!(b"hunter2", shielded.unshield().as_ref());
+/// ```
+pub struct ShieldedSmall {
+    // The prekey followed by the ciphertext of the secret and its tag.
+    buf: SecretBuf,
+    prekey_len: usize,
+    len: usize,
+    nonce: [u8; MAX_NONCE_LEN],
+    cipher: Cipher,
+    context: Vec<u8>,
+    entropy: Option<Arc<dyn EntropySource>>,
+}
+
+impl ShieldedSmall {
+    /// The longest secret a `ShieldedSmall` can hold.
+    pub const MAX_LEN: usize = 64;
+
+    /// Shield `secret` with a 1 KiB prekey and otherwise default settings,
+    /// wiping `secret`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `secret` is longer than
+    /// [`MAX_LEN`](#associatedconstant.MAX_LEN) or can't be shielded. See
+    /// [`try_new`](#method.try_new) for a fallible version.
+    pub fn new(secret: &mut [u8]) -> Self {
+        Self::try_new(secret).expect("shield new memory")
+    }
+
+    /// Shield `secret` like [`new`](#method.new), returning an error if it
+    /// can't be shielded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `secret` is longer than
+    /// [`MAX_LEN`](#associatedconstant.MAX_LEN).
+    pub fn try_new(secret: &mut [u8]) -> Result<Self, ShieldError> {
+        ShieldedBuilder::new()
+            .prekey_len(SHIELD_PREKEY_MIN_LEN)
+            .build_small(secret)
+    }
+
+    pub(crate) fn with_builder(
+        secret: &mut [u8],
+        builder: &ShieldedBuilder,
+    ) -> Result<Self, ShieldError> {
+        assert!(
+            secret.len() <= Self::MAX_LEN,
+            "a small secret holds at most {} bytes",
+            Self::MAX_LEN
+        );
+        let prekey_len = builder.prekey_len * builder.cipher.keys();
+        let buf_len = prekey_len + Self::MAX_LEN + builder.cipher.tag_len();
+        let mut shielded = Self {
+            buf: SecretBuf::new(buf_len, builder.prekey_options()),
+            prekey_len,
+            len: secret.len(),
+            nonce: [0; MAX_NONCE_LEN],
+            cipher: builder.cipher,
+            context: builder.context.clone(),
+            entropy: builder.entropy.clone(),
+        };
+        if builder.lock == LockMode::Required && !shielded.buf.is_locked() {
+            return Err(ShieldError::Lock);
+        }
+        if !ProtectionStatus::of(&shielded.buf).satisfies(&builder.protection) {
+            return Err(ShieldError::Unprotected);
+        }
+
+        shielded.buf[prekey_len..prekey_len + secret.len()].copy_from_slice(secret);
+        secret.zeroize();
+        shielded.shield()?;
+        Ok(shielded)
+    }
+
+    /// Length of the secret.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the secret is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Decrypt the secret in-place. It is shielded again under a new prekey
+    /// and nonce when the returned guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_unshield`](#method.try_unshield) for a fallible version.
+    pub fn unshield(&mut self) -> UnShieldedSmall<'_> {
+        self.try_unshield().expect("unshield memory")
+    }
+
+    /// Decrypt the secret in-place like [`unshield`](#method.unshield),
+    /// returning an error if the shielded memory fails authentication.
+    pub fn try_unshield(&mut self) -> Result<UnShieldedSmall<'_>, ShieldError> {
+        self.buf.exclude_from_wipe();
+        if let Err(e) = self.open() {
+            self.buf.include_in_wipe();
+            return Err(e);
+        }
+        Ok(UnShieldedSmall { shielded: self })
+    }
+
+    /// Call `f` with the decrypted secret and shield it again once `f`
+    /// returns or panics.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_expose`](#method.try_expose) for a fallible version.
+    pub fn expose<R, F>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        self.try_expose(f).expect("unshield memory")
+    }
+
+    /// Call `f` with the decrypted secret like [`expose`](#method.expose),
+    /// returning an error if the shielded memory fails authentication.
+    pub fn try_expose<R, F>(&mut self, f: F) -> Result<R, ShieldError>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        let unshielded = self.try_unshield()?;
+        Ok(f(&unshielded))
+    }
+
+    // Encrypt the plaintext under a new prekey and nonce, and let `wipe_all`
+    // wipe it.
+    fn shield(&mut self) -> Result<(), ShieldError> {
+        let entropy = self.entropy.as_deref();
+        fill_random(entropy, &mut self.buf[..self.prekey_len])?;
+        fill_random(entropy, &mut self.nonce[..self.cipher.nonce_len()])?;
+        let key = self.key()?;
+
+        let nonce_len = self.cipher.nonce_len();
+        let end = self.prekey_len + self.len + self.cipher.tag_len();
+        let (prekey, sealed) = self.buf[..end].split_at_mut(self.prekey_len);
+        let (payload, tag) = sealed.split_at_mut(self.len);
+        Crypto::seal(
+            self.cipher,
+            &key[..self.cipher.key_len()],
+            &self.nonce[..nonce_len],
+            prekey,
+            payload,
+            tag,
+        )?;
+        self.buf.include_in_wipe();
+        Ok(())
+    }
+
+    // Decrypt the secret in-place.
+    fn open(&mut self) -> Result<(), ShieldError> {
+        let key = self.key()?;
+        let nonce_len = self.cipher.nonce_len();
+        let end = self.prekey_len + self.len + self.cipher.tag_len();
+        let (prekey, sealed) = self.buf[..end].split_at_mut(self.prekey_len);
+        let _ = Crypto::open(
+            self.cipher,
+            &key[..self.cipher.key_len()],
+            &self.nonce[..nonce_len],
+            prekey,
+            sealed,
+        )?;
+        Ok(())
+    }
+
+    // The encryption key, derived from the prekey into an array which is
+    // wiped when dropped.
+    fn key(&self) -> Result<Zeroizing<[u8; 2 * KEY_LEN]>, ShieldError> {
+        let mut key = Zeroizing::new([0u8; 2 * KEY_LEN]);
+        derive_key(
+            self.cipher,
+            &self.buf[..self.prekey_len],
+            &self.context,
+            &mut key[..self.cipher.key_len()],
+        )?;
+        Ok(key)
+    }
+}
+
+impl fmt::Debug for ShieldedSmall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShieldedSmall")
+            .field("len", &self.len)
+            .field("cipher", &self.cipher)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The decrypted secret of a [`ShieldedSmall`](struct.ShieldedSmall.html).
+/// After `UnShieldedSmall` goes out of scope or is dropped, the secret is
+/// shielded again under a new prekey and nonce.
+pub struct UnShieldedSmall<'a> {
+    shielded: &'a mut ShieldedSmall,
+}
+
+impl<'a> Deref for UnShieldedSmall<'a> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        let start = self.shielded.prekey_len;
+        &self.shielded.buf[start..start + self.shielded.len]
+    }
+}
+
+impl<'a> DerefMut for UnShieldedSmall<'a> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        let start = self.shielded.prekey_len;
+        &mut self.shielded.buf[start..start + self.shielded.len]
+    }
+}
+
+impl<'a> AsRef<[u8]> for UnShieldedSmall<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<'a> AsMut<[u8]> for UnShieldedSmall<'a> {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl<'a> Drop for UnShieldedSmall<'a> {
+    fn drop(&mut self) {
+        if self.shielded.shield().is_err() {
+            // Nothing decrypts any more, and nothing is left in the clear.
+            self.shielded.buf.zeroize();
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{ShieldError, Shielded, ShieldedBuilder};
+
+/// A collection of named secrets, each kept in
+/// [`Shielded`](struct.Shielded.html) memory of its own with its own prekey
+/// and nonce.
+///
+/// ```
+/// use shielded::ShieldedStore;
+///
+/// let mut store = ShieldedStore::new();
+/// store.insert("db password", b"hunter2".to_vec()).unwrap();
+/// store.insert("api token", b"0123456789abcdef".to_vec()).unwrap();
+///
+/// let len = store.expose("db password", |password| password.len());
+/// assert_eq!(Some(7), len);
+///
+/// assert!(store.remove("api token"));
+/// assert_eq!(None, store.expose("api token", |token| token.len()));
+/// ```
+pub struct ShieldedStore {
+    builder: ShieldedBuilder,
+    entries: BTreeMap<String, Shielded>,
+}
+
+impl ShieldedStore {
+    /// Create an empty store whose entries are shielded with the default
+    /// settings. See
+    /// [`ShieldedBuilder::store`](struct.ShieldedBuilder.html#method.store)
+    /// for other settings.
+    pub fn new() -> Self {
+        Self::with_builder(ShieldedBuilder::new())
+    }
+
+    pub(crate) fn with_builder(builder: ShieldedBuilder) -> Self {
+        Self {
+            builder,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the store has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns `true` if the store has an entry called `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// The names of the entries, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Shield `buf` as the entry called `name`, replacing and wiping any
+    /// previous entry of that name. The buffer of `buf` is wiped.
+    ///
+    /// Returns an error if the memory can't be shielded. The store is left
+    /// unchanged then.
+    pub fn insert(&mut self, name: &str, buf: Vec<u8>) -> Result<(), ShieldError> {
+        let shielded = self.builder.clone().build(buf)?;
+        let _ = self.entries.insert(String::from(name), shielded);
+        Ok(())
+    }
+
+    /// Remove the entry called `name`, wiping its memory. Returns `true` if
+    /// there was such an entry.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.entries.remove(name).is_some()
+    }
+
+    /// Mutable access to the `Shielded` memory of the entry called `name`.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Shielded> {
+        self.entries.get_mut(name)
+    }
+
+    /// Call `f` with the decrypted content of the entry called `name`, see
+    /// [`Shielded::expose`](struct.Shielded.html#method.expose). Returns
+    /// `None` if there is no such entry.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_expose`](#method.try_expose) for a fallible version.
+    pub fn expose<R, F>(&mut self, name: &str, f: F) -> Option<R>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        self.try_expose(name, f).expect("unshield memory")
+    }
+
+    /// Call `f` with the decrypted content of the entry called `name` like
+    /// [`expose`](#method.expose), returning an error if the shielded memory
+    /// fails authentication.
+    pub fn try_expose<R, F>(&mut self, name: &str, f: F) -> Result<Option<R>, ShieldError>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        match self.entries.get_mut(name) {
+            Some(shielded) => shielded.try_expose(f).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Shield every entry again under a new prekey and nonce, e.g.
+    /// periodically or after a suspected compromise.
+    ///
+    /// Stops at the first entry which fails authentication or can't be
+    /// shielded again, and returns the error. The content of an entry which
+    /// can't be shielded again is wiped.
+    pub fn rotate_all(&mut self) -> Result<(), ShieldError> {
+        self.entries.values_mut().try_for_each(Shielded::rotate)
+    }
+}
+
+impl Default for ShieldedStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,91 @@
+use alloc::string::String;
+use core::ops::Deref;
+use core::str;
+
+use crate::{ShieldError, Shielded, UnShielded};
+
+/// A `String` kept in [`Shielded`](struct.Shielded.html) memory.
+///
+/// The contents are valid UTF-8 by construction, so unshielding hands out a
+/// `&str` without validating the contents again.
+///
+/// ```
+/// use shielded::ShieldedString;
+///
+/// let mut password = ShieldedString::new(String::from("hunter2"));
+/// assert_eq!("hunter2", &*password.unshield());
+/// ```
+#[derive(Debug)]
+pub struct ShieldedString(Shielded);
+
+impl ShieldedString {
+    /// Construct a new `ShieldedString`. The buffer of `s` is wiped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the string can't be shielded. See
+    /// [`try_new`](#method.try_new) for a fallible version.
+    pub fn new(s: String) -> Self {
+        Self::try_new(s).expect("shield new string")
+    }
+
+    /// Construct a new `ShieldedString`, returning an error if the string
+    /// can't be shielded. The buffer of `s` is wiped.
+    pub fn try_new(s: String) -> Result<Self, ShieldError> {
+        Shielded::try_new(s.into_bytes()).map(ShieldedString)
+    }
+
+    /// Decrypt the string in-place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_unshield`](#method.try_unshield) for a fallible version.
+    pub fn unshield(&mut self) -> UnShieldedString<'_> {
+        self.try_unshield().expect("unshield string")
+    }
+
+    /// Decrypt the string in-place, returning an error if the shielded memory
+    /// fails authentication.
+    pub fn try_unshield(&mut self) -> Result<UnShieldedString<'_>, ShieldError> {
+        self.0.try_unshield().map(UnShieldedString)
+    }
+
+    /// Length of the string in bytes. Known without decrypting the memory.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the string is empty. Known without decrypting the
+    /// memory.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for ShieldedString {
+    fn from(s: String) -> Self {
+        ShieldedString::new(s)
+    }
+}
+
+/// The decrypted contents of a [`ShieldedString`](struct.ShieldedString.html).
+/// After `UnShieldedString` goes out of scope or is dropped, the string is
+/// encrypted again.
+pub struct UnShieldedString<'a>(UnShielded<'a>);
+
+impl<'a> Deref for UnShieldedString<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        // The contents came from a `String` and are authenticated on unshield.
+        // `UnShieldedString` never hands out mutable access to them.
+        unsafe { str::from_utf8_unchecked(self.0.as_ref()) }
+    }
+}
+
+impl<'a> AsRef<str> for UnShieldedString<'a> {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
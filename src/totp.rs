@@ -0,0 +1,203 @@
+use alloc::format;
+use alloc::string::String;
+
+use zeroize::Zeroize;
+
+use crate::crypto::{Crypto, CryptoBackend};
+use crate::{ShieldError, Shielded, ShieldedBuilder};
+
+/// The HMAC hash function of a [`ShieldedTotp`](struct.ShieldedTotp.html).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TotpAlgorithm {
+    /// HMAC-SHA-1. This is the default, and the only one most authenticator
+    /// apps support.
+    #[default]
+    Sha1,
+    /// HMAC-SHA-256.
+    Sha256,
+    /// HMAC-SHA-512.
+    Sha512,
+}
+
+/// A generator of RFC 6238 time-based one-time passwords (TOTP), whose seed
+/// is kept in [`Shielded`](struct.Shielded.html) memory. Requires the `totp`
+/// feature.
+///
+/// The seed is decrypted only for the duration of the HMAC computing a code
+/// and never handed out. Codes have 6 digits and change every 30 seconds
+/// counted from the Unix epoch by default, like most authenticator apps
+/// expect.
+///
+/// ```
+/// use shielded::ShieldedTotp;
+///
+/// // The seed as shown to the user when enrolling, e.g. in a QR code.
+/// let seed = String::from("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+/// let mut totp = ShieldedTotp::from_base32(seed).unwrap().digits(8);
+/// assert_eq!("94287082", totp.code_at(59));
+/// ```
+pub struct ShieldedTotp {
+    seed: Shielded,
+    algorithm: TotpAlgorithm,
+    digits: u32,
+    period: u64,
+}
+
+impl ShieldedTotp {
+    /// Use the base32 encoded seed `encoded`, shielded with the default
+    /// settings. See
+    /// [`ShieldedBuilder::build_totp`](struct.ShieldedBuilder.html#method.build_totp)
+    /// for other settings.
+    ///
+    /// The seed is decoded right into the memory which is then shielded, and
+    /// `encoded` is wiped. The RFC 4648 alphabet is accepted in either case,
+    /// with or without padding, and whitespace is ignored.
+    ///
+    /// Returns [`ShieldError::Encoding`](enum.ShieldError.html#variant.Encoding)
+    /// if `encoded` isn't base32 or holds no seed.
+    pub fn from_base32(encoded: String) -> Result<Self, ShieldError> {
+        ShieldedBuilder::new().build_totp(encoded)
+    }
+
+    /// Use the seed held in `seed`.
+    pub fn from_shielded(seed: Shielded) -> Self {
+        Self {
+            seed,
+            algorithm: TotpAlgorithm::default(),
+            digits: 6,
+            period: 30,
+        }
+    }
+
+    /// Set the HMAC hash function.
+    pub fn algorithm(mut self, algorithm: TotpAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Set the number of digits of the codes.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `digits` is 6, 7 or 8, which RFC 4226 allows.
+    pub fn digits(mut self, digits: u32) -> Self {
+        assert!((6..=8).contains(&digits), "TOTP codes have 6 to 8 digits");
+        self.digits = digits;
+        self
+    }
+
+    /// Set the number of seconds a code is valid for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is zero.
+    pub fn period(mut self, period: u64) -> Self {
+        assert!(period > 0, "TOTP period must be positive");
+        self.period = period;
+        self
+    }
+
+    /// The code valid at `time`, in seconds since the Unix epoch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_code_at`](#method.try_code_at) for a fallible version.
+    pub fn code_at(&mut self, time: u64) -> String {
+        self.try_code_at(time).expect("unshield memory")
+    }
+
+    /// The code valid at `time` like [`code_at`](#method.code_at), returning
+    /// an error if the shielded memory fails authentication.
+    pub fn try_code_at(&mut self, time: u64) -> Result<String, ShieldError> {
+        let counter = (time / self.period).to_be_bytes();
+        let algorithm = self.algorithm;
+        let code = self.seed.try_expose(|seed| match algorithm {
+            TotpAlgorithm::Sha1 => truncate(&Crypto::hmac_sha1(seed, &counter)),
+            TotpAlgorithm::Sha256 => truncate(&Crypto::hmac_sha256(seed, &counter)),
+            TotpAlgorithm::Sha512 => truncate(&Crypto::hmac_sha512(seed, &counter)),
+        })?;
+        let code = code % 10u32.pow(self.digits);
+        Ok(format!("{:0width$}", code, width = self.digits as usize))
+    }
+
+    /// The code valid now.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_code`](#method.try_code) for a fallible version.
+    #[cfg(feature = "std")]
+    pub fn code(&mut self) -> String {
+        self.try_code().expect("unshield memory")
+    }
+
+    /// The code valid now like [`code`](#method.code), returning an error if
+    /// the shielded memory fails authentication.
+    #[cfg(feature = "std")]
+    pub fn try_code(&mut self) -> Result<String, ShieldError> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        self.try_code_at(now)
+    }
+
+    /// Unwrap the `Shielded` memory holding the seed.
+    pub fn into_inner(self) -> Shielded {
+        self.seed
+    }
+}
+
+// The dynamic truncation of RFC 4226 to 31 bits.
+fn truncate(mac: &[u8]) -> u32 {
+    let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+    let mut bits = [0; 4];
+    bits.copy_from_slice(&mac[offset..offset + 4]);
+    u32::from_be_bytes(bits) & 0x7fff_ffff
+}
+
+pub(crate) fn from_base32(
+    mut encoded: String,
+    builder: &ShieldedBuilder,
+) -> Result<ShieldedTotp, ShieldError> {
+    let result = decode(&encoded, builder);
+    encoded.zeroize();
+    result.map(ShieldedTotp::from_shielded)
+}
+
+// Decode the base32 in `encoded` right into shielded memory.
+fn decode(encoded: &str, builder: &ShieldedBuilder) -> Result<Shielded, ShieldError> {
+    let encoded = encoded.trim_end_matches(|c: char| c == '=' || c.is_ascii_whitespace());
+    let symbols = encoded.chars().filter(|c| !c.is_ascii_whitespace()).count();
+    // A final group of 1, 3 or 6 symbols doesn't end on a byte.
+    let len = symbols * 5 / 8;
+    if len == 0 || matches!(symbols % 8, 1 | 3 | 6) {
+        return Err(ShieldError::Encoding);
+    }
+
+    Shielded::with_content(len, builder, |memory| {
+        let mut bits = 0u16;
+        let mut pending = 0;
+        let mut written = 0;
+        let mut result = Ok(());
+        for c in encoded.bytes().filter(|c| !c.is_ascii_whitespace()) {
+            let value = match c.to_ascii_uppercase() {
+                c @ b'A'..=b'Z' => c - b'A',
+                c @ b'2'..=b'7' => c - b'2' + 26,
+                _ => {
+                    result = Err(ShieldError::Encoding);
+                    break;
+                }
+            };
+            bits = bits << 5 | u16::from(value);
+            pending += 5;
+            if pending >= 8 {
+                pending -= 8;
+                memory[written] = (bits >> pending) as u8;
+                written += 1;
+            }
+        }
+        bits.zeroize();
+        result
+    })
+}
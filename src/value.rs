@@ -0,0 +1,110 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use zeroize::Zeroize;
+
+use crate::{ShieldError, Shielded};
+
+/// A typed value kept in [`Shielded`](struct.Shielded.html) memory.
+///
+/// The value is serialized with `bincode` when constructed and the original is
+/// wiped. Unshielding deserializes a fresh copy of the value which is wiped
+/// when the returned guard is dropped.
+///
+/// ```
+/// use shielded::ShieldedValue;
+///
+/// let mut token = ShieldedValue::new(String::from("hunter2"));
+/// assert_eq!("hunter2", token.unshield().as_str());
+/// ```
+pub struct ShieldedValue<T> {
+    shielded: Shielded,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ShieldedValue<T>
+where
+    T: Serialize + DeserializeOwned + Zeroize,
+{
+    /// Construct a new `ShieldedValue`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value can't be serialized or shielded. See
+    /// [`try_new`](#method.try_new) for a fallible version.
+    pub fn new(value: T) -> Self {
+        Self::try_new(value).expect("shield new value")
+    }
+
+    /// Construct a new `ShieldedValue`, returning an error if the value can't
+    /// be serialized or shielded.
+    pub fn try_new(mut value: T) -> Result<Self, ShieldError> {
+        let buf = encode(&value);
+        value.zeroize();
+        Ok(Self {
+            shielded: Shielded::try_new(buf?)?,
+            marker: PhantomData,
+        })
+    }
+
+    /// Decrypt and deserialize the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the shielded memory fails authentication. See
+    /// [`try_unshield`](#method.try_unshield) for a fallible version.
+    pub fn unshield(&mut self) -> UnShieldedValue<'_, T> {
+        self.try_unshield().expect("unshield value")
+    }
+
+    /// Decrypt and deserialize the value, returning an error if the shielded
+    /// memory fails authentication.
+    pub fn try_unshield(&mut self) -> Result<UnShieldedValue<'_, T>, ShieldError> {
+        let value = self
+            .shielded
+            .try_expose(|buf| bincode::deserialize(buf).map_err(|_| ShieldError::Encoding))??;
+        Ok(UnShieldedValue {
+            value,
+            marker: PhantomData,
+        })
+    }
+}
+
+/// A decrypted copy of the value kept in a
+/// [`ShieldedValue`](struct.ShieldedValue.html). The copy is wiped when
+/// `UnShieldedValue` goes out of scope or is dropped.
+pub struct UnShieldedValue<'a, T: Zeroize> {
+    value: T,
+    marker: PhantomData<&'a mut ShieldedValue<T>>,
+}
+
+impl<'a, T: Zeroize> Deref for UnShieldedValue<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T: Zeroize> AsRef<T> for UnShieldedValue<'a, T> {
+    fn as_ref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<'a, T: Zeroize> Drop for UnShieldedValue<'a, T> {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+// Serialize into a buffer of the exact size, so it never reallocates and
+// leaves partial copies of the value behind in freed memory.
+fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, ShieldError> {
+    let size = bincode::serialized_size(value).map_err(|_| ShieldError::Encoding)?;
+    let mut buf = Vec::with_capacity(size as usize);
+    bincode::serialize_into(&mut buf, value).map_err(|_| ShieldError::Encoding)?;
+    Ok(buf)
+}
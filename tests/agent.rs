@@ -0,0 +1,165 @@
+#![cfg(all(feature = "std", not(target_family = "wasm")))]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use shielded::agent::{Agent, KeyConstraints, Request, Response};
+use shielded::{ShieldError, Shielded};
+
+fn hmac(key: &str) -> Request {
+    Request::HmacSha256 {
+        key: key.into(),
+        message: b"what do ya want for nothing?".to_vec(),
+    }
+}
+
+fn mac(response: Result<Response, ShieldError>) -> [u8; 32] {
+    match response.expect("response") {
+        Response::Mac(mac) => mac,
+        _ => panic!("not a MAC"),
+    }
+}
+
+#[test]
+fn test_agent_hmac() {
+    let mut agent = Agent::new();
+    agent.add_key(
+        "jefe",
+        Shielded::new(b"Jefe".to_vec()),
+        KeyConstraints::new(),
+    );
+    assert_eq!(vec!["jefe"], agent.key_names().collect::<Vec<_>>());
+
+    // RFC 4231, test case 2.
+    assert_eq!(
+        [
+            0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e, 0x6a, 0x04, 0x24, 0x26, 0x08, 0x95,
+            0x75, 0xc7, 0x5a, 0x00, 0x3f, 0x08, 0x9d, 0x27, 0x39, 0x83, 0x9d, 0xec, 0x58, 0xb9,
+            0x64, 0xec, 0x38, 0x43
+        ],
+        mac(agent.handle(hmac("jefe")))
+    );
+    assert_eq!(Some(1), agent.uses("jefe"));
+
+    assert_eq!(
+        ShieldError::UnknownKey,
+        agent.handle(hmac("other")).err().unwrap()
+    );
+    assert!(agent.remove_key("jefe"));
+    assert_eq!(None, agent.uses("jefe"));
+}
+
+#[test]
+fn test_agent_max_uses() {
+    let mut agent = Agent::new();
+    let constraints = KeyConstraints::new().max_uses(2);
+    agent.add_key("key", Shielded::new(b"key".to_vec()), constraints);
+
+    let _ = mac(agent.handle(hmac("key")));
+    let _ = mac(agent.handle(hmac("key")));
+    assert_eq!(
+        ShieldError::UnknownKey,
+        agent.handle(hmac("key")).err().unwrap()
+    );
+}
+
+#[test]
+fn test_agent_confirm() {
+    let mut agent = Agent::new();
+    let constraints = KeyConstraints::new().confirm(true);
+    agent.add_key("key", Shielded::new(b"key".to_vec()), constraints);
+    assert_eq!(
+        ShieldError::Denied,
+        agent.handle(hmac("key")).err().unwrap()
+    );
+
+    let asked = Arc::new(AtomicUsize::new(0));
+    let counter = asked.clone();
+    agent.confirm_with(move |request| {
+        let _ = counter.fetch_add(1, Ordering::SeqCst);
+        request.key() == "key" && counter.load(Ordering::SeqCst) == 1
+    });
+    let _ = mac(agent.handle(hmac("key")));
+    assert_eq!(
+        ShieldError::Denied,
+        agent.handle(hmac("key")).err().unwrap()
+    );
+    assert_eq!(2, asked.load(Ordering::SeqCst));
+    assert_eq!(Some(1), agent.uses("key"));
+}
+
+#[test]
+fn test_agent_decrypt() {
+    let mut kek = Shielded::builder().random(32).expect("random");
+    let ciphertext = kek.wrap(b"data key");
+
+    let mut agent = Agent::new();
+    agent.add_key("kek", kek, KeyConstraints::new());
+    let request = Request::Decrypt {
+        key: "kek".into(),
+        ciphertext,
+    };
+    match agent.handle(request).expect("decrypt") {
+        Response::Plaintext(mut plaintext) => {
+            assert_eq!(b"data key", plaintext.unshield().as_ref())
+        }
+        _ => panic!("not a plaintext"),
+    }
+}
+
+#[cfg(feature = "ed25519")]
+#[test]
+fn test_agent_sign() {
+    let mut key = shielded::ShieldedSigningKey::generate().expect("generate");
+    let expected = key.sign(b"message");
+
+    let mut agent = Agent::new();
+    agent.add_key("ed25519", key.into_inner(), KeyConstraints::new());
+    let request = Request::Sign {
+        key: "ed25519".into(),
+        message: b"message".to_vec(),
+    };
+    match agent.handle(request).expect("sign") {
+        Response::Signature(signature) => assert_eq!(expected[..], signature[..]),
+        _ => panic!("not a signature"),
+    }
+}
+
+#[test]
+fn test_agent_spawn() {
+    let mut agent = Agent::new();
+    agent.add_key("key", Shielded::new(b"key".to_vec()), KeyConstraints::new());
+    let expected = mac(agent.handle(hmac("key")));
+
+    let handle = agent.spawn();
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let handle = handle.clone();
+            thread::spawn(move || mac(handle.request(hmac("key"))))
+        })
+        .collect();
+    for thread in threads {
+        assert_eq!(expected, thread.join().expect("join"));
+    }
+}
+
+#[test]
+fn test_agent_disconnected() {
+    let mut agent = Agent::new();
+    agent.add_key(
+        "key",
+        Shielded::new(b"key".to_vec()),
+        KeyConstraints::new().confirm(true),
+    );
+    agent.confirm_with(|_| panic!("no operator"));
+    let handle = agent.spawn();
+    assert_eq!(
+        ShieldError::Disconnected,
+        handle.request(hmac("key")).err().unwrap()
+    );
+    assert_eq!(
+        ShieldError::Disconnected,
+        handle.request(hmac("key")).err().unwrap()
+    );
+}
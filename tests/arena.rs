@@ -0,0 +1,183 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Arc;
+
+use shielded::entropy::EntropySource;
+use shielded::{Cipher, ShieldError, Shielded, ShieldedArena};
+
+// Counts up until it is told to fail.
+#[derive(Debug)]
+struct Switch(Arc<AtomicBool>, AtomicU8);
+
+impl EntropySource for Switch {
+    fn fill(&self, buf: &mut [u8]) -> Result<(), ShieldError> {
+        if self.0.load(Ordering::SeqCst) {
+            return Err(ShieldError::Rng);
+        }
+        for byte in buf {
+            *byte = self.1.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_arena() {
+    let mut arena = ShieldedArena::new();
+    assert!(arena.is_empty());
+
+    let mut secret = *b"hello world";
+    let handle = arena.insert(&mut secret).unwrap();
+    assert_eq!([0; 11], secret);
+    assert_eq!(1, arena.len());
+    assert!(arena.contains(handle));
+
+    for _ in 0..3 {
+        assert_eq!(
+            Some(b"hello world".to_vec()),
+            arena.expose(handle, |secret| secret.to_vec())
+        );
+    }
+
+    let empty = arena.insert(&mut []).unwrap();
+    assert_eq!(Some(0), arena.expose(empty, |secret| secret.len()));
+    assert_eq!(2, arena.len());
+}
+
+#[test]
+fn test_arena_many() {
+    let mut arena = ShieldedArena::new();
+    let handles: Vec<_> = (0..200u32)
+        .map(|i| {
+            arena
+                .insert(&mut format!("token {}", i).into_bytes())
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(200, arena.len());
+    for (i, &handle) in handles.iter().enumerate().rev() {
+        assert_eq!(
+            Some(format!("token {}", i).into_bytes()),
+            arena.expose(handle, |token| token.to_vec())
+        );
+    }
+}
+
+#[test]
+fn test_arena_remove() {
+    let mut arena = ShieldedArena::new();
+    let first = arena.insert(&mut b"first".to_owned()).unwrap();
+    let second = arena.insert(&mut b"second".to_owned()).unwrap();
+    assert!(arena.remove(first));
+    assert!(!arena.remove(first));
+    assert!(!arena.contains(first));
+    assert_eq!(None, arena.expose(first, |secret| secret.len()));
+    assert_eq!(1, arena.len());
+
+    // The slot is reused, but not the handle.
+    let third = arena.insert(&mut b"third".to_owned()).unwrap();
+    assert_ne!(first, third);
+    assert_eq!(None, arena.expose(first, |secret| secret.len()));
+    assert_eq!(Some(b"third".to_vec()), arena.expose(third, <[u8]>::to_vec));
+    assert_eq!(
+        Some(b"second".to_vec()),
+        arena.expose(second, <[u8]>::to_vec)
+    );
+
+    // Handles of another arena don't refer to anything here.
+    let other = ShieldedArena::new()
+        .insert(&mut b"other".to_owned())
+        .unwrap();
+    let mut arena = ShieldedArena::new();
+    assert!(!arena.contains(other));
+    assert_eq!(None, arena.expose(other, |secret| secret.len()));
+}
+
+fn round_trip(cipher: Cipher) {
+    let mut arena = Shielded::builder()
+        .cipher(cipher)
+        .prekey_len(1024)
+        .context(b"arena")
+        .build_arena()
+        .unwrap();
+    let handle = arena.insert(&mut [7; ShieldedArena::MAX_LEN]).unwrap();
+    for _ in 0..3 {
+        assert_eq!(
+            Some(vec![7; ShieldedArena::MAX_LEN]),
+            arena.expose(handle, <[u8]>::to_vec)
+        );
+    }
+}
+
+#[test]
+fn test_arena_ciphers() {
+    for cipher in [Cipher::ChaCha20Poly1305, Cipher::Aes256Gcm, Cipher::Cascade] {
+        round_trip(cipher);
+    }
+}
+
+#[cfg(feature = "rustcrypto")]
+#[test]
+fn test_arena_xchacha20poly1305() {
+    round_trip(Cipher::XChaCha20Poly1305);
+}
+
+#[test]
+fn test_arena_panic() {
+    let mut arena = ShieldedArena::new();
+    let handle = arena.insert(&mut b"secret".to_owned()).unwrap();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        arena.expose(handle, |_| panic!("in expose"));
+    }));
+    assert!(result.is_err());
+    assert_eq!(
+        Some(b"secret".to_vec()),
+        arena.expose(handle, <[u8]>::to_vec)
+    );
+}
+
+#[test]
+#[should_panic(expected = "an arena entry holds at most 64 bytes")]
+fn test_arena_too_long() {
+    let _ = ShieldedArena::new().insert(&mut [0; ShieldedArena::MAX_LEN + 1]);
+}
+
+#[test]
+fn test_arena_entropy_failure() {
+    let fail = Arc::new(AtomicBool::new(true));
+    let switch = || Switch(fail.clone(), AtomicU8::new(0));
+    assert_eq!(
+        ShieldError::Rng,
+        Shielded::builder()
+            .entropy(switch())
+            .build_arena()
+            .err()
+            .unwrap()
+    );
+
+    fail.store(false, Ordering::SeqCst);
+    let mut arena = Shielded::builder().entropy(switch()).build_arena().unwrap();
+    let handle = arena.insert(&mut b"secret".to_owned()).unwrap();
+
+    fail.store(true, Ordering::SeqCst);
+    let mut secret = *b"another";
+    assert_eq!(ShieldError::Rng, arena.insert(&mut secret).err().unwrap());
+    assert_eq!([0; 7], secret);
+    assert_eq!(1, arena.len());
+
+    // The entry can't be shielded again after the exposure, so it is wiped.
+    assert_eq!(Some(6), arena.expose(handle, |secret| secret.len()));
+    assert_eq!(
+        ShieldError::Tamper,
+        arena.try_expose(handle, |_| ()).err().unwrap()
+    );
+}
+
+#[test]
+fn test_arena_debug() {
+    let mut arena = ShieldedArena::new();
+    let _ = arena.insert(&mut b"hunter2".to_owned()).unwrap();
+    let debug = format!("{:?}", arena);
+    assert!(debug.starts_with("ShieldedArena"));
+    assert!(!debug.contains("hunter2"));
+}
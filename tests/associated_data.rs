@@ -0,0 +1,115 @@
+use std::io::{Read, Write};
+use std::ptr;
+
+use shielded::backend::{SoftwareEnclave, WrappedPrekey, WrappingKey, WRAPPING_KEY_LEN};
+use shielded::entropy::EntropySource;
+use shielded::{Cipher, ShieldError, Shielded, ShieldedBuilder};
+
+// Always the same bytes, so memories built alike share prekey and nonce.
+#[derive(Debug)]
+struct Fixed;
+
+impl EntropySource for Fixed {
+    fn fill(&self, buf: &mut [u8]) -> Result<(), ShieldError> {
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = (i as u8) ^ 0x5a;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct FixedKey;
+
+impl WrappingKey for FixedKey {
+    fn fetch(&self, key: &mut [u8; WRAPPING_KEY_LEN]) -> Result<(), ShieldError> {
+        key.copy_from_slice(&[7; WRAPPING_KEY_LEN]);
+        Ok(())
+    }
+}
+
+fn builder(aad: &[u8]) -> ShieldedBuilder {
+    Shielded::builder().entropy(Fixed).associated_data(aad)
+}
+
+// Copy the ciphertext and tag of `from` over those of `to`, as memory
+// corruption could. With the fixed entropy, both have the same prekey.
+fn transplant(from: &mut Shielded, to: &mut Shielded) {
+    let len = from.len() + 16;
+    let src = from.expose(|content| content.as_ptr());
+    let dst = to.expose_mut(|content| content.as_mut_ptr());
+    unsafe {
+        for i in 0..len {
+            ptr::write_volatile(dst.add(i), ptr::read_volatile(src.add(i)));
+        }
+    }
+}
+
+#[test]
+fn test_associated_data() {
+    for cipher in [Cipher::ChaCha20Poly1305, Cipher::Aes256Gcm, Cipher::Cascade] {
+        let mut shielded = Shielded::builder()
+            .cipher(cipher)
+            .chunk_size(4)
+            .associated_data(b"db password, uid 1000")
+            .build(b"hello world".to_vec())
+            .expect("build");
+        for _ in 0..3 {
+            assert_eq!(b"hello world", shielded.unshield().as_ref());
+        }
+        assert_eq!(Ok(()), shielded.verify());
+        assert!(shielded.ct_eq(b"hello world"));
+    }
+}
+
+#[test]
+fn test_associated_data_transplant() {
+    // Same associated data, the transplanted ciphertext decrypts.
+    let mut alice = builder(b"alice")
+        .build(b"alice's key".to_vec())
+        .expect("build");
+    let mut other = builder(b"alice")
+        .build(b"other's key".to_vec())
+        .expect("build");
+    transplant(&mut alice, &mut other);
+    assert_eq!(b"alice's key", other.unshield().as_ref());
+
+    // Different associated data, it doesn't.
+    let mut alice = builder(b"alice")
+        .build(b"alice's key".to_vec())
+        .expect("build");
+    let mut bob = builder(b"bob")
+        .build(b"bob's key!!".to_vec())
+        .expect("build");
+    transplant(&mut alice, &mut bob);
+    assert_eq!(ShieldError::Tamper, bob.try_unshield().err().unwrap());
+}
+
+#[test]
+fn test_associated_data_writer_and_reader() {
+    let mut writer = Shielded::builder()
+        .chunk_size(3)
+        .associated_data(b"epoch 7")
+        .writer()
+        .expect("writer");
+    writer.write_all(b"hello world").expect("write");
+    let mut shielded = writer.finish().expect("finish");
+
+    let mut content = Vec::new();
+    let _ = shielded.reader().read_to_end(&mut content).expect("read");
+    assert_eq!(b"hello world".to_vec(), content);
+}
+
+#[test]
+fn test_associated_data_backend_encrypts() {
+    let mut shielded = Shielded::builder()
+        .backend(SoftwareEnclave::new(WrappedPrekey::new(FixedKey)))
+        .associated_data(b"epoch 7")
+        .build(b"hello world".to_vec())
+        .expect("build");
+    for _ in 0..3 {
+        assert_eq!(b"hello world", shielded.unshield().as_ref());
+    }
+    let mut copy = shielded.try_clone().expect("clone");
+    assert_eq!(b"hello world", copy.unshield().as_ref());
+}
@@ -0,0 +1,161 @@
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use shielded::audit::{Audit, AuditEvent};
+use shielded::{ShieldError, Shielded};
+
+// Records the events with their labels.
+#[derive(Debug, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<(&'static str, String)>>>,
+}
+
+impl Recorder {
+    fn record(&self, kind: &'static str, event: &AuditEvent<'_>) {
+        assert!(event.time() <= SystemTime::now());
+        let label = event.label().to_string();
+        self.events.lock().unwrap().push((kind, label));
+    }
+}
+
+impl Audit for Recorder {
+    fn on_shield(&self, event: &AuditEvent<'_>) {
+        self.record("shield", event);
+    }
+
+    fn on_unshield(&self, event: &AuditEvent<'_>) {
+        self.record("unshield", event);
+    }
+
+    fn on_wipe(&self, event: &AuditEvent<'_>) {
+        self.record("wipe", event);
+    }
+
+    fn on_violation(&self, event: &AuditEvent<'_>) {
+        self.record("violation", event);
+    }
+
+    fn on_poison(&self, event: &AuditEvent<'_>) {
+        self.record("poison", event);
+    }
+}
+
+fn kinds(events: &Mutex<Vec<(&'static str, String)>>) -> Vec<&'static str> {
+    events
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(kind, _)| *kind)
+        .collect()
+}
+
+#[test]
+fn test_audit_events() {
+    let recorder = Recorder::default();
+    let events = recorder.events.clone();
+
+    let mut shielded = Shielded::builder()
+        .audit(recorder)
+        .label("db password")
+        .build(b"hunter2".to_vec())
+        .expect("build");
+    assert_eq!(vec!["shield"], kinds(&events));
+
+    assert_eq!(b"hunter2", shielded.unshield().as_ref());
+    assert!(shielded.ct_eq(b"hunter2"));
+    shielded.verify().expect("verify");
+    assert_eq!(
+        vec!["shield", "unshield", "shield", "unshield"],
+        kinds(&events)
+    );
+
+    drop(shielded);
+    assert_eq!(
+        vec!["shield", "unshield", "shield", "unshield", "wipe"],
+        kinds(&events)
+    );
+    assert!(events
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|(_, label)| label == "db password"));
+}
+
+#[test]
+fn test_audit_expiry() {
+    let recorder = Recorder::default();
+    let events = recorder.events.clone();
+
+    let mut shielded = Shielded::builder()
+        .audit(recorder)
+        .max_uses(1)
+        .build(b"one-time".to_vec())
+        .expect("build");
+    shielded.expose(|_| ());
+    assert_eq!(
+        Err(ShieldError::Expired),
+        shielded.try_expose(|buf| buf.to_vec())
+    );
+    drop(shielded);
+
+    assert_eq!(vec!["shield", "unshield", "wipe"], kinds(&events));
+    assert_eq!("", events.lock().unwrap()[0].1);
+}
+
+#[test]
+fn test_audit_into_inner() {
+    let recorder = Recorder::default();
+    let events = recorder.events.clone();
+
+    let shielded = Shielded::builder()
+        .audit(recorder)
+        .build(b"hunter2".to_vec())
+        .expect("build");
+    assert_eq!(b"hunter2", &shielded.into_inner()[..]);
+    assert_eq!(vec!["shield", "unshield", "wipe"], kinds(&events));
+}
+
+#[test]
+fn test_audit_violation() {
+    let recorder = Recorder::default();
+    let events = recorder.events.clone();
+
+    let mut shielded = Shielded::builder()
+        .audit(recorder)
+        .build(b"hunter2".to_vec())
+        .expect("build");
+    {
+        let unshielded = shielded.unshield_for(Duration::from_millis(1));
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(b"hunter2", unshielded.as_ref());
+    }
+    assert_eq!(
+        vec!["shield", "unshield", "violation", "shield"],
+        kinds(&events)
+    );
+}
+
+#[test]
+fn test_audit_poison() {
+    let recorder = Recorder::default();
+    let events = recorder.events.clone();
+
+    let mut shielded = Shielded::builder()
+        .audit(recorder)
+        .build(b"hunter2".to_vec())
+        .expect("build");
+    let memory = shielded.expose(|content| content.as_ptr() as *mut u8);
+    unsafe { ptr::write_volatile(memory, !ptr::read_volatile(memory)) };
+    assert_eq!(Err(ShieldError::Tamper), shielded.verify());
+    assert_eq!(
+        ShieldError::Poisoned,
+        shielded.try_unshield().err().unwrap()
+    );
+    drop(shielded);
+    assert_eq!(
+        vec!["shield", "unshield", "shield", "poison"],
+        kinds(&events)
+    );
+}
@@ -0,0 +1,276 @@
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Arc;
+
+use shielded::backend::{
+    Aead, Backend, SoftwareEnclave, WrappedPrekey, WrappingKey, WRAPPING_KEY_LEN,
+};
+use shielded::{Padding, ReshieldPolicy, ShieldError, Shielded};
+
+// Flips every bit of the prekey at rest.
+#[derive(Debug)]
+struct Invert;
+
+impl Backend for Invert {
+    fn protect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        prekey.iter_mut().for_each(|b| *b = !*b);
+        Ok(())
+    }
+
+    fn unprotect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        self.protect(prekey)
+    }
+}
+
+// Restores something else than what it was given.
+#[derive(Debug)]
+struct Forgetful;
+
+impl Backend for Forgetful {
+    fn protect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        prekey.iter_mut().for_each(|b| *b = 0);
+        Ok(())
+    }
+
+    fn unprotect(&self, _prekey: &mut [u8]) -> Result<(), ShieldError> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_backend_round_trip() {
+    let buf = b"hello world".to_vec();
+
+    let original = buf.clone();
+    let mut shielded = Shielded::builder()
+        .backend(Invert)
+        .build(buf)
+        .expect("build");
+
+    {
+        let unshielded = shielded.unshield();
+        assert_eq!(original, unshielded.as_ref());
+    }
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[test]
+fn test_backend_not_restoring_prekey() {
+    let mut shielded = Shielded::builder()
+        .backend(Forgetful)
+        .build(b"hello world".to_vec())
+        .expect("build");
+
+    assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+}
+
+#[test]
+fn test_backend_verify() {
+    let mut shielded = Shielded::builder()
+        .backend(Invert)
+        .chunk_size(4)
+        .build(b"hello world".to_vec())
+        .expect("build");
+
+    shielded.verify().expect("verify");
+    shielded.verify().expect("verify again");
+    assert_eq!(b"hello world", shielded.unshield().as_ref());
+
+    let mut shielded = Shielded::builder()
+        .backend(Forgetful)
+        .build(b"hello world".to_vec())
+        .expect("build");
+
+    assert_eq!(Err(ShieldError::Tamper), shielded.verify());
+    assert_eq!(
+        Err(ShieldError::Poisoned),
+        shielded.try_ct_eq(b"hello world")
+    );
+}
+
+// Fetches a wrapping key which changes after `changes_after` calls, or fails.
+#[derive(Debug)]
+struct FixedKey {
+    calls: AtomicU8,
+    changes_after: u8,
+    fails: bool,
+}
+
+impl FixedKey {
+    fn new(changes_after: u8, fails: bool) -> Self {
+        FixedKey {
+            calls: AtomicU8::new(0),
+            changes_after,
+            fails,
+        }
+    }
+}
+
+impl WrappingKey for FixedKey {
+    fn fetch(&self, key: &mut [u8; WRAPPING_KEY_LEN]) -> Result<(), ShieldError> {
+        if self.fails {
+            return Err(ShieldError::Backend);
+        }
+        let calls = self.calls.fetch_add(1, Ordering::SeqCst);
+        *key = [u8::from(calls >= self.changes_after); WRAPPING_KEY_LEN];
+        Ok(())
+    }
+}
+
+#[test]
+fn test_wrapped_prekey_backend() {
+    let mut shielded = Shielded::builder()
+        .backend(WrappedPrekey::new(FixedKey::new(u8::MAX, false)))
+        .build(b"hello world".to_vec())
+        .expect("build");
+    for _ in 0..3 {
+        assert_eq!(b"hello world", shielded.unshield().as_ref());
+    }
+}
+
+#[test]
+fn test_wrapped_prekey_backend_salted() {
+    let backend = WrappedPrekey::new(FixedKey::new(u8::MAX, false));
+    let salt_len = backend.extra_len();
+    assert!(salt_len > 0);
+
+    let prekey = [42; 1024];
+    let mut first = vec![0; prekey.len() + salt_len];
+    first[..prekey.len()].copy_from_slice(&prekey);
+    let mut second = first.clone();
+    backend.protect(&mut first).unwrap();
+    backend.protect(&mut second).unwrap();
+    assert_ne!(&prekey[..], &first[..prekey.len()]);
+    assert_ne!(first, second);
+
+    backend.unprotect(&mut first).unwrap();
+    backend.unprotect(&mut second).unwrap();
+    assert_eq!(&prekey[..], &first[..prekey.len()]);
+    assert_eq!(&prekey[..], &second[..prekey.len()]);
+}
+
+#[test]
+fn test_wrapped_prekey_backend_wrong_key() {
+    let mut shielded = Shielded::builder()
+        .backend(WrappedPrekey::new(FixedKey::new(1, false)))
+        .build(b"hello world".to_vec())
+        .expect("build");
+    assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+}
+
+#[test]
+fn test_wrapped_prekey_backend_fetch_fails() {
+    let result = Shielded::builder()
+        .backend(WrappedPrekey::new(FixedKey::new(0, true)))
+        .build(b"hello world".to_vec());
+    assert_eq!(ShieldError::Backend, result.err().unwrap());
+}
+
+// Encrypts itself like an enclave, and records if the prekey is ever
+// restored.
+#[derive(Debug, Default)]
+struct Enclave {
+    restored: Arc<AtomicBool>,
+}
+
+impl Backend for Enclave {
+    fn protect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        Invert.protect(prekey)
+    }
+
+    fn unprotect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        self.restored.store(true, Ordering::SeqCst);
+        Invert.unprotect(prekey)
+    }
+
+    fn encrypts(&self) -> bool {
+        true
+    }
+
+    fn seal(
+        &self,
+        prekey: &[u8],
+        aead: &Aead<'_>,
+        payload: &mut [u8],
+        tag: &mut [u8],
+    ) -> Result<(), ShieldError> {
+        let prekey: Vec<u8> = prekey.iter().map(|b| !b).collect();
+        aead.seal(&prekey, payload, tag)
+    }
+
+    fn open(
+        &self,
+        prekey: &[u8],
+        aead: &Aead<'_>,
+        in_out: &mut [u8],
+    ) -> Result<usize, ShieldError> {
+        let prekey: Vec<u8> = prekey.iter().map(|b| !b).collect();
+        aead.open(&prekey, in_out)
+    }
+}
+
+#[test]
+fn test_encrypting_backend() {
+    let restored = Arc::new(AtomicBool::new(false));
+    let enclave = || Enclave {
+        restored: restored.clone(),
+    };
+
+    let content: Vec<u8> = (0..100).collect();
+    let mut shielded = Shielded::builder()
+        .backend(enclave())
+        .chunk_size(7)
+        .padding(Padding::Block(64))
+        .reshield_policy(ReshieldPolicy::Every(2))
+        .context(b"enclave")
+        .build(content.clone())
+        .expect("build");
+
+    for _ in 0..3 {
+        assert_eq!(content, shielded.unshield().as_ref());
+    }
+    shielded.unshield_mut().truncate(50);
+    assert_eq!(&content[10..20], &*shielded.unshield_range(10..20));
+    assert!(shielded.ct_eq(&content[..50]));
+    shielded.verify().expect("verify");
+    let mut read = Vec::new();
+    let _ = shielded.reader().read_to_end(&mut read).expect("read");
+    assert_eq!(&content[..50], &read[..]);
+
+    let mut writer = Shielded::builder()
+        .backend(enclave())
+        .chunk_size(16)
+        .writer()
+        .expect("writer");
+    writer.write_all(&content).expect("write");
+    let mut written = writer.finish().expect("finish");
+    assert_eq!(content, written.unshield().as_ref());
+
+    assert!(!restored.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_software_enclave_backend() {
+    let mut shielded = Shielded::builder()
+        .backend(SoftwareEnclave::new(WrappedPrekey::new(FixedKey::new(
+            u8::MAX,
+            false,
+        ))))
+        .chunk_size(3)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    for _ in 0..3 {
+        assert_eq!(b"hello world", shielded.unshield().as_ref());
+    }
+    assert_eq!(b"world", &*shielded.unshield_range(6..11));
+
+    let mut shielded = Shielded::builder()
+        .backend(SoftwareEnclave::new(WrappedPrekey::new(FixedKey::new(
+            2, false,
+        ))))
+        .build(b"hello world".to_vec())
+        .expect("build");
+    assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+}
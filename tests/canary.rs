@@ -0,0 +1,60 @@
+use std::ptr;
+
+use shielded::{ShieldError, Shielded};
+
+// Length of the encryption tag after the content.
+const TAG_LEN: usize = 16;
+
+// Flip a bit in the slack after the shielded memory, past the tag.
+fn flip_slack(shielded: &mut Shielded, offset: usize) {
+    let len = shielded.len();
+    let memory = shielded.expose(|content| content.as_ptr() as *mut u8);
+    unsafe {
+        let byte = memory.add(len + TAG_LEN + offset);
+        ptr::write_volatile(byte, ptr::read_volatile(byte) ^ 1);
+    }
+}
+
+#[test]
+fn test_canaries_intact() {
+    let mut shielded = Shielded::new(b"secret".to_vec());
+    assert_eq!(Ok(()), shielded.check_canaries());
+    assert_eq!(b"secret", shielded.unshield().as_ref());
+    assert_eq!(Ok(()), shielded.check_canaries());
+    assert_eq!(Ok(()), shielded.verify());
+}
+
+#[test]
+fn test_canary_bit_flip() {
+    let mut shielded = Shielded::new(b"secret".to_vec());
+    flip_slack(&mut shielded, 0);
+    assert_eq!(Err(ShieldError::Tamper), shielded.check_canaries());
+    assert_eq!(Err(ShieldError::Tamper), shielded.verify());
+    assert_eq!(
+        ShieldError::Poisoned,
+        shielded.try_unshield().err().unwrap()
+    );
+}
+
+#[test]
+fn test_canary_far_in_slack() {
+    let mut shielded = Shielded::new(b"secret".to_vec());
+    flip_slack(&mut shielded, 1000);
+    assert_eq!(Err(ShieldError::Tamper), shielded.check_canaries());
+}
+
+#[test]
+fn test_canaries_after_resize() {
+    let mut shielded = Shielded::new(b"secret".to_vec());
+    {
+        let mut unshielded = shielded.unshield_mut();
+        unshielded.truncate(2);
+    }
+    assert_eq!(Ok(()), shielded.check_canaries());
+    {
+        let mut unshielded = shielded.unshield_mut();
+        unshielded.extend_from_slice(b"cond").unwrap();
+    }
+    assert_eq!(Ok(()), shielded.check_canaries());
+    assert_eq!(b"second", shielded.unshield().as_ref());
+}
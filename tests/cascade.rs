@@ -0,0 +1,156 @@
+use std::io::Read;
+use std::ptr;
+
+use quickcheck::quickcheck;
+use shielded::{Cipher, ReshieldPolicy, ShieldError, Shielded};
+
+const KEK: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+// Flips every bit of the prekey at rest.
+#[derive(Debug)]
+struct Invert;
+
+impl shielded::backend::Backend for Invert {
+    fn protect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        prekey.iter_mut().for_each(|b| *b = !*b);
+        Ok(())
+    }
+
+    fn unprotect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        self.protect(prekey)
+    }
+}
+
+// Flip a bit of the shielded memory at `offset`.
+fn flip(shielded: &mut Shielded, offset: usize) {
+    let memory = shielded.expose(|content| content.as_ptr() as *mut u8);
+    unsafe {
+        let byte = memory.add(offset);
+        ptr::write_volatile(byte, ptr::read_volatile(byte) ^ 1);
+    }
+}
+
+#[test]
+fn test_cascade() {
+    let mut shielded = Shielded::with_cipher(Cipher::Cascade, b"hello world".to_vec());
+    for _ in 0..3 {
+        assert_eq!(b"hello world", shielded.unshield().as_ref());
+    }
+    assert_eq!(Ok(()), shielded.verify());
+    assert_eq!(Ok(()), shielded.check_canaries());
+}
+
+#[test]
+fn test_cascade_empty() {
+    let mut shielded = Shielded::with_cipher(Cipher::Cascade, Vec::new());
+    assert!(shielded.unshield().as_ref().is_empty());
+}
+
+#[test]
+fn test_cascade_tamper() {
+    // The ciphertext, the outer tag and the inner tag.
+    for offset in [0, 11, 11 + 16] {
+        let mut shielded = Shielded::with_cipher(Cipher::Cascade, b"hello world".to_vec());
+        flip(&mut shielded, offset);
+        assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+    }
+}
+
+#[test]
+fn test_cascade_try_clone() {
+    let mut shielded = Shielded::with_cipher(Cipher::Cascade, b"hello world".to_vec());
+    let mut copy = shielded.try_clone().expect("clone");
+    assert_eq!(b"hello world", copy.unshield().as_ref());
+    assert_eq!(Ok(()), shielded.verify());
+}
+
+#[test]
+fn test_cascade_chunked() {
+    let content: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+    let mut shielded = Shielded::builder()
+        .cipher(Cipher::Cascade)
+        .chunk_size(1000)
+        .build(content.clone())
+        .expect("build");
+    assert_eq!(&content[2500..2600], &*shielded.unshield_range(2500..2600));
+
+    let mut read = Vec::new();
+    let _ = shielded.reader().read_to_end(&mut read).unwrap();
+    assert_eq!(content, read);
+}
+
+#[test]
+fn test_cascade_resize() {
+    let mut shielded = Shielded::builder()
+        .cipher(Cipher::Cascade)
+        .chunk_size(4)
+        .build(b"secret".to_vec())
+        .expect("build");
+    {
+        let mut unshielded = shielded.unshield_mut();
+        unshielded.truncate(2);
+        unshielded.extend_from_slice(b"cond, longer").unwrap();
+    }
+    assert_eq!(b"second, longer", shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_cascade_writer() {
+    let mut writer = Shielded::builder()
+        .cipher(Cipher::Cascade)
+        .chunk_size(4)
+        .writer()
+        .unwrap();
+    std::io::Write::write_all(&mut writer, b"hello world").unwrap();
+    let mut shielded = writer.finish().unwrap();
+    assert_eq!(b"hello world", shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_cascade_scattered_with_backend() {
+    let mut shielded = Shielded::builder()
+        .cipher(Cipher::Cascade)
+        .prekey_len(1024)
+        .scatter_prekey(7)
+        .backend(Invert)
+        .reshield_policy(ReshieldPolicy::Every(2))
+        .build(b"hello world".to_vec())
+        .expect("build");
+    for _ in 0..4 {
+        assert_eq!(b"hello world", shielded.unshield().as_ref());
+    }
+}
+
+#[test]
+fn test_cascade_export_import() {
+    let mut shielded = Shielded::with_cipher(Cipher::Cascade, b"hello world".to_vec());
+    let blob = shielded.export(KEK);
+    assert_eq!(4 + 2 + 32 + 12 + 11 + 32, blob.len());
+
+    // Into memory with shorter tags, and with both.
+    let mut imported = Shielded::import(&blob, KEK).expect("import");
+    assert_eq!(b"hello world", imported.unshield().as_ref());
+    let mut imported = Shielded::builder()
+        .cipher(Cipher::Cascade)
+        .import(&blob, KEK)
+        .expect("import");
+    assert_eq!(b"hello world", imported.unshield().as_ref());
+
+    for i in 0..blob.len() {
+        let mut modified = blob.clone();
+        modified[i] ^= 1;
+        assert!(Shielded::import(&modified, KEK).is_err());
+    }
+}
+
+quickcheck! {
+    fn prop_cascade(xs: Vec<u8>, chunk_size: u8) -> bool {
+        let mut shielded = Shielded::builder()
+            .cipher(Cipher::Cascade)
+            .chunk_size(usize::from(chunk_size) + 1)
+            .build(xs.clone())
+            .expect("build");
+        let unshielded = shielded.unshield();
+        xs == unshielded.as_ref()
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use std::thread;
+
+use shielded::{Shielded, ShieldedCell};
+
+#[test]
+fn test_cell_concurrent_expose_mut() {
+    let cell = Arc::new(ShieldedCell::new(Shielded::new(vec![0u8; 8])));
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let cell = cell.clone();
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    cell.expose_mut(|secret| {
+                        let n = u64::from_le_bytes([
+                            secret[0], secret[1], secret[2], secret[3], secret[4], secret[5],
+                            secret[6], secret[7],
+                        ]);
+                        secret.copy_from_slice(&(n + 1).to_le_bytes());
+                    });
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let mut shielded = Arc::try_unwrap(cell).ok().unwrap().into_inner();
+    let unshielded = shielded.unshield();
+    assert_eq!(&80u64.to_le_bytes(), unshielded.as_ref());
+}
+
+#[test]
+fn test_cell_recovers_from_panic() {
+    let cell = ShieldedCell::from(Shielded::new(b"hello".to_vec()));
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        cell.expose(|_| panic!("oops"));
+    }));
+    assert!(result.is_err());
+
+    assert_eq!(5, cell.expose(|secret| secret.len()));
+    assert_eq!(b"hello".to_vec(), cell.expose(|secret| secret.to_vec()));
+}
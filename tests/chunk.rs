@@ -0,0 +1,159 @@
+use quickcheck::quickcheck;
+use shielded::Shielded;
+
+fn chunked(buf: Vec<u8>, chunk_size: usize) -> Shielded {
+    Shielded::builder()
+        .chunk_size(chunk_size)
+        .build(buf)
+        .expect("build")
+}
+
+#[test]
+fn test_chunked_unshield() {
+    let buf: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+
+    let original = buf.clone();
+    let mut shielded = chunked(buf, 1000);
+
+    {
+        let unshielded = shielded.unshield();
+        assert_eq!(original, unshielded.as_ref());
+    }
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[test]
+fn test_chunked_unshield_many_chunks() {
+    let buf: Vec<u8> = (0..4 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+
+    let original = buf.clone();
+    let mut shielded = chunked(buf, 16 * 1024);
+
+    {
+        let unshielded = shielded.unshield();
+        assert_eq!(original, unshielded.as_ref());
+    }
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[test]
+fn test_chunked_unshield_mut_grow_and_truncate() {
+    let mut shielded = chunked(b"hello".to_vec(), 4);
+
+    shielded
+        .unshield_mut()
+        .extend_from_slice(b" world")
+        .expect("extend");
+    assert_eq!(b"hello world", shielded.unshield().as_ref());
+
+    shielded.unshield_mut().truncate(4);
+    assert_eq!(b"hell", shielded.unshield().as_ref());
+
+    shielded.unshield_mut().truncate(0);
+    assert_eq!(b"", shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_unshield_range() {
+    let buf: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+
+    let original = buf.clone();
+    let mut shielded = chunked(buf, 1000);
+
+    assert_eq!(&original[1500..2500], &*shielded.unshield_range(1500..2500));
+    assert_eq!(&original[..], &*shielded.unshield_range(0..10_000));
+    assert_eq!(&original[9999..], &*shielded.unshield_range(9999..10_000));
+    assert!(shielded.unshield_range(42..42).is_empty());
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[test]
+fn test_unshield_range_unchunked() {
+    let mut shielded = Shielded::new(b"hello world".to_vec());
+    assert_eq!(b"world", &*shielded.unshield_range(6..11));
+}
+
+#[test]
+#[should_panic]
+fn test_unshield_range_out_of_bounds() {
+    let mut shielded = chunked(b"hello".to_vec(), 2);
+    let _ = shielded.unshield_range(3..6);
+}
+
+#[test]
+fn test_view() {
+    let buf: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+
+    let original = buf.clone();
+    let mut shielded = chunked(buf, 1000);
+
+    let mut view = shielded.view(1500..1600);
+    assert_eq!(1500..1600, view.window());
+    assert_eq!(&original[1500..1600], &*view);
+    // Within the same chunks, then across others.
+    view.set_window(1000..2000);
+    assert_eq!(&original[1000..2000], &*view);
+    view.set_window(8999..9001);
+    assert_eq!(&original[8999..9001], &*view);
+    view.set_window(0..10_000);
+    assert_eq!(&original[..], &*view);
+    view.set_window(42..42);
+    assert!(view.is_empty());
+    drop(view);
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[test]
+#[should_panic(expected = "range out of bounds")]
+fn test_view_out_of_bounds() {
+    let mut shielded = chunked(b"hello".to_vec(), 2);
+    let mut view = shielded.view(0..2);
+    view.set_window(3..6);
+}
+
+quickcheck! {
+    fn prop_chunked_shield_unshield(xs: Vec<u8>, chunk_size: u8) -> bool {
+        let original = xs.clone();
+        let mut shielded = chunked(xs, chunk_size as usize + 1);
+
+        {
+            let unshielded = shielded.unshield();
+            assert_eq!(original, unshielded.as_ref());
+        }
+
+        let unshielded = shielded.unshield();
+        original == unshielded.as_ref()
+    }
+
+    fn prop_unshield_range(xs: Vec<u8>, chunk_size: u8, a: usize, b: usize) -> bool {
+        let original = xs.clone();
+        let mut shielded = chunked(xs, chunk_size as usize + 1);
+
+        let len = original.len() + 1;
+        let (start, end) = (a % len, b % len);
+        let range = start.min(end)..start.max(end);
+        original[range.clone()] == *shielded.unshield_range(range)
+    }
+
+    fn prop_view(xs: Vec<u8>, chunk_size: u8, windows: Vec<(usize, usize)>) -> bool {
+        let original = xs.clone();
+        let mut shielded = chunked(xs, chunk_size as usize + 1);
+
+        let len = original.len() + 1;
+        let mut view = shielded.view(0..0);
+        windows.into_iter().all(|(a, b)| {
+            let (start, end) = (a % len, b % len);
+            let range = start.min(end)..start.max(end);
+            view.set_window(range.clone());
+            original[range] == *view
+        })
+    }
+}
@@ -0,0 +1,33 @@
+use shielded::{LockMode, Shielded};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn test_derive_subkey() {
+    let mut master = Shielded::new(b"master key".to_vec());
+
+    let mut subkey = master.derive_subkey(b"service", 42);
+    assert_eq!(
+        "e82336f70f22b8bcb359720a08caf89c0aa5ba759ee313c2088d161fd779fd53a5109165f83af934af55",
+        hex(subkey.unshield().as_ref())
+    );
+
+    let mut other = Shielded::builder()
+        .lock(LockMode::BestEffort)
+        .chunk_size(8)
+        .derive_subkey(&mut master, b"other service", 42)
+        .expect("derive");
+    assert_ne!(subkey.unshield().as_ref(), other.unshield().as_ref());
+
+    // The master is still intact.
+    assert_eq!(b"master key", master.unshield().as_ref());
+}
+
+#[test]
+fn test_derive_subkey_too_long() {
+    let mut master = Shielded::new(b"master key".to_vec());
+    assert!(master.try_derive_subkey(b"service", 255 * 64).is_ok());
+    assert!(master.try_derive_subkey(b"service", 255 * 64 + 1).is_err());
+}
@@ -0,0 +1,131 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use shielded::entropy::EntropySource;
+use shielded::{Cipher, ReshieldPolicy, ShieldError, Shielded};
+
+// Deterministic, and counting how often it has been asked for randomness.
+#[derive(Debug, Default)]
+struct Counter {
+    calls: Arc<AtomicUsize>,
+}
+
+impl EntropySource for Counter {
+    fn fill(&self, buf: &mut [u8]) -> Result<(), ShieldError> {
+        let calls = self.calls.fetch_add(1, Ordering::SeqCst);
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = (i + calls) as u8;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Failing;
+
+impl EntropySource for Failing {
+    fn fill(&self, _buf: &mut [u8]) -> Result<(), ShieldError> {
+        Err(ShieldError::Rng)
+    }
+}
+
+#[test]
+fn test_entropy_source() {
+    let buf = b"hello world".to_vec();
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let original = buf.clone();
+    let mut shielded = Shielded::builder()
+        .entropy(Counter {
+            calls: calls.clone(),
+        })
+        .build(buf)
+        .expect("build");
+    // The canary, one prekey and one nonce.
+    assert_eq!(3, calls.load(Ordering::SeqCst));
+
+    {
+        let unshielded = shielded.unshield();
+        assert_eq!(original, unshielded.as_ref());
+    }
+    assert_eq!(5, calls.load(Ordering::SeqCst));
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[test]
+fn test_entropy_source_failure() {
+    let result = Shielded::builder()
+        .entropy(Failing)
+        .build(b"hello world".to_vec());
+    assert_eq!(ShieldError::Rng, result.err().unwrap());
+}
+
+#[test]
+fn test_reshield_policy_every() {
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let mut shielded = Shielded::builder()
+        .entropy(Counter {
+            calls: calls.clone(),
+        })
+        .reshield_policy(ReshieldPolicy::Every(3))
+        .build(b"hello world".to_vec())
+        .expect("build");
+    assert_eq!(3, calls.load(Ordering::SeqCst));
+
+    // The first two exposures only take a new nonce, the third a new prekey
+    // and nonce.
+    for expected in &[4, 5, 7, 8, 9, 11] {
+        assert_eq!(b"hello world", shielded.unshield().as_ref());
+        assert_eq!(*expected, calls.load(Ordering::SeqCst));
+    }
+}
+
+#[test]
+fn test_nonce_sequence() {
+    for cipher in [Cipher::ChaCha20Poly1305, Cipher::Aes256Gcm, Cipher::Cascade] {
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let mut shielded = Shielded::builder()
+            .entropy(Counter {
+                calls: calls.clone(),
+            })
+            .cipher(cipher)
+            .chunk_size(4)
+            .reshield_policy(ReshieldPolicy::Every(3))
+            .nonce_sequence(true)
+            .build(b"hello world".to_vec())
+            .expect("build");
+        let built = calls.load(Ordering::SeqCst);
+
+        // Only the third exposure takes a new prekey and nonce.
+        for expected in &[0, 0, 2, 2, 2, 4] {
+            assert_eq!(b"hello world", shielded.unshield().as_ref());
+            assert_eq!(built + expected, calls.load(Ordering::SeqCst));
+        }
+        shielded.unshield_mut().as_mut()[0] = b'j';
+        assert_eq!(b"jello world", shielded.unshield().as_ref());
+        assert_eq!(Ok(()), shielded.verify());
+    }
+}
+
+#[test]
+fn test_nonce_sequence_always() {
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let mut shielded = Shielded::builder()
+        .entropy(Counter {
+            calls: calls.clone(),
+        })
+        .nonce_sequence(true)
+        .build(b"hello world".to_vec())
+        .expect("build");
+
+    // Every exposure still takes a new prekey and nonce.
+    for expected in &[5, 7, 9] {
+        assert_eq!(b"hello world", shielded.unshield().as_ref());
+        assert_eq!(*expected, calls.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,58 @@
+use std::env;
+use std::io;
+
+use shielded::Shielded;
+
+// The only test in this binary, so no other thread reads the environment
+// while it is scrubbed.
+#[test]
+fn test_from_env() {
+    env::set_var("SHIELDED_TEST_TOKEN", "hunter2");
+    let mut token = unsafe { Shielded::from_env("SHIELDED_TEST_TOKEN") }.expect("from env");
+    assert_eq!(b"hunter2", token.unshield().as_ref());
+    assert!(env::var_os("SHIELDED_TEST_TOKEN").is_none());
+
+    let err = unsafe { Shielded::from_env("SHIELDED_TEST_TOKEN") }.unwrap_err();
+    assert_eq!(io::ErrorKind::NotFound, err.kind());
+
+    env::set_var("SHIELDED_TEST_TOKEN", "");
+    let builder = Shielded::builder().chunk_size(4);
+    let token = unsafe { builder.build_from_env("SHIELDED_TEST_TOKEN") }.expect("from env");
+    assert!(token.is_empty());
+    assert!(env::var_os("SHIELDED_TEST_TOKEN").is_none());
+
+    #[cfg(target_os = "linux")]
+    check_proc_environ();
+}
+
+// Run this test again in a child process started with the token in its
+// environment, which must be gone from `/proc/self/environ` afterwards.
+#[cfg(target_os = "linux")]
+fn check_proc_environ() {
+    use std::fs;
+    use std::process::Command;
+
+    const CHILD: &str = "SHIELDED_ENV_CHILD";
+    let environ = || fs::read("/proc/self/environ").unwrap();
+    let contains = |haystack: &[u8], needle: &[u8]| {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    };
+
+    if env::var_os(CHILD).is_some() {
+        assert!(contains(&environ(), b"SHIELDED_CHILD_TOKEN=hunter2"));
+        let mut token = unsafe { Shielded::from_env("SHIELDED_CHILD_TOKEN") }.expect("from env");
+        assert_eq!(b"hunter2", token.unshield().as_ref());
+        assert!(!contains(&environ(), b"hunter2"));
+        return;
+    }
+
+    let status = Command::new(env::current_exe().unwrap())
+        .args(["--exact", "test_from_env", "--test-threads", "1"])
+        .env(CHILD, "1")
+        .env("SHIELDED_CHILD_TOKEN", "hunter2")
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
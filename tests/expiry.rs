@@ -0,0 +1,129 @@
+use std::io::Read;
+use std::time::Duration;
+
+use shielded::{ShieldError, Shielded};
+
+#[test]
+fn test_max_uses() {
+    let mut shielded = Shielded::builder()
+        .max_uses(3)
+        .build(b"hello".to_vec())
+        .expect("build");
+
+    assert_eq!(b"hello", shielded.unshield().as_ref());
+    assert!(shielded.ct_eq(b"hello"));
+    shielded.verify().expect("verify doesn't count");
+    assert_eq!(b"ell", &*shielded.unshield_range(1..4));
+
+    assert_eq!(ShieldError::Expired, shielded.try_unshield().err().unwrap());
+    assert_eq!(Err(ShieldError::Expired), shielded.verify());
+    assert_eq!(Err(ShieldError::Expired), shielded.try_ct_eq(b"hello"));
+    let mut other = Shielded::new(b"hello".to_vec());
+    assert_eq!(
+        Err(ShieldError::Expired),
+        shielded.try_ct_eq_shielded(&mut other)
+    );
+    assert_eq!(
+        Err(ShieldError::Expired),
+        other.try_ct_eq_shielded(&mut shielded)
+    );
+    assert_eq!(
+        ShieldError::Expired,
+        shielded.try_unshield_mut().err().unwrap()
+    );
+}
+
+#[test]
+fn test_try_clone_inherits_uses() {
+    let mut shielded = Shielded::builder()
+        .max_uses(3)
+        .build(b"hello".to_vec())
+        .expect("build");
+
+    // Copying counts as a use, for the copy too.
+    let mut copy = shielded.try_clone().expect("clone");
+    assert_eq!(b"hello", copy.unshield().as_ref());
+    assert_eq!(b"hello", copy.unshield().as_ref());
+    assert_eq!(ShieldError::Expired, copy.try_unshield().err().unwrap());
+
+    assert_eq!(b"hello", shielded.unshield().as_ref());
+    let mut last = shielded.try_clone().expect("clone");
+    assert_eq!(ShieldError::Expired, shielded.try_unshield().err().unwrap());
+    assert_eq!(ShieldError::Expired, last.try_unshield().err().unwrap());
+}
+
+#[test]
+fn test_max_uses_wipes_after_last_use() {
+    let mut shielded = Shielded::builder()
+        .max_uses(1)
+        .build(b"one-time key".to_vec())
+        .expect("build");
+
+    {
+        let mut unshielded = shielded.unshield_mut();
+        unshielded.truncate(3);
+    }
+    assert_eq!(Err(ShieldError::Expired), shielded.verify());
+    assert_eq!(
+        Err(ShieldError::Expired),
+        shielded.try_expose(|buf| buf.to_vec())
+    );
+    assert_eq!(Err(ShieldError::Expired), shielded.try_into_inner());
+}
+
+#[test]
+fn test_max_uses_reader_counts_once() {
+    let mut shielded = Shielded::builder()
+        .max_uses(2)
+        .chunk_size(2)
+        .build(b"hello world".to_vec())
+        .expect("build");
+
+    let mut read = Vec::new();
+    let _ = shielded.reader().read_to_end(&mut read).expect("read");
+    assert_eq!(b"hello world", &read[..]);
+
+    assert_eq!(b"hello world", shielded.unshield().as_ref());
+    assert!(shielded.reader().read_to_end(&mut read).is_err());
+}
+
+#[test]
+fn test_max_uses_view_counts_once() {
+    let mut shielded = Shielded::builder()
+        .max_uses(2)
+        .chunk_size(2)
+        .build(b"hello world".to_vec())
+        .expect("build");
+
+    let mut view = shielded.view(0..2);
+    for i in 0..10 {
+        view.set_window(i..i + 1);
+    }
+    drop(view);
+
+    assert_eq!(b"hello world", shielded.unshield().as_ref());
+    assert_eq!(ShieldError::Expired, shielded.try_view(0..2).err().unwrap());
+}
+
+#[test]
+#[should_panic]
+fn test_max_uses_zero() {
+    let _ = Shielded::builder().max_uses(0);
+}
+
+#[test]
+fn test_time_to_live() {
+    let mut shielded = Shielded::builder()
+        .time_to_live(Duration::from_secs(3600))
+        .build(b"hello".to_vec())
+        .expect("build");
+    assert_eq!(b"hello", shielded.unshield().as_ref());
+
+    let mut shielded = Shielded::builder()
+        .time_to_live(Duration::from_millis(10))
+        .build(b"hello".to_vec())
+        .expect("build");
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(ShieldError::Expired, shielded.try_unshield().err().unwrap());
+    assert_eq!(Err(ShieldError::Expired), shielded.verify());
+}
@@ -0,0 +1,91 @@
+use quickcheck::quickcheck;
+use shielded::{Cipher, Format, Padding, ShieldError, Shielded};
+
+const KEK: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+#[test]
+fn test_export_import() {
+    let mut shielded = Shielded::builder()
+        .cipher(Cipher::Aes256Gcm)
+        .chunk_size(3)
+        .padding(Padding::Block(64))
+        .build(b"hello world".to_vec())
+        .expect("build");
+
+    let blob = shielded.export(KEK);
+    assert_eq!(4 + 2 + 32 + 12 + 11 + 16, blob.len());
+    assert_eq!(Ok(Format::LATEST), Format::of(&blob));
+    assert!(!blob.windows(5).any(|w| w == b"hello"));
+
+    let mut imported = Shielded::builder()
+        .chunk_size(4)
+        .import(&blob, KEK)
+        .expect("import");
+    assert_eq!(b"hello world", imported.unshield().as_ref());
+
+    // The exported memory stays usable.
+    assert_eq!(b"hello world", shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_export_fresh_nonce() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+    assert_ne!(shielded.export(KEK), shielded.export(KEK));
+}
+
+#[test]
+fn test_import_wrong_kek() {
+    let blob = Shielded::new(b"hello".to_vec()).export(KEK);
+    assert_eq!(
+        ShieldError::Tamper,
+        Shielded::import(&blob, b"another key").err().unwrap()
+    );
+}
+
+#[test]
+fn test_import_modified() {
+    let blob = Shielded::new(b"hello".to_vec()).export(KEK);
+    for i in 0..blob.len() {
+        let mut modified = blob.clone();
+        modified[i] ^= 1;
+        assert!(Shielded::import(&modified, KEK).is_err());
+    }
+    for len in 0..blob.len() {
+        assert!(Shielded::import(&blob[..len], KEK).is_err());
+    }
+}
+
+#[test]
+fn test_import_malformed() {
+    let mut blob = Shielded::new(b"hello".to_vec()).export(KEK);
+    blob[4] = 2;
+    assert_eq!(
+        ShieldError::Encoding,
+        Shielded::import(&blob, KEK).err().unwrap()
+    );
+    assert_eq!(
+        ShieldError::Encoding,
+        Shielded::import(b"hello world", KEK).err().unwrap()
+    );
+}
+
+#[test]
+fn test_format_of() {
+    assert_eq!(Err(ShieldError::Encoding), Format::of(b""));
+    assert_eq!(Err(ShieldError::Encoding), Format::of(b"shld"));
+    assert_eq!(Err(ShieldError::Encoding), Format::of(b"shld\x02\x01"));
+    assert_eq!(Err(ShieldError::Encoding), Format::of(b"hello world"));
+    assert_eq!(Ok(Format::V1), Format::of(b"shld\x01\x01"));
+
+    let blob = Shielded::new(b"hello".to_vec()).export(KEK);
+    assert_eq!(Ok(Format::LATEST), Format::of(&blob));
+}
+
+quickcheck! {
+    fn prop_export_import(xs: Vec<u8>) -> bool {
+        let blob = Shielded::new(xs.clone()).export(KEK);
+        let mut imported = Shielded::import(&blob, KEK).expect("import");
+        let unshielded = imported.unshield();
+        xs == unshielded.as_ref()
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
+
+use shielded::{ExposurePolicy, ShieldError, Shielded, ShieldedCell};
+
+#[test]
+fn test_exposure_max_duration() {
+    let policy = ExposurePolicy::new().max_duration(Duration::from_millis(10));
+    let mut shielded = Shielded::builder()
+        .exposure_policy(policy)
+        .build(b"hello".to_vec())
+        .expect("build");
+
+    assert_eq!(b"hello", shielded.unshield().as_ref());
+
+    {
+        let unshielded = shielded.unshield();
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(b"hello", unshielded.as_ref());
+    }
+
+    assert_eq!(
+        ShieldError::PolicyViolation,
+        shielded.try_unshield().err().unwrap()
+    );
+    assert_eq!(
+        Err(ShieldError::PolicyViolation),
+        shielded.try_expose(|buf| buf.to_vec())
+    );
+    // The memory was shielded again.
+    shielded.verify().expect("verify");
+}
+
+#[test]
+fn test_unshield_for() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+
+    // Dropped in time, nothing happens.
+    assert_eq!(
+        b"hello",
+        shielded.unshield_for(Duration::from_secs(60)).as_ref()
+    );
+    assert_eq!(b"hello", shielded.unshield().as_ref());
+
+    {
+        let unshielded = shielded.unshield_for(Duration::from_millis(10));
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(b"hello", unshielded.as_ref());
+    }
+    assert_eq!(
+        ShieldError::PolicyViolation,
+        shielded.try_unshield().err().unwrap()
+    );
+    shielded.verify().expect("verify");
+}
+
+#[test]
+fn test_unshield_for_shorter_policy() {
+    // The shorter of the policy and the explicit duration applies.
+    let policy = ExposurePolicy::new().max_duration(Duration::from_millis(10));
+    let mut shielded = Shielded::builder()
+        .exposure_policy(policy)
+        .build(b"hello".to_vec())
+        .expect("build");
+    {
+        let unshielded = shielded.unshield_for(Duration::from_secs(60));
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(b"hello", unshielded.as_ref());
+    }
+    assert_eq!(
+        Err(ShieldError::PolicyViolation),
+        shielded
+            .try_unshield_for(Duration::from_secs(60))
+            .map(|_| ())
+    );
+}
+
+#[test]
+fn test_exposure_thread() {
+    let policy = ExposurePolicy::new().current_thread();
+    let mut shielded = Shielded::builder()
+        .exposure_policy(policy)
+        .build(b"hello".to_vec())
+        .expect("build");
+    assert_eq!(b"hello", shielded.unshield().as_ref());
+
+    let mut shielded = thread::spawn(move || {
+        assert_eq!(
+            ShieldError::PolicyViolation,
+            shielded.try_unshield().err().unwrap()
+        );
+        assert_eq!(
+            Err(ShieldError::PolicyViolation),
+            shielded
+                .try_unshield_range(0..1)
+                .map(|range| range.to_vec())
+        );
+        shielded
+    })
+    .join()
+    .unwrap();
+
+    // Wrong threads don't poison the memory.
+    assert_eq!(b"hello", shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_exposure_exclusive_cell() {
+    let policy = ExposurePolicy::new().exclusive(true);
+    let shielded = Shielded::builder()
+        .exposure_policy(policy)
+        .build(b"hello".to_vec())
+        .expect("build");
+    let cell = Arc::new(ShieldedCell::new(shielded));
+    let exposing = Arc::new(Barrier::new(2));
+    let checked = Arc::new(Barrier::new(2));
+
+    let handle = {
+        let (cell, exposing, checked) = (cell.clone(), exposing.clone(), checked.clone());
+        thread::spawn(move || {
+            cell.expose(|_| {
+                let _ = exposing.wait();
+                let _ = checked.wait();
+            })
+        })
+    };
+
+    let _ = exposing.wait();
+    assert_eq!(
+        Err(ShieldError::PolicyViolation),
+        cell.try_expose(|buf| buf.to_vec())
+    );
+    let _ = checked.wait();
+    handle.join().unwrap();
+
+    assert_eq!(b"hello", &cell.expose(|buf| buf.to_vec())[..]);
+}
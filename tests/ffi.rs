@@ -0,0 +1,96 @@
+#![cfg(feature = "ffi")]
+
+use std::ffi::CStr;
+use std::os::raw::c_void;
+use std::ptr;
+use std::slice;
+
+use shielded::ffi::*;
+
+extern "C" fn copy(ctx: *mut c_void, data: *const u8, len: usize) {
+    let out = unsafe { &mut *ctx.cast::<Vec<u8>>() };
+    out.extend_from_slice(unsafe { slice::from_raw_parts(data, len) });
+}
+
+extern "C" fn upper(_: *mut c_void, data: *mut u8, len: usize) {
+    unsafe { slice::from_raw_parts_mut(data, len) }.make_ascii_uppercase();
+}
+
+fn exposed(handle: *mut ShieldedHandle) -> Vec<u8> {
+    let mut out = Vec::new();
+    let ctx: *mut Vec<u8> = &mut out;
+    assert_eq!(SHIELDED_OK, unsafe {
+        shielded_expose(handle, Some(copy), ctx.cast())
+    });
+    out
+}
+
+#[test]
+fn test_ffi_round_trip() {
+    let mut handle = ptr::null_mut();
+    let secret = b"secret";
+    assert_eq!(SHIELDED_OK, unsafe {
+        shielded_new(secret.as_ptr(), secret.len(), &mut handle)
+    });
+    assert_eq!(6, unsafe { shielded_len(handle) });
+    assert_eq!(b"secret", &exposed(handle)[..]);
+
+    assert_eq!(SHIELDED_OK, unsafe {
+        shielded_expose_mut(handle, Some(upper), ptr::null_mut())
+    });
+    assert_eq!(b"SECRET", &exposed(handle)[..]);
+    assert_eq!(SHIELDED_OK, unsafe { shielded_verify(handle) });
+    unsafe { shielded_free(handle) };
+}
+
+#[test]
+fn test_ffi_empty() {
+    let mut handle = ptr::null_mut();
+    assert_eq!(SHIELDED_OK, unsafe {
+        shielded_new(ptr::null(), 0, &mut handle)
+    });
+    assert_eq!(0, unsafe { shielded_len(handle) });
+    assert!(exposed(handle).is_empty());
+    unsafe { shielded_free(handle) };
+}
+
+#[test]
+fn test_ffi_null() {
+    let mut handle = ptr::null_mut();
+    assert_eq!(SHIELDED_ERR_NULL, unsafe {
+        shielded_new(ptr::null(), 1, &mut handle)
+    });
+    assert_eq!(SHIELDED_ERR_NULL, unsafe {
+        shielded_new(b"x".as_ptr(), 1, ptr::null_mut())
+    });
+    assert!(handle.is_null());
+    assert_eq!(SHIELDED_ERR_NULL, unsafe {
+        shielded_expose(handle, Some(copy), ptr::null_mut())
+    });
+    assert_eq!(SHIELDED_ERR_NULL, unsafe { shielded_verify(handle) });
+    assert_eq!(0, unsafe { shielded_len(handle) });
+    unsafe { shielded_free(handle) };
+
+    assert_eq!(SHIELDED_OK, unsafe {
+        shielded_new(b"x".as_ptr(), 1, &mut handle)
+    });
+    assert_eq!(SHIELDED_ERR_NULL, unsafe {
+        shielded_expose(handle, None, ptr::null_mut())
+    });
+    unsafe { shielded_free(handle) };
+}
+
+#[test]
+fn test_ffi_strerror() {
+    let msg = |code| unsafe { CStr::from_ptr(shielded_strerror(code)) };
+    assert_eq!("success", msg(SHIELDED_OK).to_str().unwrap());
+    assert_eq!(
+        "shielded memory failed authentication",
+        msg(SHIELDED_ERR_TAMPER).to_str().unwrap()
+    );
+    assert_eq!(
+        "shielded memory is poisoned",
+        msg(SHIELDED_ERR_POISONED).to_str().unwrap()
+    );
+    assert_eq!("unknown error", msg(1).to_str().unwrap());
+}
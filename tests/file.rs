@@ -0,0 +1,87 @@
+#![cfg(feature = "passphrase")]
+
+use std::io;
+use std::path::PathBuf;
+
+use shielded::{ShieldError, Shielded};
+
+// A path in the temporary directory, removed when dropped.
+struct TempPath(PathBuf);
+
+impl TempPath {
+    fn new(name: &str) -> Self {
+        let name = format!("shielded-{}-{}", std::process::id(), name);
+        TempPath(std::env::temp_dir().join(name))
+    }
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn shield_error(e: io::Error) -> ShieldError {
+    assert_eq!(io::ErrorKind::InvalidData, e.kind());
+    *e.into_inner()
+        .expect("inner error")
+        .downcast::<ShieldError>()
+        .expect("ShieldError")
+}
+
+#[test]
+fn test_seal_to_file() {
+    let path = TempPath::new("seal");
+    let mut shielded = Shielded::new(b"hello world".to_vec());
+    shielded
+        .seal_to_file(&path.0, b"correct horse")
+        .expect("seal");
+
+    let file = std::fs::read(&path.0).expect("read");
+    assert!(!file.windows(5).any(|w| w == b"hello"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path.0)
+            .expect("metadata")
+            .permissions()
+            .mode();
+        assert_eq!(0o600, mode & 0o777);
+    }
+
+    let mut opened = Shielded::builder()
+        .chunk_size(4)
+        .open_from_file(&path.0, b"correct horse")
+        .expect("open");
+    assert_eq!(b"hello world", opened.unshield().as_ref());
+}
+
+#[test]
+fn test_open_from_file_wrong_passphrase() {
+    let path = TempPath::new("wrong");
+    Shielded::new(b"hello".to_vec())
+        .seal_to_file(&path.0, b"correct horse")
+        .expect("seal");
+
+    let err = Shielded::open_from_file(&path.0, b"battery staple")
+        .err()
+        .unwrap();
+    assert_eq!(ShieldError::Tamper, shield_error(err));
+}
+
+#[test]
+fn test_open_from_file_malformed() {
+    let path = TempPath::new("malformed");
+    std::fs::write(&path.0, b"hello world").expect("write");
+
+    let err = Shielded::open_from_file(&path.0, b"correct horse")
+        .err()
+        .unwrap();
+    assert_eq!(ShieldError::Encoding, shield_error(err));
+
+    let missing = TempPath::new("missing");
+    let err = Shielded::open_from_file(&missing.0, b"correct horse")
+        .err()
+        .unwrap();
+    assert_eq!(io::ErrorKind::NotFound, err.kind());
+}
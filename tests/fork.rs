@@ -0,0 +1,70 @@
+#![cfg(unix)]
+
+use std::sync::{Arc, Mutex};
+
+use shielded::audit::{Audit, AuditEvent};
+use shielded::{ReshieldPolicy, Shielded, ShieldedBuilder};
+
+// Records the kinds of events.
+#[derive(Debug, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Audit for Recorder {
+    fn on_shield(&self, _: &AuditEvent<'_>) {
+        self.events.lock().unwrap().push("shield");
+    }
+
+    fn on_unshield(&self, _: &AuditEvent<'_>) {
+        self.events.lock().unwrap().push("unshield");
+    }
+}
+
+// The only test in this binary, so no other thread runs while forking.
+#[test]
+fn test_rekey_after_fork() {
+    check_rekey_after_fork(Shielded::builder());
+    // Secret memory is shared with the child, which must leave it alone.
+    #[cfg(feature = "memfd-secret")]
+    check_rekey_after_fork(Shielded::builder().memfd_secret_memory(true));
+}
+
+fn check_rekey_after_fork(builder: ShieldedBuilder) {
+    let recorder = Recorder::default();
+    let events = recorder.events.clone();
+    let mut shielded = builder
+        .audit(recorder)
+        .reshield_policy(ReshieldPolicy::Every(100))
+        .build(b"secret".to_vec())
+        .expect("build");
+    assert_eq!(b"secret", shielded.unshield().as_ref());
+
+    match unsafe { libc::fork() } {
+        0 => {
+            // Shielded again before the first unshield, only then.
+            let ok = shielded.unshield().as_ref() == b"secret"
+                && shielded.unshield().as_ref() == b"secret"
+                && *events.lock().unwrap()
+                    == [
+                        "shield", "unshield", "shield", // before the fork
+                        "shield", "unshield", "shield", "unshield", "shield",
+                    ];
+            unsafe { libc::_exit(if ok { 0 } else { 1 }) }
+        }
+        -1 => panic!("fork failed"),
+        child => {
+            let mut status = 0;
+            assert_eq!(child, unsafe { libc::waitpid(child, &mut status, 0) });
+            assert!(libc::WIFEXITED(status));
+            assert_eq!(0, libc::WEXITSTATUS(status));
+
+            // The parent keeps its prekey.
+            assert_eq!(b"secret", shielded.unshield().as_ref());
+            assert_eq!(
+                vec!["shield", "unshield", "shield", "unshield", "shield"],
+                *events.lock().unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use shielded::Shielded;
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+fn poll_once<F: Future + Unpin>(future: &mut F) -> Poll<F::Output> {
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut cx = Context::from_waker(&waker);
+    Pin::new(future).poll(&mut cx)
+}
+
+#[test]
+fn test_expose_async() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+
+    let mut polls = 0;
+    let mut future = shielded.expose_async(|secret, cx| {
+        assert_eq!(b"hello", secret);
+        polls += 1;
+        if polls < 3 {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        } else {
+            Poll::Ready(secret.len())
+        }
+    });
+
+    assert_eq!(Poll::Pending, poll_once(&mut future));
+    assert_eq!(Poll::Pending, poll_once(&mut future));
+    assert_eq!(Poll::Ready(5), poll_once(&mut future));
+}
+
+#[test]
+fn test_expose_async_cancelled() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+
+    {
+        let mut future = shielded.try_expose_async(|_, _| Poll::<()>::Pending);
+        assert_eq!(Poll::Pending, poll_once(&mut future));
+    }
+
+    let unshielded = shielded.unshield();
+    assert_eq!(b"hello", unshielded.as_ref());
+}
@@ -0,0 +1,48 @@
+#![cfg(target_os = "linux")]
+
+use std::env;
+use std::os::unix::process::ExitStatusExt;
+use std::process::Command;
+use std::ptr;
+
+use shielded::Shielded;
+
+const CHILD: &str = "SHIELDED_GUARD_PAGES_CHILD";
+
+// Run the test `name` again in a child process, which reads the byte right
+// before the exposed memory, and return the signal that killed it.
+fn read_before_start(name: &str, shielded: impl FnOnce() -> Shielded) -> Option<i32> {
+    if env::var_os(CHILD).is_some() {
+        let mut shielded = shielded();
+        let byte =
+            shielded.expose(|content| unsafe { ptr::read_volatile(content.as_ptr().sub(1)) });
+        panic!("read {} from a guard page", byte);
+    }
+
+    let status = Command::new(env::current_exe().unwrap())
+        .args(["--exact", name, "--test-threads", "1"])
+        .env(CHILD, "1")
+        .status()
+        .unwrap();
+    status.signal()
+}
+
+#[test]
+fn test_guard_page_before_memory() {
+    let signal = read_before_start("test_guard_page_before_memory", || {
+        Shielded::new(b"secret".to_vec())
+    });
+    assert_eq!(Some(libc::SIGSEGV), signal);
+}
+
+#[cfg(feature = "memfd-secret")]
+#[test]
+fn test_guard_page_before_memfd_secret_memory() {
+    let signal = read_before_start("test_guard_page_before_memfd_secret_memory", || {
+        Shielded::builder()
+            .memfd_secret_memory(true)
+            .build(b"secret".to_vec())
+            .unwrap()
+    });
+    assert_eq!(Some(libc::SIGSEGV), signal);
+}
@@ -0,0 +1,51 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use shielded::{Cipher, Shielded};
+
+// Counts the heap allocations of the current thread.
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+// The derived keys are kept in secret memory of their own, not on the heap.
+#[test]
+fn test_keys_off_heap() {
+    for cipher in [Cipher::ChaCha20Poly1305, Cipher::Aes256Gcm, Cipher::Cascade] {
+        let mut shielded = Shielded::with_cipher(cipher, b"secret".to_vec());
+        let n = allocations(|| {
+            assert_eq!(b"secret", shielded.unshield().as_ref());
+        });
+        assert_eq!(0, n, "{:?}", cipher);
+    }
+}
+
+#[test]
+fn test_export_key_off_heap() {
+    let mut shielded = Shielded::new(b"secret".to_vec());
+    let kek = [7u8; 32];
+    // Only the exported blob itself.
+    assert_eq!(1, allocations(|| drop(shielded.export(&kek))));
+}
@@ -0,0 +1,163 @@
+use std::io::{self, Read, Write};
+
+use quickcheck::quickcheck;
+use shielded::{Shielded, ShieldedBuffer};
+
+// Reads `data` a few bytes at a time, interrupted now and then, failing at
+// the end if `fail` is set.
+struct Trickle<'a> {
+    data: &'a [u8],
+    reads: usize,
+    fail: bool,
+}
+
+impl<'a> Trickle<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            reads: 0,
+            fail: false,
+        }
+    }
+}
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reads += 1;
+        if self.reads.is_multiple_of(5) {
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+        if self.data.is_empty() && self.fail {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        let n = buf.len().min(self.data.len()).min(self.reads % 7 + 1);
+        buf[..n].copy_from_slice(&self.data[..n]);
+        self.data = &self.data[n..];
+        Ok(n)
+    }
+}
+
+fn write_chunked(data: &[u8], chunk_size: usize) -> Shielded {
+    let mut writer = Shielded::builder()
+        .chunk_size(chunk_size)
+        .writer()
+        .expect("writer");
+    // Write in pieces which don't line up with the chunks.
+    for piece in data.chunks(7) {
+        writer.write_all(piece).expect("write");
+    }
+    writer.finish().expect("finish")
+}
+
+#[test]
+fn test_writer() {
+    let data: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+
+    let mut shielded = write_chunked(&data, 1000);
+    assert_eq!(data, shielded.unshield().as_ref());
+    assert_eq!(&data[1234..5678], &*shielded.unshield_range(1234..5678));
+}
+
+#[test]
+fn test_writer_empty() {
+    let writer = Shielded::builder().writer().expect("writer");
+    let mut shielded = writer.finish().expect("finish");
+    assert!(shielded.unshield().as_ref().is_empty());
+}
+
+#[test]
+fn test_reader() {
+    let data: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+
+    let mut shielded = Shielded::builder()
+        .chunk_size(1000)
+        .build(data.clone())
+        .expect("build");
+
+    let mut read = Vec::new();
+    let _ = shielded.reader().read_to_end(&mut read).expect("read");
+    assert_eq!(data, read);
+
+    // The memory is still shielded and intact afterwards.
+    assert_eq!(data, shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_reader_unchunked() {
+    let mut shielded = Shielded::new(b"hello world".to_vec());
+
+    let mut read = String::new();
+    let _ = shielded.reader().read_to_string(&mut read).expect("read");
+    assert_eq!("hello world", read);
+}
+
+#[test]
+fn test_from_reader() {
+    let data: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+    let mut shielded = Shielded::from_reader(Trickle::new(&data)).expect("read");
+    assert_eq!(data, shielded.unshield().as_ref());
+
+    let mut shielded = Shielded::from_reader(io::empty()).expect("read");
+    assert!(shielded.unshield().as_ref().is_empty());
+}
+
+#[test]
+fn test_build_from_reader_chunked() {
+    let data: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+    let mut shielded = Shielded::builder()
+        .chunk_size(1000)
+        .build_from_reader(&data[..])
+        .expect("read");
+    assert_eq!(&data[2500..2600], &*shielded.unshield_range(2500..2600));
+}
+
+#[test]
+fn test_from_reader_error() {
+    let mut reader = Trickle::new(b"secret");
+    reader.fail = true;
+    let e = Shielded::from_reader(reader).expect_err("error");
+    assert_eq!(io::ErrorKind::BrokenPipe, e.kind());
+}
+
+#[test]
+fn test_buffer_read_from() {
+    let mut buffer = ShieldedBuffer::new();
+    buffer.extend_from_slice(b"hello").unwrap();
+    assert_eq!(6, buffer.read_from(Trickle::new(b" world")).unwrap());
+    assert_eq!(0, buffer.read_from(io::empty()).unwrap());
+
+    let mut reader = Trickle::new(b"!");
+    reader.fail = true;
+    assert!(buffer.read_from(reader).is_err());
+    assert_eq!(12, buffer.len());
+    let mut shielded = buffer.finish().unwrap();
+    assert_eq!(b"hello world!", shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_from_file() {
+    let path = std::env::temp_dir().join(format!("shielded-{}-from-file", std::process::id()));
+    std::fs::write(&path, b"secret").unwrap();
+    let result = Shielded::from_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(b"secret", result.expect("read").unshield().as_ref());
+
+    let e = Shielded::from_file(&path).expect_err("error");
+    assert_eq!(io::ErrorKind::NotFound, e.kind());
+}
+
+quickcheck! {
+    fn prop_from_reader(xs: Vec<u8>) -> bool {
+        let mut shielded = Shielded::from_reader(Trickle::new(&xs)).expect("read");
+        let unshielded = shielded.unshield();
+        xs == unshielded.as_ref()
+    }
+
+    fn prop_write_read(xs: Vec<u8>, chunk_size: u8) -> bool {
+        let mut shielded = write_chunked(&xs, chunk_size as usize + 1);
+
+        let mut read = Vec::new();
+        let _ = shielded.reader().read_to_end(&mut read).expect("read");
+        xs == read && xs == shielded.unshield().as_ref()
+    }
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "passphrase")]
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use shielded::{KdfParams, ShieldError, Shielded};
+
+const SALT: &[u8] = b"somesalt";
+
+fn params() -> KdfParams {
+    KdfParams::new(SALT).costs(64, 1, 1)
+}
+
+#[test]
+fn test_from_passphrase() {
+    let argon2 = Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        Params::new(64, 1, 1, Some(32)).unwrap(),
+    );
+    let mut expected = [0u8; 32];
+    argon2
+        .hash_password_into(b"password", SALT, &mut expected)
+        .unwrap();
+
+    let mut passphrase = b"password".to_vec();
+    let mut shielded = Shielded::from_passphrase(&mut passphrase, &params());
+    assert_eq!(vec![0; 8], passphrase);
+    assert_eq!(&expected, shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_from_passphrase_params() {
+    let mut a = Shielded::from_passphrase(&mut b"password".to_vec(), &params());
+    let mut b = Shielded::from_passphrase(&mut b"password".to_vec(), &params());
+    assert_eq!(a.unshield().as_ref(), b.unshield().as_ref());
+
+    let mut c = Shielded::from_passphrase(
+        &mut b"password".to_vec(),
+        &KdfParams::new(b"othersalt").costs(64, 1, 1),
+    );
+    assert_ne!(a.unshield().as_ref(), c.unshield().as_ref());
+
+    let mut d = Shielded::from_passphrase(&mut b"password".to_vec(), &params().costs(64, 2, 1));
+    assert_ne!(a.unshield().as_ref(), d.unshield().as_ref());
+
+    let mut e = Shielded::from_passphrase(&mut b"password".to_vec(), &params().output_len(64));
+    assert_eq!(64, e.len());
+    assert_ne!(a.unshield().as_ref(), &e.unshield().as_ref()[..32]);
+}
+
+#[test]
+fn test_from_passphrase_invalid() {
+    let mut passphrase = b"password".to_vec();
+    let short_salt = KdfParams::new(b"salt").costs(64, 1, 1);
+    assert_eq!(
+        ShieldError::Crypto,
+        Shielded::try_from_passphrase(&mut passphrase, &short_salt)
+            .err()
+            .unwrap()
+    );
+    assert_eq!(vec![0; 8], passphrase);
+
+    for params in [
+        params().costs(1, 1, 1),
+        params().costs(64, 0, 1),
+        params().output_len(2),
+    ] {
+        assert_eq!(
+            ShieldError::Crypto,
+            Shielded::try_from_passphrase(&mut b"password".to_vec(), &params)
+                .err()
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_build_from_passphrase() {
+    let mut shielded = Shielded::builder()
+        .label("login")
+        .build_from_passphrase(&mut b"password".to_vec(), &params())
+        .unwrap();
+    assert_eq!(32, shielded.len());
+    assert_eq!(
+        Shielded::from_passphrase(&mut b"password".to_vec(), &params())
+            .unshield()
+            .as_ref(),
+        shielded.unshield().as_ref()
+    );
+}
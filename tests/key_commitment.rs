@@ -0,0 +1,105 @@
+use std::io::{Read, Write};
+
+use shielded::backend::{Backend, SoftwareEnclave, WrappedPrekey, WrappingKey, WRAPPING_KEY_LEN};
+use shielded::{Cipher, ReshieldPolicy, ShieldError, Shielded};
+
+#[derive(Debug)]
+struct FixedKey;
+
+impl WrappingKey for FixedKey {
+    fn fetch(&self, key: &mut [u8; WRAPPING_KEY_LEN]) -> Result<(), ShieldError> {
+        key.copy_from_slice(&[7; WRAPPING_KEY_LEN]);
+        Ok(())
+    }
+}
+
+// Restores something else than what it was given.
+#[derive(Debug)]
+struct Forgetful;
+
+impl Backend for Forgetful {
+    fn protect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        prekey.iter_mut().for_each(|b| *b = 0);
+        Ok(())
+    }
+
+    fn unprotect(&self, _prekey: &mut [u8]) -> Result<(), ShieldError> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_key_commitment() {
+    for cipher in [Cipher::ChaCha20Poly1305, Cipher::Aes256Gcm, Cipher::Cascade] {
+        let mut shielded = Shielded::builder()
+            .cipher(cipher)
+            .chunk_size(4)
+            .key_commitment(true)
+            .build(b"hello world".to_vec())
+            .expect("build");
+        for _ in 0..3 {
+            assert_eq!(b"hello world", shielded.unshield().as_ref());
+        }
+        assert_eq!(b"o w", shielded.unshield_range(4..7).as_ref());
+        assert_eq!(Ok(()), shielded.verify());
+
+        let mut copy = shielded.try_clone().expect("clone");
+        assert_eq!(b"hello world", copy.unshield().as_ref());
+    }
+}
+
+#[test]
+fn test_key_commitment_kept_prekey() {
+    for policy in [ReshieldPolicy::Every(3), ReshieldPolicy::Always] {
+        let mut shielded = Shielded::builder()
+            .reshield_policy(policy)
+            .nonce_sequence(true)
+            .key_commitment(true)
+            .build(b"hello world".to_vec())
+            .expect("build");
+        for _ in 0..7 {
+            shielded.unshield_mut()[0] ^= 0x20;
+        }
+        assert_eq!(b"Hello world", shielded.unshield().as_ref());
+    }
+}
+
+#[test]
+fn test_key_commitment_wrong_prekey() {
+    let mut shielded = Shielded::builder()
+        .backend(Forgetful)
+        .key_commitment(true)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+    assert_eq!(ShieldError::Poisoned, shielded.verify().err().unwrap());
+}
+
+#[test]
+fn test_key_commitment_writer_and_reader() {
+    let mut writer = Shielded::builder()
+        .chunk_size(3)
+        .key_commitment(true)
+        .writer()
+        .expect("writer");
+    writer.write_all(b"hello world").expect("write");
+    let mut shielded = writer.finish().expect("finish");
+
+    let mut content = Vec::new();
+    let _ = shielded.reader().read_to_end(&mut content).expect("read");
+    assert_eq!(b"hello world".to_vec(), content);
+}
+
+#[test]
+fn test_key_commitment_backend_encrypts() {
+    let mut shielded = Shielded::builder()
+        .backend(SoftwareEnclave::new(WrappedPrekey::new(FixedKey)))
+        .key_commitment(true)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    for _ in 0..3 {
+        assert_eq!(b"hello world", shielded.unshield().as_ref());
+    }
+    let mut copy = shielded.try_clone().expect("clone");
+    assert_eq!(b"hello world", copy.unshield().as_ref());
+}
@@ -1,5 +1,5 @@
 use quickcheck::quickcheck;
-use shielded::Shielded;
+use shielded::{Cipher, LockMode, ReshieldPolicy, ShieldError, Shielded, ShieldedString};
 
 #[test]
 fn test_shielded_unshield() {
@@ -38,6 +38,472 @@ fn test_unshielded_as_mut() {
     assert_eq!(b"bello", unshielded.as_ref());
 }
 
+#[test]
+fn test_try_new_try_unshield() {
+    let buf = b"hello world".to_vec();
+
+    let original = buf.clone();
+    let mut shielded = Shielded::try_new(buf).expect("try_new");
+
+    let unshielded = shielded.try_unshield().expect("try_unshield");
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[test]
+fn test_shield_error_is_error() {
+    fn assert_error<E: std::error::Error + Send + Sync + 'static>(_: E) {}
+
+    assert_eq!(
+        "shielded memory failed authentication",
+        ShieldError::Tamper.to_string()
+    );
+    assert_error(ShieldError::Rng);
+}
+
+#[test]
+fn test_unshield_mut_modify() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+
+    {
+        let mut unshielded = shielded.unshield_mut();
+        unshielded[0] = b'j';
+    }
+
+    let unshielded = shielded.unshield();
+    assert_eq!(b"jello", unshielded.as_ref());
+}
+
+#[test]
+fn test_unshield_mut_truncate() {
+    let mut shielded = Shielded::new(b"hello world".to_vec());
+
+    {
+        let mut unshielded = shielded.unshield_mut();
+        unshielded.truncate(5);
+        assert_eq!(b"hello", &unshielded[..]);
+    }
+
+    let unshielded = shielded.unshield();
+    assert_eq!(b"hello", unshielded.as_ref());
+}
+
+#[test]
+fn test_unshield_mut_grow() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+
+    // Grow well beyond the original allocation.
+    let tail = vec![0xAA; 3 * 4096];
+    {
+        let mut unshielded = shielded.unshield_mut();
+        unshielded.extend_from_slice(&tail).expect("extend");
+        unshielded
+            .resize(unshielded.len() + 3, b'!')
+            .expect("resize");
+    }
+
+    let mut expected = b"hello".to_vec();
+    expected.extend_from_slice(&tail);
+    expected.extend_from_slice(b"!!!");
+
+    let unshielded = shielded.unshield();
+    assert_eq!(expected, unshielded.as_ref());
+}
+
+#[test]
+fn test_extend_from_slice_and_truncate() {
+    let mut shielded = Shielded::builder()
+        .chunk_size(4)
+        .build(b"token1".to_vec())
+        .expect("build");
+
+    shielded.extend_from_slice(b";token2");
+    assert_eq!(Ok(()), shielded.try_extend_from_slice(b";token3"));
+    assert_eq!(b"token1;token2;token3", shielded.unshield().as_ref());
+
+    shielded.truncate(100);
+    assert_eq!(20, shielded.len());
+    shielded.truncate(13);
+    assert_eq!(b"token1;token2", shielded.unshield().as_ref());
+    assert_eq!(Ok(()), shielded.try_truncate(0));
+    assert!(shielded.is_empty());
+}
+
+#[test]
+fn test_append_wipes_input() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+
+    let mut data = *b" world";
+    shielded.append(&mut data);
+    assert_eq!([0; 6], data);
+    assert_eq!(b"hello world", shielded.unshield().as_ref());
+
+    let mut data = vec![b'!'; 3 * 4096];
+    assert_eq!(Ok(()), shielded.try_append(&mut data));
+    assert!(data.iter().all(|&b| b == 0));
+    assert_eq!(11 + 3 * 4096, shielded.len());
+}
+
+#[test]
+fn test_expose() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+
+    let exposed = shielded.expose(|buf| buf.to_vec());
+    assert_eq!(b"hello", &exposed[..]);
+
+    shielded.expose_mut(|buf| buf[0] = b'c');
+    let exposed = shielded.try_expose(|buf| buf.to_vec()).expect("try_expose");
+    assert_eq!(b"cello", &exposed[..]);
+}
+
+#[test]
+fn test_expose_reshields_on_panic() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        shielded.expose(|_| panic!("oops"));
+    }));
+    assert!(result.is_err());
+
+    let unshielded = shielded.unshield();
+    assert_eq!(b"hello", unshielded.as_ref());
+}
+
+#[cfg(feature = "rustcrypto")]
+#[test]
+fn test_with_cipher_xchacha20poly1305() {
+    let buf = b"hello world".to_vec();
+
+    let original = buf.clone();
+    let mut shielded = Shielded::with_cipher(Cipher::XChaCha20Poly1305, buf);
+
+    {
+        let unshielded = shielded.unshield();
+        assert_eq!(original, unshielded.as_ref());
+    }
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[cfg(not(feature = "rustcrypto"))]
+#[test]
+fn test_xchacha20poly1305_unsupported() {
+    let result = Shielded::builder()
+        .cipher(Cipher::XChaCha20Poly1305)
+        .build(b"hello world".to_vec());
+    assert_eq!(ShieldError::Unsupported, result.err().unwrap());
+}
+
+#[test]
+fn test_with_cipher_aes256gcm() {
+    let buf = b"hello world".to_vec();
+
+    let original = buf.clone();
+    let mut shielded = Shielded::with_cipher(Cipher::Aes256Gcm, buf);
+
+    {
+        let unshielded = shielded.unshield();
+        assert_eq!(original, unshielded.as_ref());
+    }
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[test]
+fn test_shielded_string() {
+    let mut shielded = ShieldedString::from("pässword".to_string());
+
+    {
+        let unshielded = shielded.unshield();
+        assert_eq!("pässword", &*unshielded);
+    }
+
+    let unshielded = shielded.try_unshield().expect("try_unshield");
+    assert_eq!(9, unshielded.len());
+}
+
+#[test]
+fn test_not_locked_by_default() {
+    let shielded = Shielded::new(b"hello".to_vec());
+    assert!(!shielded.is_locked());
+}
+
+#[test]
+fn test_builder_lock_best_effort() {
+    let buf = b"hello world".to_vec();
+
+    let original = buf.clone();
+    let mut shielded = Shielded::builder()
+        .lock(LockMode::BestEffort)
+        .build(buf)
+        .expect("build");
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[test]
+fn test_builder_prekey_len() {
+    let buf = b"hello world".to_vec();
+
+    let original = buf.clone();
+    let mut shielded = Shielded::builder()
+        .prekey_len(4 * 1024)
+        .build(buf)
+        .expect("build");
+
+    {
+        let unshielded = shielded.unshield();
+        assert_eq!(original, unshielded.as_ref());
+    }
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[test]
+#[should_panic]
+fn test_builder_prekey_len_too_short() {
+    let _ = Shielded::builder().prekey_len(16);
+}
+
+#[test]
+fn test_builder_context() {
+    let buf = b"hello world".to_vec();
+
+    let original = buf.clone();
+    let mut shielded = Shielded::builder()
+        .context(b"my application")
+        .build(buf)
+        .expect("build");
+
+    {
+        let unshielded = shielded.unshield();
+        assert_eq!(original, unshielded.as_ref());
+    }
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+#[test]
+fn test_reshield_policy_keeps_content() {
+    for policy in &[
+        ReshieldPolicy::Every(4),
+        ReshieldPolicy::After(std::time::Duration::from_secs(3600)),
+    ] {
+        let mut shielded = Shielded::builder()
+            .reshield_policy(*policy)
+            .build(b"hello".to_vec())
+            .expect("build");
+
+        for _ in 0..10 {
+            shielded
+                .unshield_mut()
+                .extend_from_slice(b"!")
+                .expect("extend");
+        }
+        shielded.unshield_mut().as_mut()[0] = b'j';
+
+        let unshielded = shielded.unshield();
+        assert_eq!(b"jello!!!!!!!!!!", unshielded.as_ref());
+    }
+}
+
+#[test]
+fn test_len() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+    assert_eq!(5, shielded.len());
+    assert!(!shielded.is_empty());
+
+    shielded.unshield_mut().truncate(0);
+    assert_eq!(0, shielded.len());
+    assert!(shielded.is_empty());
+
+    let shielded = Shielded::builder()
+        .chunk_size(3)
+        .build(vec![0; 10])
+        .expect("build");
+    assert_eq!(10, shielded.len());
+
+    let string = ShieldedString::new(String::from("héllo"));
+    assert_eq!(6, string.len());
+}
+
+#[test]
+fn test_verify() {
+    for chunk_size in &[None, Some(3)] {
+        for buf in &[&b""[..], &b"hello world"[..]] {
+            let mut builder = Shielded::builder();
+            if let Some(chunk_size) = chunk_size {
+                builder = builder.chunk_size(*chunk_size);
+            }
+            let mut shielded = builder.build(buf.to_vec()).expect("build");
+
+            shielded.verify().expect("verify");
+            assert_eq!(*buf, shielded.unshield().as_ref());
+            shielded.verify().expect("verify after unshield");
+        }
+    }
+}
+
+#[test]
+fn test_ct_eq() {
+    let mut shielded = Shielded::builder()
+        .chunk_size(3)
+        .build(b"hello world".to_vec())
+        .expect("build");
+
+    assert!(shielded.ct_eq(b"hello world"));
+    assert!(!shielded.ct_eq(b"hello worle"));
+    assert!(!shielded.ct_eq(b"jello world"));
+    assert!(!shielded.ct_eq(b"hello"));
+    assert!(!shielded.ct_eq(b""));
+    assert_eq!(Ok(true), shielded.try_ct_eq(b"hello world"));
+
+    let mut empty = Shielded::new(Vec::new());
+    assert!(empty.ct_eq(b""));
+    assert!(!empty.ct_eq(b"x"));
+}
+
+#[test]
+fn test_ct_eq_shielded() {
+    let mut a = Shielded::builder()
+        .chunk_size(3)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    let mut b = Shielded::new(b"hello world".to_vec());
+    let mut c = Shielded::new(b"hello worle".to_vec());
+    let mut d = Shielded::new(b"hello".to_vec());
+
+    assert!(a.ct_eq_shielded(&mut b));
+    assert!(b.ct_eq_shielded(&mut a));
+    assert!(!a.ct_eq_shielded(&mut c));
+    assert!(!a.ct_eq_shielded(&mut d));
+    assert_eq!(Ok(false), d.try_ct_eq_shielded(&mut a));
+
+    let mut empty = Shielded::new(Vec::new());
+    assert!(empty.ct_eq_shielded(&mut Shielded::new(Vec::new())));
+    assert!(!empty.ct_eq_shielded(&mut d));
+
+    // Both stay shielded and intact.
+    assert_eq!(b"hello world", a.unshield().as_ref());
+    assert_eq!(b"hello world", b.unshield().as_ref());
+}
+
+#[test]
+fn test_unshield_into() {
+    let mut shielded = Shielded::builder()
+        .chunk_size(3)
+        .build(b"hello world".to_vec())
+        .expect("build");
+
+    let mut out = [0xffu8; 16];
+    assert_eq!(11, shielded.unshield_into(&mut out));
+    assert_eq!(b"hello world", &out[..11]);
+    assert_eq!([0xff; 5], out[11..]);
+    assert_eq!(Ok(11), shielded.try_unshield_into(&mut out[..11]));
+
+    let mut empty = Shielded::new(Vec::new());
+    assert_eq!(0, empty.unshield_into(&mut []));
+}
+
+#[test]
+#[should_panic(expected = "output buffer too small")]
+fn test_unshield_into_too_small() {
+    let mut shielded = Shielded::new(b"hello world".to_vec());
+    let _ = shielded.unshield_into(&mut [0u8; 10]);
+}
+
+#[test]
+fn test_into_inner() {
+    let shielded = Shielded::builder()
+        .chunk_size(3)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    assert_eq!(b"hello world".to_vec(), shielded.into_inner());
+
+    let empty = Shielded::new(Vec::new());
+    assert_eq!(Ok(Vec::new()), empty.try_into_inner());
+}
+
+#[test]
+fn test_try_clone() {
+    let mut shielded = Shielded::builder()
+        .chunk_size(4)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    let mut copy = shielded.try_clone().expect("clone");
+    assert_eq!(shielded.len(), copy.len());
+
+    // Shielded under keys of its own, the same content encrypts differently.
+    let ciphertext = |shielded: &mut Shielded| {
+        let (ptr, len) = shielded.expose(|content| (content.as_ptr(), content.len()));
+        unsafe { std::slice::from_raw_parts(ptr, len) }.to_vec()
+    };
+    assert_ne!(ciphertext(&mut shielded), ciphertext(&mut copy));
+
+    copy.truncate(5);
+    assert_eq!(b"hello world", shielded.unshield().as_ref());
+    assert_eq!(b"hello", copy.unshield().as_ref());
+
+    let mut empty = Shielded::new(Vec::new());
+    assert!(empty.try_clone().expect("clone").is_empty());
+}
+
+#[test]
+fn test_debug_redacted() {
+    let mut shielded = Shielded::builder()
+        .label("api token")
+        .build(b"hunter2".to_vec())
+        .expect("build");
+    let fingerprint = shielded.fingerprint();
+    assert_eq!(fingerprint, shielded.fingerprint());
+
+    let debug = format!("{:?}", shielded);
+    assert!(!debug.contains("hunter2"));
+    assert!(!debug.contains("104, 117"));
+    assert!(debug.contains("api token"));
+    assert!(debug.contains("len: 7"));
+    assert!(debug.contains(&format!("{:016x}", fingerprint)));
+
+    // Shielded again under a new nonce.
+    assert_eq!(b"hunter2", shielded.unshield().as_ref());
+    assert_ne!(fingerprint, shielded.fingerprint());
+    let other = Shielded::new(b"hunter2".to_vec());
+    assert_ne!(other.fingerprint(), shielded.fingerprint());
+
+    let string = ShieldedString::new(String::from("hunter2"));
+    assert!(!format!("{:?}", string).contains("hunter2"));
+}
+
+#[test]
+fn test_builder_random() {
+    let mut a = Shielded::builder().random(32).expect("random");
+    let mut b = Shielded::builder()
+        .chunk_size(5)
+        .random(32)
+        .expect("random");
+    assert_eq!(32, a.len());
+    assert_ne!(a.unshield().as_ref(), b.unshield().as_ref());
+
+    let empty = Shielded::builder().random(0).expect("random");
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_builder_lock_required() {
+    // Whether locking succeeds depends on RLIMIT_MEMLOCK of the test runner.
+    match Shielded::builder()
+        .lock(LockMode::Required)
+        .build(b"hello".to_vec())
+    {
+        Ok(shielded) => assert!(shielded.is_locked()),
+        Err(err) => assert_eq!(ShieldError::Lock, err),
+    }
+}
+
 quickcheck! {
     fn prop_shield_unshield(xs: Vec<u8>) -> bool {
         let original = xs.clone();
@@ -58,4 +524,88 @@ quickcheck! {
         let unshielded = shielded.unshield();
         original == unshielded.as_ref()
     }
+
+    fn prop_shield_unshield_aes256gcm(xs: Vec<u8>) -> bool {
+        let original = xs.clone();
+        let mut shielded = Shielded::builder()
+            .cipher(Cipher::Aes256Gcm)
+            .build(xs)
+            .expect("build");
+        let unshielded = shielded.unshield();
+        original == unshielded.as_ref()
+    }
+
+    fn prop_len(xs: Vec<u8>, chunk_size: u8) -> bool {
+        let len = xs.len();
+        let shielded = Shielded::builder()
+            .chunk_size(chunk_size as usize + 1)
+            .build(xs)
+            .expect("build");
+        len == shielded.len()
+    }
+
+    fn prop_ct_eq(xs: Vec<u8>, ys: Vec<u8>) -> bool {
+        let mut shielded = Shielded::new(xs.clone());
+        shielded.ct_eq(&xs) && shielded.ct_eq(&ys) == (xs == ys)
+    }
+
+    fn prop_unshield_into(xs: Vec<u8>, chunk_size: u8) -> bool {
+        let mut shielded = Shielded::builder()
+            .chunk_size(chunk_size as usize + 1)
+            .build(xs.clone())
+            .expect("build");
+        let mut out = vec![0u8; xs.len()];
+        shielded.unshield_into(&mut out) == xs.len() && out == xs
+    }
+
+    fn prop_into_inner(xs: Vec<u8>, chunk_size: u8) -> bool {
+        let shielded = Shielded::builder()
+            .chunk_size(chunk_size as usize + 1)
+            .build(xs.clone())
+            .expect("build");
+        shielded.into_inner() == xs
+    }
+
+    fn prop_shielded_string(s: String) -> bool {
+        let original = s.clone();
+        let mut shielded = ShieldedString::new(s);
+        let unshielded = shielded.unshield();
+        original == *unshielded
+    }
+
+    fn prop_unshield_mut_extend(xs: Vec<u8>, ys: Vec<u8>) -> bool {
+        let mut expected = xs.clone();
+        expected.extend_from_slice(&ys);
+        let mut shielded = Shielded::new(xs);
+
+        shielded
+            .unshield_mut()
+            .extend_from_slice(&ys)
+            .expect("extend");
+
+        let unshielded = shielded.unshield();
+        expected == unshielded.as_ref()
+    }
+    fn prop_extend_from_slice(xs: Vec<u8>, ys: Vec<u8>, chunk_size: u8) -> bool {
+        let mut expected = xs.clone();
+        expected.extend_from_slice(&ys);
+        let mut shielded = Shielded::builder()
+            .chunk_size(chunk_size as usize + 1)
+            .build(xs)
+            .expect("build");
+
+        shielded.extend_from_slice(&ys);
+        shielded.truncate(expected.len() / 2);
+        expected.truncate(expected.len() / 2);
+        let unshielded = shielded.unshield();
+        expected == unshielded.as_ref()
+    }
+    fn prop_ct_eq_shielded(xs: Vec<u8>, ys: Vec<u8>, chunk_size: u8) -> bool {
+        let mut a = Shielded::builder()
+            .chunk_size(chunk_size as usize + 1)
+            .build(xs.clone())
+            .expect("build");
+        a.ct_eq_shielded(&mut Shielded::new(ys.clone())) == (xs == ys)
+            && a.ct_eq_shielded(&mut Shielded::new(xs))
+    }
 }
@@ -0,0 +1,65 @@
+#![cfg(all(target_os = "linux", feature = "linux-keyring"))]
+
+use std::fs;
+
+use shielded::backend::LinuxKeyring;
+use shielded::{ShieldError, Shielded};
+
+// Valid keys holding prekeys of this process.
+fn prekeys() -> usize {
+    let description = format!("shielded prekey {} ", std::process::id());
+    fs::read_to_string("/proc/keys")
+        .expect("read /proc/keys")
+        .lines()
+        .filter(|line| line.contains(&description))
+        .filter(|line| {
+            let flags = line.split_whitespace().nth(1).unwrap_or("");
+            !flags.contains('i') && !flags.contains('D')
+        })
+        .count()
+}
+
+fn shielded(buf: &[u8]) -> Shielded {
+    Shielded::builder()
+        .prekey_len(4096)
+        .backend(LinuxKeyring::new())
+        .build(buf.to_vec())
+        .expect("build")
+}
+
+#[test]
+fn test_linux_keyring() {
+    let mut first = shielded(b"hello world");
+    let mut second = shielded(b"another secret");
+    assert_eq!(2, prekeys());
+
+    for _ in 0..3 {
+        assert_eq!(b"hello world", first.unshield().as_ref());
+        assert_eq!(b"another secret", second.unshield().as_ref());
+        assert_eq!(2, prekeys());
+    }
+
+    {
+        let unshielded = first.unshield();
+        assert_eq!(1, prekeys());
+        drop(unshielded);
+    }
+    assert_eq!(2, prekeys());
+
+    drop(first);
+    assert_eq!(1, prekeys());
+
+    let mut expiring = Shielded::builder()
+        .prekey_len(4096)
+        .max_uses(1)
+        .backend(LinuxKeyring::new())
+        .build(b"once".to_vec())
+        .expect("build");
+    assert_eq!(2, prekeys());
+    assert_eq!(b"once", expiring.unshield().as_ref());
+    assert_eq!(1, prekeys());
+    assert_eq!(ShieldError::Expired, expiring.try_unshield().err().unwrap());
+
+    drop(second);
+    assert_eq!(0, prekeys());
+}
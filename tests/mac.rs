@@ -0,0 +1,33 @@
+use shielded::{Padding, Shielded};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// RFC 4231, test case 2.
+const KEY: &[u8] = b"Jefe";
+const MESSAGE: &[u8] = b"what do ya want for nothing?";
+
+#[test]
+fn test_hmac_sha256() {
+    let mut key = Shielded::new(KEY.to_vec());
+    assert_eq!(
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+        hex(&key.hmac_sha256(MESSAGE))
+    );
+    assert_eq!(Ok(key.hmac_sha256(MESSAGE)), key.try_hmac_sha256(MESSAGE));
+}
+
+#[test]
+fn test_hmac_sha512() {
+    let mut key = Shielded::builder()
+        .chunk_size(3)
+        .padding(Padding::Block(16))
+        .build(KEY.to_vec())
+        .expect("build");
+    assert_eq!(
+        "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea250554\
+         9758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737",
+        hex(&key.hmac_sha512(MESSAGE))
+    );
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "memfd-secret")]
+
+use quickcheck::quickcheck;
+use shielded::Shielded;
+
+#[test]
+fn test_memfd_secret_memory() {
+    let buf = b"hello world".to_vec();
+
+    let original = buf.clone();
+    let mut shielded = Shielded::builder()
+        .memfd_secret_memory(true)
+        .build(buf)
+        .expect("build");
+
+    {
+        let unshielded = shielded.unshield();
+        assert_eq!(original, unshielded.as_ref());
+    }
+
+    let unshielded = shielded.unshield();
+    assert_eq!(original, unshielded.as_ref());
+}
+
+quickcheck! {
+    fn prop_memfd_secret_shield_unshield(xs: Vec<u8>) -> bool {
+        let original = xs.clone();
+        let mut shielded = Shielded::new(xs);
+        let unshielded = shielded.unshield();
+        original == unshielded.as_ref()
+    }
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "metrics")]
+
+use metrics_util::debugging::{DebugValue, DebuggingRecorder, Snapshotter};
+use shielded::metrics::{EXPOSURE_SECONDS, PROTECTED_BYTES, SHIELDS, UNSHIELDS};
+use shielded::Shielded;
+
+// The value of the metric `name` of the memories labelled `label`.
+fn value(snapshotter: &Snapshotter, name: &str, label: &str) -> Option<DebugValue> {
+    snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .find(|(key, _, _, _)| {
+            let key = key.key();
+            key.name() == name
+                && key
+                    .labels()
+                    .any(|l| l.key() == "label" && l.value() == label)
+        })
+        .map(|(_, _, _, value)| value)
+}
+
+fn counter(snapshotter: &Snapshotter, name: &str, label: &str) -> u64 {
+    match value(snapshotter, name, label) {
+        Some(DebugValue::Counter(n)) => n,
+        None => 0,
+        value => panic!("not a counter: {:?}", value),
+    }
+}
+
+fn gauge(snapshotter: &Snapshotter, name: &str, label: &str) -> f64 {
+    match value(snapshotter, name, label) {
+        Some(DebugValue::Gauge(n)) => n.0,
+        value => panic!("not a gauge: {:?}", value),
+    }
+}
+
+#[test]
+fn test_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+
+    metrics::with_local_recorder(&recorder, || {
+        let mut shielded = Shielded::builder()
+            .label("db password")
+            .build(b"hunter2".to_vec())
+            .expect("build");
+        assert_eq!(1, counter(&snapshotter, SHIELDS, "db password"));
+        assert_eq!(7.0, gauge(&snapshotter, PROTECTED_BYTES, "db password"));
+
+        for _ in 0..3 {
+            assert_eq!(b"hunter2", shielded.unshield().as_ref());
+        }
+        // Taking a snapshot drains the histograms.
+        match value(&snapshotter, EXPOSURE_SECONDS, "db password") {
+            Some(DebugValue::Histogram(durations)) => assert_eq!(3, durations.len()),
+            value => panic!("not a histogram: {:?}", value),
+        }
+        assert!(shielded.ct_eq(b"hunter2"));
+        assert_eq!(4, counter(&snapshotter, SHIELDS, "db password"));
+        assert_eq!(4, counter(&snapshotter, UNSHIELDS, "db password"));
+
+        shielded
+            .unshield_mut()
+            .extend_from_slice(b"!!!")
+            .expect("extend");
+        assert_eq!(10.0, gauge(&snapshotter, PROTECTED_BYTES, "db password"));
+
+        let other = Shielded::builder()
+            .label("api token")
+            .build(b"token".to_vec())
+            .expect("build");
+        assert_eq!(5.0, gauge(&snapshotter, PROTECTED_BYTES, "api token"));
+        assert_eq!(0, counter(&snapshotter, UNSHIELDS, "api token"));
+
+        drop(shielded);
+        assert_eq!(0.0, gauge(&snapshotter, PROTECTED_BYTES, "db password"));
+        drop(other);
+        assert_eq!(0.0, gauge(&snapshotter, PROTECTED_BYTES, "api token"));
+    });
+}
+
+#[test]
+fn test_metrics_expired() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+
+    metrics::with_local_recorder(&recorder, || {
+        let mut shielded = Shielded::builder()
+            .label("once")
+            .max_uses(1)
+            .build(b"hunter2".to_vec())
+            .expect("build");
+        assert_eq!(b"hunter2", shielded.unshield().as_ref());
+        assert_eq!(0.0, gauge(&snapshotter, PROTECTED_BYTES, "once"));
+        drop(shielded);
+        assert_eq!(0.0, gauge(&snapshotter, PROTECTED_BYTES, "once"));
+    });
+}
@@ -0,0 +1,166 @@
+use std::io::{Read, Write};
+
+use quickcheck::quickcheck;
+use shielded::{Padding, Shielded, ShieldedBuffer};
+
+fn padded(padding: Padding, chunk_size: Option<usize>, buf: &[u8]) -> Shielded {
+    let mut builder = Shielded::builder().padding(padding);
+    if let Some(chunk_size) = chunk_size {
+        builder = builder.chunk_size(chunk_size);
+    }
+    builder.build(buf.to_vec()).expect("build")
+}
+
+#[test]
+fn test_padding_unshield_into() {
+    let mut shielded = padded(Padding::Block(256), Some(100), b"hello");
+    let mut out = [0u8; 5];
+    assert_eq!(5, shielded.unshield_into(&mut out));
+    assert_eq!(b"hello", &out);
+}
+
+#[test]
+fn test_padding_hides_len() {
+    let mut shielded = padded(Padding::Block(256), None, b"hello");
+    assert_eq!(256, shielded.len());
+    assert_eq!(b"hello", shielded.unshield().as_ref());
+
+    let shielded = padded(Padding::Block(256), None, &[0; 255]);
+    assert_eq!(256, shielded.len());
+    let shielded = padded(Padding::Block(256), None, &[0; 256]);
+    assert_eq!(512, shielded.len());
+
+    let mut shielded = padded(Padding::PowerOfTwo, None, b"");
+    assert_eq!(1, shielded.len());
+    assert!(!shielded.is_empty());
+    assert_eq!(b"", shielded.unshield().as_ref());
+
+    let shielded = padded(Padding::PowerOfTwo, None, &[0x80; 1000]);
+    assert_eq!(1024, shielded.len());
+}
+
+#[test]
+fn test_padding_unshield_mut() {
+    let mut shielded = padded(Padding::Block(16), Some(5), b"hello world");
+
+    shielded
+        .unshield_mut()
+        .extend_from_slice(&[0x80; 20])
+        .expect("extend");
+    assert_eq!(32, shielded.len());
+
+    let mut expected = b"hello world".to_vec();
+    expected.extend_from_slice(&[0x80; 20]);
+    assert_eq!(expected, shielded.unshield().as_ref());
+
+    shielded.unshield_mut().truncate(5);
+    assert_eq!(16, shielded.len());
+    assert_eq!(b"hello", shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_padding_extend_from_slice_and_truncate() {
+    let mut shielded = padded(Padding::Block(16), None, b"hello");
+
+    shielded.extend_from_slice(&[b'!'; 12]);
+    assert_eq!(32, shielded.len());
+    shielded.truncate(6);
+    assert_eq!(16, shielded.len());
+    assert_eq!(b"hello!", shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_padding_try_clone() {
+    let mut shielded = padded(Padding::Block(16), Some(5), b"hello");
+    let mut copy = shielded.try_clone().expect("clone");
+    assert_eq!(16, copy.len());
+    assert_eq!(b"hello", copy.unshield().as_ref());
+}
+
+#[test]
+fn test_padding_unshield_range_and_reader() {
+    let content: Vec<u8> = (0..100).collect();
+    let mut shielded = padded(Padding::PowerOfTwo, Some(7), &content);
+    assert_eq!(128, shielded.len());
+
+    assert_eq!(&content[90..100], &*shielded.unshield_range(90..100));
+    let mut view = shielded.view(0..10);
+    assert_eq!(&content[..10], &*view);
+    view.set_window(90..100);
+    assert_eq!(&content[90..100], &*view);
+    drop(view);
+
+    let mut read = Vec::new();
+    let _ = shielded.reader().read_to_end(&mut read).expect("read");
+    assert_eq!(content, read);
+}
+
+#[test]
+#[should_panic]
+fn test_padding_unshield_range_out_of_bounds() {
+    let mut shielded = padded(Padding::Block(64), None, b"hello");
+    let _ = shielded.unshield_range(0..6);
+}
+
+#[test]
+fn test_padding_ct_eq_and_verify() {
+    let mut shielded = padded(Padding::Block(16), Some(4), b"hunter2");
+
+    shielded.verify().expect("verify");
+    assert!(shielded.ct_eq(b"hunter2"));
+    assert!(!shielded.ct_eq(b"hunter"));
+    assert!(!shielded.ct_eq(b"hunter2\x80"));
+    assert!(!shielded.ct_eq(b"hunter2\0"));
+}
+
+#[test]
+fn test_padding_ct_eq_shielded() {
+    let mut a = padded(Padding::Block(16), Some(5), b"hello");
+    let mut b = padded(Padding::PowerOfTwo, None, b"hello");
+    let mut c = padded(Padding::Block(16), None, b"hello!");
+    assert!(a.ct_eq_shielded(&mut b));
+    assert!(b.ct_eq_shielded(&mut a));
+    assert!(!a.ct_eq_shielded(&mut c));
+    assert!(a.ct_eq_shielded(&mut Shielded::new(b"hello".to_vec())));
+}
+
+#[test]
+fn test_padding_writer_and_buffer() {
+    let builder = Shielded::builder()
+        .padding(Padding::Block(100))
+        .chunk_size(32);
+
+    let mut writer = builder.clone().writer().expect("writer");
+    writer.write_all(&[7; 150]).expect("write");
+    let mut shielded = writer.finish().expect("finish");
+    assert_eq!(200, shielded.len());
+    assert_eq!(&[7; 150][..], shielded.unshield().as_ref());
+
+    let mut buffer = builder.buffer();
+    buffer.extend_from_slice(b"hello").expect("extend");
+    let mut shielded = buffer.finish().expect("finish");
+    assert_eq!(100, shielded.len());
+    assert_eq!(b"hello", shielded.unshield().as_ref());
+
+    let mut shielded = ShieldedBuffer::new().finish().expect("finish");
+    assert_eq!(b"", shielded.unshield().as_ref());
+}
+
+#[test]
+#[should_panic]
+fn test_padding_zero_block() {
+    let _ = Shielded::builder().padding(Padding::Block(0));
+}
+
+quickcheck! {
+    fn prop_padding(xs: Vec<u8>, block: u8, chunk_size: u8) -> bool {
+        let padding = match block {
+            0 => Padding::PowerOfTwo,
+            block => Padding::Block(block as usize),
+        };
+        let mut shielded = padded(padding, Some(chunk_size as usize + 1), &xs);
+        shielded.len() > xs.len()
+            && shielded.ct_eq(&xs)
+            && xs == shielded.unshield().as_ref()
+    }
+}
@@ -0,0 +1,30 @@
+#![cfg(all(target_os = "linux", target_pointer_width = "64"))]
+
+use std::fs;
+
+use shielded::Shielded;
+
+// Where prekeys are placed at random.
+const LOW: u64 = 1 << 36;
+const HIGH: u64 = 1 << 45;
+
+// Accessible mappings starting in the range of random placement.
+fn random_mappings() -> usize {
+    let maps = fs::read_to_string("/proc/self/maps").unwrap();
+    maps.lines()
+        .filter(|line| line.contains(" rw-"))
+        .filter(|line| {
+            let start = line.split('-').next().unwrap();
+            let start = u64::from_str_radix(start, 16).unwrap();
+            (LOW..HIGH).contains(&start)
+        })
+        .count()
+}
+
+#[test]
+fn test_prekey_random_placement() {
+    let before = random_mappings();
+    let shielded: Vec<Shielded> = (0..4).map(|_| Shielded::new(b"secret".to_vec())).collect();
+    // Other tests running concurrently may add prekeys of their own.
+    assert!(random_mappings() >= before + shielded.len());
+}
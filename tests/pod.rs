@@ -0,0 +1,68 @@
+#![cfg(feature = "bytemuck")]
+
+use bytemuck::{Pod, Zeroable};
+use shielded::{ShieldError, Shielded, ShieldedBox};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+struct ChaChaKey {
+    key: [u8; 32],
+    nonce: [u8; 12],
+    counter: u32,
+}
+
+unsafe impl Zeroable for ChaChaKey {}
+unsafe impl Pod for ChaChaKey {}
+
+#[test]
+fn test_shielded_box() {
+    let value = ChaChaKey {
+        key: [1; 32],
+        nonce: [2; 12],
+        counter: 7,
+    };
+    let mut shielded = ShieldedBox::new(value);
+    for _ in 0..3 {
+        let unshielded = shielded.unshield();
+        assert_eq!(value, *unshielded);
+        assert_eq!(7, unshielded.counter);
+    }
+    assert_eq!(48, shielded.into_inner().len());
+}
+
+#[test]
+fn test_shielded_box_words() {
+    let mut shielded = ShieldedBox::new([u64::MAX, 1, 2]);
+    assert_eq!(&[u64::MAX, 1, 2], shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_shielded_box_random() {
+    let mut a = ShieldedBox::<[u8; 32]>::random(Shielded::builder()).expect("random");
+    let mut b = ShieldedBox::<[u8; 32]>::random(Shielded::builder()).expect("random");
+    assert_ne!(*a.unshield(), *b.unshield());
+}
+
+#[test]
+fn test_shielded_box_from_shielded() {
+    let shielded = Shielded::new(vec![9; 4]);
+    let mut boxed = ShieldedBox::<u32>::from_shielded(shielded).expect("from shielded");
+    assert_eq!(0x0909_0909, *boxed.unshield());
+
+    let shielded = Shielded::new(vec![9; 5]);
+    assert_eq!(
+        ShieldError::Encoding,
+        ShieldedBox::<u32>::from_shielded(shielded).err().unwrap()
+    );
+}
+
+#[test]
+fn test_shielded_box_expired() {
+    let shielded = Shielded::builder()
+        .max_uses(1)
+        .build(vec![1; 8])
+        .expect("build");
+    let mut boxed = ShieldedBox::<[u8; 8]>::from_shielded(shielded).expect("from shielded");
+    assert_eq!([1; 8], *boxed.unshield());
+    assert_eq!(ShieldError::Expired, boxed.try_unshield().err().unwrap());
+}
@@ -0,0 +1,82 @@
+use std::ptr;
+
+use shielded::{Padding, ShieldError, Shielded};
+
+// Flip a bit of the ciphertext, as memory corruption could.
+fn corrupt(shielded: &mut Shielded) {
+    let memory = shielded.expose(|content| content.as_ptr() as *mut u8);
+    unsafe {
+        ptr::write_volatile(memory, ptr::read_volatile(memory) ^ 1);
+    }
+}
+
+#[test]
+fn test_poison_on_tamper() {
+    let mut shielded = Shielded::new(b"hello world".to_vec());
+    corrupt(&mut shielded);
+    assert!(!shielded.is_poisoned());
+
+    assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+    assert!(shielded.is_poisoned());
+    assert_eq!(
+        ShieldError::Poisoned,
+        shielded.try_unshield().err().unwrap()
+    );
+    assert_eq!(
+        ShieldError::Poisoned,
+        shielded.try_unshield_mut().err().unwrap()
+    );
+    assert_eq!(Err(ShieldError::Poisoned), shielded.verify());
+    assert_eq!(
+        Err(ShieldError::Poisoned),
+        shielded.try_ct_eq(b"hello world")
+    );
+    assert_eq!(ShieldError::Poisoned, shielded.try_clone().err().unwrap());
+    assert_eq!(
+        ShieldError::Poisoned,
+        shielded.try_unshield_range(0..5).err().unwrap()
+    );
+}
+
+#[test]
+fn test_poison_on_verify() {
+    let mut shielded = Shielded::builder()
+        .chunk_size(4)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    corrupt(&mut shielded);
+
+    assert_eq!(Err(ShieldError::Tamper), shielded.verify());
+    assert!(shielded.is_poisoned());
+    assert_eq!(
+        ShieldError::Poisoned,
+        shielded.try_unshield().err().unwrap()
+    );
+}
+
+#[test]
+fn test_recover_from() {
+    let mut shielded = Shielded::builder()
+        .chunk_size(4)
+        .padding(Padding::Block(16))
+        .build(b"hello world".to_vec())
+        .expect("build");
+    corrupt(&mut shielded);
+    assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+
+    shielded
+        .try_recover_from(b"hello again, world".to_vec())
+        .expect("recover");
+    assert!(!shielded.is_poisoned());
+    assert_eq!(32, shielded.len());
+    assert_eq!(b"hello again, world", shielded.unshield().as_ref());
+    assert_eq!(Ok(()), shielded.verify());
+    assert_eq!(b"again", shielded.unshield_range(6..11).as_ref());
+}
+
+#[test]
+fn test_recover_from_intact() {
+    let mut shielded = Shielded::new(b"old secret".to_vec());
+    shielded.recover_from(b"new secret".to_vec());
+    assert_eq!(b"new secret", shielded.unshield().as_ref());
+}
@@ -0,0 +1,69 @@
+use shielded::{ProtectionRequirements, ShieldError, Shielded};
+
+#[test]
+fn test_protection_status() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+    let status = shielded.protection_status();
+    assert_eq!(shielded.is_locked(), status.locked());
+    #[cfg(any(unix, windows))]
+    assert!(status.guard_pages());
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    assert!(status.dump_excluded());
+    #[cfg(not(feature = "memfd-secret"))]
+    assert!(!status.memfd_secret());
+
+    // Unchanged by shielding again.
+    assert_eq!(b"hello", shielded.unshield().as_ref());
+    assert_eq!(status, shielded.protection_status());
+}
+
+#[test]
+fn test_require_protection() {
+    let mut requirements = ProtectionRequirements::new();
+    #[cfg(any(unix, windows))]
+    {
+        requirements = requirements.guard_pages();
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    {
+        requirements = requirements.dump_excluded();
+    }
+    let mut shielded = Shielded::builder()
+        .require_protection(requirements)
+        .build(b"hello".to_vec())
+        .expect("build");
+    assert!(shielded.protection_status().satisfies(&requirements));
+
+    // Growing the memory allocates it again, with the same protections.
+    shielded.extend_from_slice(&[b'!'; 8192]);
+    assert_eq!(8197, shielded.len());
+    assert!(shielded.protection_status().satisfies(&requirements));
+}
+
+#[test]
+fn test_require_locked() {
+    // Locked even without a lock mode, if the platform and the limits allow.
+    let requirements = ProtectionRequirements::new().locked();
+    match Shielded::builder()
+        .require_protection(requirements)
+        .build(b"hello".to_vec())
+    {
+        Ok(shielded) => assert!(shielded.protection_status().locked()),
+        Err(e) => assert_eq!(ShieldError::Unprotected, e),
+    }
+}
+
+#[cfg(not(feature = "memfd-secret"))]
+#[test]
+fn test_require_memfd_secret_unavailable() {
+    let requirements = ProtectionRequirements::new().memfd_secret();
+    let result = Shielded::builder()
+        .require_protection(requirements)
+        .build(b"hello".to_vec());
+    assert_eq!(ShieldError::Unprotected, result.unwrap_err());
+
+    let result = Shielded::builder()
+        .require_protection(requirements)
+        .build_small(&mut b"hello".to_owned());
+    assert_eq!(ShieldError::Unprotected, result.unwrap_err());
+}
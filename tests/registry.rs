@@ -0,0 +1,69 @@
+#![cfg(feature = "std")]
+
+use std::panic;
+
+use shielded::{registry, ShieldError, Shielded, ShieldedSmall, ShieldedStore};
+
+// The only test in this binary, as wiping reaches all memory of the process.
+#[test]
+fn test_wipe_all() {
+    // Not registered, so never wiped.
+    let mut early = Shielded::new(b"early".to_vec());
+    assert!(!registry::is_enabled());
+    registry::enable();
+    assert!(registry::is_enabled());
+
+    let mut shielded = Shielded::builder()
+        .chunk_size(4)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    let mut store = ShieldedStore::new();
+    store.insert("token", b"hunter2".to_vec()).expect("insert");
+    let mut small = ShieldedSmall::new(&mut b"pin".to_owned());
+    // Freed before the wipe, which must not touch it anymore.
+    drop(Shielded::new(b"dropped".to_vec()));
+
+    shielded::wipe_all();
+    assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+    assert_eq!(
+        ShieldError::Poisoned,
+        shielded.try_unshield().err().unwrap()
+    );
+    let token = store.get_mut("token").expect("entry");
+    assert_eq!(ShieldError::Tamper, token.try_unshield().err().unwrap());
+    assert_eq!(ShieldError::Tamper, small.try_unshield().err().unwrap());
+    assert_eq!(b"early", early.unshield().as_ref());
+
+    // Memory allocated afterwards works.
+    shielded.recover_from(b"hello again".to_vec());
+    assert_eq!(b"hello again", shielded.unshield().as_ref());
+
+    // Exposed content is left alone, and wiped once shielded again.
+    {
+        let unshielded = shielded.unshield();
+        shielded::wipe_all();
+        assert_eq!(b"hello again", unshielded.as_ref());
+    }
+    assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+    let mut small = ShieldedSmall::new(&mut b"pin".to_owned());
+    {
+        let unshielded = small.unshield();
+        shielded::wipe_all();
+        assert_eq!(b"pin", &unshielded[..]);
+    }
+    assert_eq!(ShieldError::Tamper, small.try_unshield().err().unwrap());
+
+    registry::wipe_on_panic();
+    let mut shielded = Shielded::new(b"secret".to_vec());
+    assert!(panic::catch_unwind(|| panic!("compromised")).is_err());
+    assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+
+    #[cfg(unix)]
+    {
+        // Ignored by default, so handling it again doesn't stop the test.
+        registry::wipe_on_signal(libc::SIGWINCH).expect("signal");
+        let mut shielded = Shielded::new(b"secret".to_vec());
+        assert_eq!(0, unsafe { libc::raise(libc::SIGWINCH) });
+        assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+    }
+}
@@ -0,0 +1,92 @@
+#![cfg(feature = "auto-rotate")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use shielded::audit::{Audit, AuditEvent};
+use shielded::{ReshieldPolicy, Rotator, ShieldError, Shielded, ShieldedCell};
+
+// Counts how often the memory has been shielded.
+#[derive(Debug, Default)]
+struct Shields(Arc<AtomicUsize>);
+
+impl Audit for Shields {
+    fn on_shield(&self, _event: &AuditEvent<'_>) {
+        let _ = self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn counted(builder: shielded::ShieldedBuilder) -> (Arc<ShieldedCell>, Arc<AtomicUsize>) {
+    let shields = Shields::default();
+    let count = shields.0.clone();
+    let shielded = builder
+        .audit(shields)
+        .build(b"hello".to_vec())
+        .expect("build");
+    (Arc::new(ShieldedCell::new(shielded)), count)
+}
+
+// Wait until `count` reaches `n`, failing after a generous timeout.
+fn wait_for(count: &AtomicUsize, n: usize) {
+    let start = Instant::now();
+    while count.load(Ordering::SeqCst) < n {
+        assert!(start.elapsed() < Duration::from_secs(10), "not rotated");
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn test_rotator() {
+    let rotator = Rotator::spawn(Duration::from_millis(5));
+    // Not rekeyed on its own for a long time.
+    let (cell, shields) =
+        counted(Shielded::builder().reshield_policy(ReshieldPolicy::Every(u64::MAX)));
+    rotator.register(&cell);
+    assert_eq!(1, rotator.len());
+
+    wait_for(&shields, 4);
+    assert_eq!(b"hello", &cell.expose(|content| content.to_vec())[..]);
+}
+
+#[test]
+fn test_rotator_skips_busy_and_dropped() {
+    let rotator = Rotator::spawn(Duration::from_millis(1));
+    let (cell, shields) = counted(Shielded::builder());
+    rotator.register(&cell);
+
+    // Held for a while, the rotator doesn't wait for it nor make it fail.
+    let before = cell.expose(|content| {
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(b"hello", content);
+        shields.load(Ordering::SeqCst)
+    });
+    // Shielded once when the closure returned.
+    assert!(shields.load(Ordering::SeqCst) > before);
+    wait_for(&shields, before + 4);
+
+    // A round in progress may still hold the cell for a moment.
+    drop(cell);
+    let start = Instant::now();
+    while !rotator.is_empty() {
+        assert!(start.elapsed() < Duration::from_secs(10), "not dropped");
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(0, rotator.len());
+}
+
+#[test]
+fn test_rotator_doesnt_count_uses() {
+    let rotator = Rotator::spawn(Duration::from_millis(1));
+    let (cell, shields) = counted(Shielded::builder().max_uses(1));
+    rotator.register(&cell);
+    wait_for(&shields, 4);
+
+    assert_eq!(b"hello", &cell.expose(|content| content.to_vec())[..]);
+    assert_eq!(
+        Err(ShieldError::Expired),
+        cell.try_expose(|content| content.len())
+    );
+    drop(rotator);
+}
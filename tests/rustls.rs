@@ -0,0 +1,99 @@
+#![cfg(all(feature = "rustls", feature = "ring"))]
+
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, PrivateSec1KeyDer};
+use rustls::sign::SigningKey;
+use rustls::{SignatureAlgorithm, SignatureScheme};
+use shielded::rustls::ShieldedTlsKey;
+use shielded::ShieldError;
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("hex"))
+        .collect()
+}
+
+// RFC 8032 test 1 seed as PKCS#8.
+fn ed25519_key() -> PrivateKeyDer<'static> {
+    let der = hex(concat!(
+        "302e020100300506032b657004220420",
+        "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60"
+    ));
+    PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(der))
+}
+
+fn p256_key() -> PrivateKeyDer<'static> {
+    let der = hex(concat!(
+        "30770201010420bd434a5dc7b875f80b940c68d284f60ad11f4587e5286c691a5a7eea20",
+        "dcd293a00a06082a8648ce3d030107a14403420004143639f68e0286c26caf0c01db77d0",
+        "07975d8e2bec000a07c9d2ff9cd1af70cd6618d7b976c8bc266efcf810a208f5769f0595",
+        "bb5616a8c8c93f542bfce4e748"
+    ));
+    PrivateKeyDer::Sec1(PrivateSec1KeyDer::from(der))
+}
+
+#[test]
+fn test_tls_key_ed25519() {
+    let key = ShieldedTlsKey::new(ed25519_key()).expect("key");
+    assert_eq!(SignatureAlgorithm::ED25519, key.algorithm());
+
+    let public_key = key.public_key().expect("public key");
+    assert!(public_key.as_ref().ends_with(&hex(
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+    )));
+
+    assert!(key
+        .choose_scheme(&[SignatureScheme::ECDSA_NISTP256_SHA256])
+        .is_none());
+    let signer = key
+        .choose_scheme(&[SignatureScheme::RSA_PSS_SHA256, SignatureScheme::ED25519])
+        .expect("scheme");
+    assert_eq!(SignatureScheme::ED25519, signer.scheme());
+    assert_eq!(
+        hex(concat!(
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb88215",
+            "90a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+        )),
+        signer.sign(b"").expect("sign")
+    );
+}
+
+#[test]
+fn test_tls_key_ecdsa() {
+    let key = ShieldedTlsKey::new(p256_key()).expect("key");
+    assert_eq!(SignatureAlgorithm::ECDSA, key.algorithm());
+
+    let signer = key
+        .choose_scheme(&[
+            SignatureScheme::ECDSA_NISTP384_SHA384,
+            SignatureScheme::ECDSA_NISTP256_SHA256,
+        ])
+        .expect("scheme");
+    assert_eq!(SignatureScheme::ECDSA_NISTP256_SHA256, signer.scheme());
+
+    let signature = signer.sign(b"message").expect("sign");
+    let public_key = key.public_key().expect("public key");
+    let point = &public_key.as_ref()[public_key.as_ref().len() - 65..];
+    UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, point)
+        .verify(b"message", &signature)
+        .expect("verify");
+}
+
+#[test]
+fn test_tls_key_certified() {
+    let certified = ShieldedTlsKey::new(ed25519_key())
+        .expect("key")
+        .certified_key(vec![CertificateDer::from(vec![0u8; 4])]);
+    assert_eq!(1, certified.cert.len());
+    assert_eq!(SignatureAlgorithm::ED25519, certified.key.algorithm());
+}
+
+#[test]
+fn test_tls_key_invalid() {
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(vec![0x30, 0x00]));
+    assert_eq!(
+        ShieldError::Encoding,
+        ShieldedTlsKey::new(key).err().unwrap()
+    );
+}
@@ -0,0 +1,126 @@
+use std::io::Read;
+
+use quickcheck::quickcheck;
+use shielded::{ReshieldPolicy, ShieldError, Shielded};
+
+// Flips every bit of the prekey at rest.
+#[derive(Debug)]
+struct Invert;
+
+impl shielded::backend::Backend for Invert {
+    fn protect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        prekey.iter_mut().for_each(|b| *b = !*b);
+        Ok(())
+    }
+
+    fn unprotect(&self, prekey: &mut [u8]) -> Result<(), ShieldError> {
+        self.protect(prekey)
+    }
+}
+
+#[test]
+fn test_scattered_prekey() {
+    let mut shielded = Shielded::builder()
+        .scatter_prekey(64)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    for _ in 0..3 {
+        assert_eq!(b"hello world", shielded.unshield().as_ref());
+    }
+    assert_eq!(Ok(()), shielded.verify());
+    assert_eq!(Ok(()), shielded.check_canaries());
+}
+
+#[test]
+fn test_scattered_prekey_uneven_fragments() {
+    // 1024 bytes don't split evenly into 7 fragments.
+    let mut shielded = Shielded::builder()
+        .prekey_len(1024)
+        .scatter_prekey(7)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    assert_eq!(b"hello world", shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_scattered_prekey_more_fragments_than_bytes() {
+    let mut shielded = Shielded::builder()
+        .prekey_len(1024)
+        .scatter_prekey(5000)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    assert_eq!(b"hello world", shielded.unshield().as_ref());
+}
+
+#[test]
+fn test_scattered_prekey_with_backend() {
+    let mut shielded = Shielded::builder()
+        .scatter_prekey(16)
+        .backend(Invert)
+        .reshield_policy(ReshieldPolicy::Every(2))
+        .build(b"hello world".to_vec())
+        .expect("build");
+    for _ in 0..4 {
+        assert_eq!(b"hello world", shielded.unshield().as_ref());
+    }
+}
+
+#[test]
+fn test_scattered_prekey_try_clone() {
+    let mut shielded = Shielded::builder()
+        .prekey_len(1024)
+        .scatter_prekey(7)
+        .backend(Invert)
+        .build(b"hello world".to_vec())
+        .expect("build");
+    let mut copy = shielded.try_clone().expect("clone");
+    drop(shielded);
+    assert_eq!(b"hello world", copy.unshield().as_ref());
+    assert_eq!(Ok(()), copy.check_canaries());
+}
+
+#[test]
+fn test_scattered_prekey_chunked() {
+    let content: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+    let mut shielded = Shielded::builder()
+        .scatter_prekey(16)
+        .chunk_size(1000)
+        .build(content.clone())
+        .expect("build");
+    assert_eq!(&content[2500..2600], &*shielded.unshield_range(2500..2600));
+
+    let mut read = Vec::new();
+    let _ = shielded.reader().read_to_end(&mut read).unwrap();
+    assert_eq!(content, read);
+}
+
+#[test]
+fn test_scattered_prekey_writer() {
+    let mut writer = Shielded::builder()
+        .scatter_prekey(16)
+        .chunk_size(4)
+        .writer()
+        .unwrap();
+    std::io::Write::write_all(&mut writer, b"hello world").unwrap();
+    let mut shielded = writer.finish().unwrap();
+    assert_eq!(b"hello world", shielded.unshield().as_ref());
+}
+
+#[test]
+#[should_panic(expected = "at least one fragment")]
+fn test_scatter_prekey_zero() {
+    let _ = Shielded::builder().scatter_prekey(0);
+}
+
+quickcheck! {
+    fn prop_scattered_prekey(xs: Vec<u8>, fragments: u8) -> bool {
+        let fragments = usize::from(fragments) + 1;
+        let mut shielded = Shielded::builder()
+            .prekey_len(1024)
+            .scatter_prekey(fragments)
+            .build(xs.clone())
+            .expect("build");
+        let unshielded = shielded.unshield();
+        xs == unshielded.as_ref()
+    }
+}
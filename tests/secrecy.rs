@@ -0,0 +1,64 @@
+#![cfg(feature = "secrecy")]
+
+use secrecy::{ExposeSecret, SecretSlice};
+use shielded::{ShieldError, Shielded, ShieldedCell};
+
+// Written against secrecy only, as code using shielded memory would be.
+fn digest(secret: &impl ExposeSecret<[u8]>) -> u32 {
+    secret
+        .expose_secret()
+        .iter()
+        .fold(0, |acc, b| acc.wrapping_mul(31).wrapping_add(u32::from(*b)))
+}
+
+#[test]
+fn test_from_secret_slice() {
+    let secret = SecretSlice::from(b"hunter2".to_vec());
+    let expected = digest(&secret);
+
+    let mut shielded = Shielded::from(secret);
+    assert_eq!(7, shielded.len());
+    assert_eq!(b"hunter2", shielded.unshield().as_ref());
+    assert_eq!(expected, shielded.with_secret(|secret| digest(secret)));
+}
+
+#[test]
+fn test_with_secret() {
+    let mut shielded = Shielded::new(b"hello".to_vec());
+    let content = shielded.with_secret(|secret| secret.expose_secret().to_vec());
+    assert_eq!(b"hello".to_vec(), content);
+    assert_eq!(
+        "ExposedSecret([REDACTED])",
+        shielded.with_secret(|secret| format!("{:?}", secret))
+    );
+    shielded.verify().expect("verify");
+}
+
+#[test]
+fn test_with_secret_expired() {
+    let mut shielded = Shielded::builder()
+        .max_uses(1)
+        .build(b"hello".to_vec())
+        .expect("build");
+    assert_eq!(
+        5,
+        shielded.with_secret(|secret| secret.expose_secret().len())
+    );
+    assert_eq!(
+        Err(ShieldError::Expired),
+        shielded.try_with_secret(|secret| secret.expose_secret().len())
+    );
+}
+
+#[test]
+fn test_cell_with_secret() {
+    let cell = ShieldedCell::new(Shielded::new(b"hello".to_vec()));
+    assert_eq!(
+        digest(&SecretSlice::from(b"hello".to_vec())),
+        cell.with_secret(|secret| digest(secret))
+    );
+    assert_eq!(
+        Ok(5),
+        cell.try_with_secret(|secret| secret.expose_secret().len())
+    );
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "serde")]
+
+use serde::{Deserialize, Serialize};
+use shielded::serde::Exported;
+use shielded::{ShieldError, Shielded};
+
+#[derive(Deserialize)]
+struct Config {
+    user: String,
+    password: Shielded,
+}
+
+#[derive(Serialize, Deserialize)]
+struct State {
+    token: Exported,
+}
+
+#[test]
+fn test_deserialize_from_string() {
+    let json = r#"{ "user": "admin", "password": "hunter2" }"#;
+    let mut config: Config = serde_json::from_str(json).expect("deserialize");
+    assert_eq!("admin", config.user);
+    assert_eq!(b"hunter2", config.password.unshield().as_ref());
+
+    // With an escape, so decoded by the deserializer first.
+    let json = r#"{ "user": "admin", "password": "hunter\u0032" }"#;
+    let mut config: Config = serde_json::from_str(json).expect("deserialize");
+    assert_eq!(b"hunter2", config.password.unshield().as_ref());
+}
+
+#[test]
+fn test_deserialize_from_bytes() {
+    let json = r#"{ "user": "admin", "password": [104, 117, 110, 116, 101, 114, 50] }"#;
+    let mut config: Config = serde_json::from_str(json).expect("deserialize");
+    assert_eq!(b"hunter2", config.password.unshield().as_ref());
+
+    let bytes = bincode::serialize(&("admin", b"hunter2".to_vec())).expect("serialize");
+    let mut config: Config = bincode::deserialize(&bytes).expect("deserialize");
+    assert_eq!(b"hunter2", config.password.unshield().as_ref());
+
+    let empty: Shielded = serde_json::from_str("\"\"").expect("deserialize");
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_deserialize_invalid() {
+    assert!(serde_json::from_str::<Shielded>("42").is_err());
+    assert!(serde_json::from_str::<Shielded>("[1, 256]").is_err());
+}
+
+#[test]
+fn test_exported() {
+    let kek = [7u8; 32];
+    let mut token = Shielded::new(b"hunter2".to_vec());
+    let state = State {
+        token: Exported::new(&mut token, &kek).expect("export"),
+    };
+    assert!(!state
+        .token
+        .as_bytes()
+        .windows(7)
+        .any(|window| window == b"hunter2"));
+
+    let json = serde_json::to_vec(&state).expect("serialize");
+    let bincode = bincode::serialize(&state).expect("serialize");
+    for state in [
+        serde_json::from_slice::<State>(&json).expect("deserialize"),
+        bincode::deserialize::<State>(&bincode).expect("deserialize"),
+    ] {
+        let mut token = state.token.import(&kek).expect("import");
+        assert_eq!(b"hunter2", token.unshield().as_ref());
+        assert_eq!(
+            ShieldError::Tamper,
+            state.token.import(&[8; 32]).err().unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_exported_invalid() {
+    let json = r#"{ "token": [1, 2, 3] }"#;
+    assert!(serde_json::from_str::<State>(json).is_err());
+}
@@ -0,0 +1,107 @@
+use quickcheck::quickcheck;
+use shielded::{Padding, ShieldError, Shielded};
+
+const SECRET: &[u8] = b"0123456789abcdef";
+
+fn combined(shares: &mut [Shielded]) -> Vec<u8> {
+    Shielded::combine(shares)
+        .expect("combine")
+        .unshield()
+        .as_ref()
+        .to_vec()
+}
+
+#[test]
+fn test_split_combine() {
+    let mut root = Shielded::new(SECRET.to_vec());
+    let mut shares = root.split(5, 3);
+    assert_eq!(5, shares.len());
+    for (x, share) in shares.iter_mut().enumerate() {
+        let unshielded = share.unshield().as_ref().to_vec();
+        assert_eq!(17, unshielded.len());
+        assert_eq!(x as u8 + 1, unshielded[0]);
+        assert_ne!(SECRET, &unshielded[1..]);
+    }
+
+    assert_eq!(SECRET, &combined(&mut shares[..3])[..]);
+    assert_eq!(SECRET, &combined(&mut shares[2..])[..]);
+    assert_eq!(SECRET, &combined(&mut shares[..])[..]);
+    shares.swap(0, 4);
+    shares.swap(1, 3);
+    assert_eq!(SECRET, &combined(&mut shares[1..4])[..]);
+
+    // Too few shares.
+    assert_ne!(SECRET, &combined(&mut shares[..2])[..]);
+
+    // The original stays usable.
+    assert_eq!(SECRET, root.unshield().as_ref());
+}
+
+#[test]
+fn test_split_single_share() {
+    let mut shares = Shielded::new(SECRET.to_vec()).split(3, 1);
+    for share in shares.iter_mut() {
+        assert_eq!(SECRET, &share.unshield().as_ref()[1..]);
+    }
+}
+
+#[test]
+fn test_split_with_builder() {
+    let mut root = Shielded::new(SECRET.to_vec());
+    let mut shares = Shielded::builder()
+        .padding(Padding::Block(64))
+        .split(&mut root, 3, 2)
+        .expect("split");
+    assert_eq!(64, shares[0].len());
+
+    let mut combined = Shielded::builder()
+        .chunk_size(5)
+        .combine(&mut shares[1..])
+        .expect("combine");
+    assert_eq!(SECRET, combined.unshield().as_ref());
+}
+
+#[test]
+fn test_combine_malformed() {
+    let mut shares = Shielded::new(SECRET.to_vec()).split(3, 2);
+    assert_eq!(
+        ShieldError::Encoding,
+        Shielded::combine(&mut []).err().unwrap()
+    );
+
+    let duplicate = shares[2].unshield().as_ref().to_vec();
+    shares.push(Shielded::new(duplicate));
+    assert_eq!(
+        ShieldError::Encoding,
+        Shielded::combine(&mut shares[2..]).err().unwrap()
+    );
+    let _ = shares.pop();
+
+    shares.push(Shielded::new(vec![4, 0]));
+    assert_eq!(
+        ShieldError::Encoding,
+        Shielded::combine(&mut shares[2..]).err().unwrap()
+    );
+    let _ = shares.pop();
+
+    shares.push(Shielded::new(vec![0; 17]));
+    assert_eq!(
+        ShieldError::Encoding,
+        Shielded::combine(&mut shares[2..]).err().unwrap()
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_split_threshold_above_count() {
+    let _ = Shielded::new(SECRET.to_vec()).split(2, 3);
+}
+
+quickcheck! {
+    fn prop_split_combine(xs: Vec<u8>, n: u8, k: u8) -> bool {
+        let n = n % 32 + 1;
+        let k = k % n + 1;
+        let mut shares = Shielded::new(xs.clone()).split(n, k);
+        xs == combined(&mut shares[(n - k) as usize..])
+    }
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "ed25519")]
+
+use shielded::{ShieldError, Shielded, ShieldedSigningKey};
+
+fn unhex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_signing_key_rfc8032() {
+    // RFC 8032, section 7.1, test 1.
+    let seed = unhex("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60");
+    let shielded = Shielded::builder()
+        .chunk_size(7)
+        .build(seed)
+        .expect("build");
+    let mut key = ShieldedSigningKey::from_shielded(shielded).expect("key");
+
+    assert_eq!(
+        unhex("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"),
+        key.public_key()
+    );
+    assert_eq!(
+        unhex(
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+        ),
+        &key.sign(b"")[..]
+    );
+}
+
+#[test]
+fn test_signing_key_generate() {
+    let mut key = ShieldedSigningKey::generate().expect("generate");
+    let other = ShieldedSigningKey::generate().expect("generate");
+    assert_ne!(key.public_key(), other.public_key());
+
+    assert_eq!(key.sign(b"message"), key.sign(b"message"));
+    assert_ne!(key.sign(b"message"), key.sign(b"massage"));
+    assert_eq!(32, key.into_inner().len());
+}
+
+#[test]
+fn test_signing_key_wrong_seed_len() {
+    let shielded = Shielded::new(vec![0; 31]);
+    assert_eq!(
+        ShieldError::Encoding,
+        ShieldedSigningKey::from_shielded(shielded).err().unwrap()
+    );
+}
@@ -0,0 +1,99 @@
+use std::ptr;
+
+use shielded::entropy::EntropySource;
+use shielded::{Cipher, ShieldError, Shielded, ShieldedSmall};
+
+#[derive(Debug)]
+struct Failing;
+
+impl EntropySource for Failing {
+    fn fill(&self, _buf: &mut [u8]) -> Result<(), ShieldError> {
+        Err(ShieldError::Rng)
+    }
+}
+
+#[test]
+fn test_small() {
+    let mut secret = *b"hello world";
+    let mut shielded = ShieldedSmall::new(&mut secret);
+    assert_eq!([0; 11], secret);
+    assert_eq!(11, shielded.len());
+    assert!(!shielded.is_empty());
+
+    for _ in 0..3 {
+        assert_eq!(b"hello world", shielded.unshield().as_ref());
+    }
+    assert_eq!(11, shielded.expose(|secret| secret.len()));
+
+    shielded.unshield()[0] = b'j';
+    assert_eq!(b"jello world", shielded.unshield().as_ref());
+}
+
+fn round_trip(cipher: Cipher) {
+    let mut secret = [7; ShieldedSmall::MAX_LEN];
+    let mut shielded = Shielded::builder()
+        .cipher(cipher)
+        .prekey_len(1024)
+        .context(b"small")
+        .build_small(&mut secret)
+        .expect("build");
+    for _ in 0..3 {
+        assert_eq!(
+            &[7; ShieldedSmall::MAX_LEN][..],
+            shielded.unshield().as_ref()
+        );
+    }
+}
+
+#[test]
+fn test_small_ciphers() {
+    for cipher in [Cipher::ChaCha20Poly1305, Cipher::Aes256Gcm, Cipher::Cascade] {
+        round_trip(cipher);
+    }
+}
+
+#[cfg(feature = "rustcrypto")]
+#[test]
+fn test_small_xchacha20poly1305() {
+    round_trip(Cipher::XChaCha20Poly1305);
+}
+
+#[test]
+fn test_small_empty() {
+    let mut shielded = ShieldedSmall::new(&mut []);
+    assert!(shielded.is_empty());
+    assert_eq!(b"", shielded.unshield().as_ref());
+}
+
+#[test]
+#[should_panic(expected = "at most 64 bytes")]
+fn test_small_too_long() {
+    let _ = ShieldedSmall::new(&mut [0; ShieldedSmall::MAX_LEN + 1]);
+}
+
+#[test]
+fn test_small_entropy_failure() {
+    let result = Shielded::builder()
+        .entropy(Failing)
+        .build_small(&mut b"hello".to_owned());
+    assert_eq!(ShieldError::Rng, result.unwrap_err());
+}
+
+#[test]
+fn test_small_tamper() {
+    let mut shielded = ShieldedSmall::new(&mut b"hello".to_owned());
+    // The secret is shielded again in the same place once the guard drops.
+    let secret = shielded.unshield().as_mut_ptr();
+    unsafe {
+        ptr::write_volatile(secret, ptr::read_volatile(secret) ^ 1);
+    }
+    assert_eq!(ShieldError::Tamper, shielded.try_unshield().err().unwrap());
+}
+
+#[test]
+fn test_small_debug_redacted() {
+    let shielded = ShieldedSmall::new(&mut b"hunter2".to_owned());
+    let debug = format!("{:?}", shielded);
+    assert!(debug.starts_with("ShieldedSmall { len: 7"), "{}", debug);
+    assert!(!debug.contains("hunter2"));
+}
@@ -0,0 +1,63 @@
+use shielded::{ReshieldPolicy, Shielded, ShieldedStore};
+
+#[test]
+fn test_store() {
+    let mut store = ShieldedStore::new();
+    assert!(store.is_empty());
+
+    store.insert("db", b"hunter2".to_vec()).expect("insert");
+    store.insert("api", b"token".to_vec()).expect("insert");
+    assert_eq!(2, store.len());
+    assert!(store.contains("db"));
+    assert_eq!(vec!["api", "db"], store.names().collect::<Vec<_>>());
+
+    assert_eq!(
+        Some(b"hunter2".to_vec()),
+        store.expose("db", |b| b.to_vec())
+    );
+    assert_eq!(None, store.expose("tls", |b| b.to_vec()));
+    assert_eq!(Ok(None), store.try_expose("tls", |b| b.to_vec()));
+
+    store
+        .insert("db", b"correct horse".to_vec())
+        .expect("replace");
+    assert_eq!(2, store.len());
+    assert_eq!(
+        Some(b"correct horse".to_vec()),
+        store.expose("db", |b| b.to_vec())
+    );
+
+    let shielded = store.get_mut("api").expect("get_mut");
+    shielded
+        .unshield_mut()
+        .extend_from_slice(b"!")
+        .expect("extend");
+    assert_eq!(
+        Some(b"token!".to_vec()),
+        store.expose("api", |b| b.to_vec())
+    );
+
+    assert!(store.remove("api"));
+    assert!(!store.remove("api"));
+    assert!(!store.contains("api"));
+    assert_eq!(1, store.len());
+}
+
+#[test]
+fn test_store_rotate_all() {
+    let mut store = Shielded::builder()
+        .chunk_size(3)
+        .reshield_policy(ReshieldPolicy::Every(100))
+        .store();
+    store.insert("a", b"hello world".to_vec()).expect("insert");
+    store.insert("b", Vec::new()).expect("insert");
+
+    store.rotate_all().expect("rotate");
+    store.rotate_all().expect("rotate again");
+
+    assert_eq!(
+        Some(b"hello world".to_vec()),
+        store.expose("a", |b| b.to_vec())
+    );
+    assert_eq!(Some(Vec::new()), store.expose("b", |b| b.to_vec()));
+}
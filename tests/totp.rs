@@ -0,0 +1,95 @@
+#![cfg(feature = "totp")]
+
+use shielded::{ShieldError, Shielded, ShieldedBuilder, ShieldedTotp, TotpAlgorithm};
+
+// The seeds of the test vectors of RFC 6238, appendix B.
+const SEED_SHA1: &[u8] = b"12345678901234567890";
+const SEED_SHA256: &[u8] = b"12345678901234567890123456789012";
+const SEED_SHA512: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+const VECTORS: &[(u64, &str, &str, &str)] = &[
+    (59, "94287082", "46119246", "90693936"),
+    (1111111109, "07081804", "68084774", "25091201"),
+    (1111111111, "14050471", "67062674", "99943326"),
+    (1234567890, "89005924", "91819424", "93441116"),
+    (2000000000, "69279037", "90698825", "38618901"),
+    (20000000000, "65353130", "77737706", "47863826"),
+];
+
+fn totp(seed: &[u8], algorithm: TotpAlgorithm) -> ShieldedTotp {
+    ShieldedTotp::from_shielded(Shielded::new(seed.to_vec()))
+        .algorithm(algorithm)
+        .digits(8)
+}
+
+#[test]
+fn test_rfc6238_vectors() {
+    let mut sha1 = totp(SEED_SHA1, TotpAlgorithm::Sha1);
+    let mut sha256 = totp(SEED_SHA256, TotpAlgorithm::Sha256);
+    let mut sha512 = totp(SEED_SHA512, TotpAlgorithm::Sha512);
+    for &(time, code_sha1, code_sha256, code_sha512) in VECTORS {
+        assert_eq!(code_sha1, sha1.code_at(time));
+        assert_eq!(code_sha256, sha256.code_at(time));
+        assert_eq!(code_sha512, sha512.code_at(time));
+    }
+}
+
+#[test]
+fn test_defaults() {
+    let mut totp = ShieldedTotp::from_shielded(Shielded::new(SEED_SHA1.to_vec()));
+    assert_eq!("287082", totp.code_at(59));
+    assert_eq!("081804", totp.code_at(1111111109));
+    assert_eq!(totp.code_at(1111111080), totp.code_at(1111111109));
+    assert_eq!(6, totp.code().len());
+
+    let mut totp = totp.period(60);
+    assert_eq!(totp.code_at(1111111080), totp.code_at(1111111139));
+    assert_eq!(SEED_SHA1.len(), totp.into_inner().len());
+}
+
+#[test]
+fn test_from_base32() {
+    for encoded in [
+        "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ",
+        "gezd gnbv gy3t qojq gezd gnbv gy3t qojq",
+        "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\n",
+    ] {
+        let mut totp = ShieldedTotp::from_base32(encoded.to_owned()).unwrap();
+        assert_eq!("287082", totp.code_at(59));
+    }
+
+    let seed = ShieldedTotp::from_base32("MFRGGZA=".to_owned()).unwrap();
+    assert_eq!(b"abcd", seed.into_inner().unshield().as_ref());
+    let seed = ShieldedTotp::from_base32("MFRGG===".to_owned()).unwrap();
+    assert_eq!(b"abc", seed.into_inner().unshield().as_ref());
+}
+
+#[test]
+fn test_from_base32_invalid() {
+    for encoded in [
+        "", "====", "M", "MFR", "MFRGGZ", "MFRGG1", "MF=RGGZA", "MFRGGÄ",
+    ] {
+        assert_eq!(
+            ShieldError::Encoding,
+            ShieldedTotp::from_base32(encoded.to_owned()).err().unwrap(),
+            "{}",
+            encoded
+        );
+    }
+}
+
+#[test]
+fn test_build_totp() {
+    let mut totp = ShieldedBuilder::new()
+        .label("2fa")
+        .build_totp("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ".to_owned())
+        .unwrap()
+        .digits(7);
+    assert_eq!("4287082", totp.code_at(59));
+}
+
+#[test]
+#[should_panic(expected = "TOTP codes have 6 to 8 digits")]
+fn test_digits_invalid() {
+    let _ = ShieldedTotp::from_shielded(Shielded::new(SEED_SHA1.to_vec())).digits(9);
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "serde")]
+
+use serde::{Deserialize, Serialize};
+use shielded::ShieldedValue;
+use zeroize::Zeroize;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Credentials {
+    user: String,
+    password: String,
+    pin: u32,
+}
+
+impl Zeroize for Credentials {
+    fn zeroize(&mut self) {
+        self.user.zeroize();
+        self.password.zeroize();
+        self.pin.zeroize();
+    }
+}
+
+#[test]
+fn test_shielded_value_struct() {
+    let credentials = Credentials {
+        user: "root".to_string(),
+        password: "hunter2".to_string(),
+        pin: 1234,
+    };
+
+    let original = credentials.clone();
+    let mut shielded = ShieldedValue::new(credentials);
+
+    {
+        let unshielded = shielded.unshield();
+        assert_eq!(original, *unshielded);
+    }
+
+    let unshielded = shielded.try_unshield().expect("try_unshield");
+    assert_eq!("hunter2", unshielded.password);
+}
+
+#[test]
+fn test_shielded_value_bytes() {
+    let mut shielded = ShieldedValue::new(vec![1u8, 2, 3]);
+    assert_eq!(&[1, 2, 3], &shielded.unshield()[..]);
+}
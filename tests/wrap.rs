@@ -0,0 +1,66 @@
+use quickcheck::quickcheck;
+use shielded::{Cipher, Padding, ShieldError, Shielded};
+
+fn root() -> Shielded {
+    Shielded::builder()
+        .cipher(Cipher::ChaCha20Poly1305)
+        .padding(Padding::Block(64))
+        .random(32)
+        .expect("random")
+}
+
+#[test]
+fn test_wrap_unwrap() {
+    let mut root = root();
+    let wrapped = root.wrap(b"data key");
+    assert!(!wrapped.windows(8).any(|w| w == b"data key"));
+
+    let mut data_key = Shielded::builder()
+        .chunk_size(3)
+        .unwrap_into_shielded(&mut root, &wrapped)
+        .expect("unwrap");
+    assert_eq!(b"data key", data_key.unshield().as_ref());
+}
+
+#[test]
+fn test_wrap_is_export_format() {
+    let mut root = Shielded::new(b"0123456789abcdef0123456789abcdef".to_vec());
+    let wrapped = root.wrap(b"data key");
+    let mut imported =
+        Shielded::import(&wrapped, b"0123456789abcdef0123456789abcdef").expect("import");
+    assert_eq!(b"data key", imported.unshield().as_ref());
+}
+
+#[test]
+fn test_unwrap_wrong_kek() {
+    let wrapped = root().wrap(b"data key");
+    assert_eq!(
+        ShieldError::Tamper,
+        root().unwrap_into_shielded(&wrapped).err().unwrap()
+    );
+}
+
+#[test]
+fn test_unwrap_modified() {
+    let mut root = root();
+    let wrapped = root.wrap(b"data key");
+    for i in 0..wrapped.len() {
+        let mut modified = wrapped.clone();
+        modified[i] ^= 1;
+        assert!(root.unwrap_into_shielded(&modified).is_err());
+    }
+    assert_eq!(
+        ShieldError::Encoding,
+        root.unwrap_into_shielded(b"data key").err().unwrap()
+    );
+}
+
+quickcheck! {
+    fn prop_wrap_unwrap(xs: Vec<u8>) -> bool {
+        let mut root = root();
+        let wrapped = root.wrap(&xs);
+        let mut unwrapped = root.unwrap_into_shielded(&wrapped).expect("unwrap");
+        let unshielded = unwrapped.unshield();
+        xs == unshielded.as_ref()
+    }
+}